@@ -0,0 +1,75 @@
+//! `cargo bench -p cadconvert-algo --bench topology`
+//!
+//! Drawing-like input: a lattice of small rectangles (4 segments each) crossed by a
+//! few long construction lines. Each size runs both the R-tree sweep and the
+//! original all-pairs one: the former should grow roughly linearly with the
+//! segment count, the latter quadratically.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use cadconvert_algo::structs::{View2D, ViewPlane};
+use cadconvert_algo::topology::{build_topology, build_topology_all_pairs};
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::Entity2D;
+use cadconvert_core::tolerance::Tolerances;
+use common::line;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn lattice(segments: usize) -> Vec<Entity2D> {
+    let rects = segments / 4;
+    let side = (rects as f64).sqrt().ceil() as usize;
+    let mut out = Vec::with_capacity(segments + 8);
+    let mut id = 0;
+    for r in 0..rects {
+        let x = (r % side) as f64 * 10.0;
+        let y = (r / side) as f64 * 10.0;
+        let corners = [
+            Vec2::new(x, y),
+            Vec2::new(x + 6.0, y),
+            Vec2::new(x + 6.0, y + 4.0),
+            Vec2::new(x, y + 4.0),
+        ];
+        for i in 0..4 {
+            id += 1;
+            out.push(line(id, corners[i], corners[(i + 1) % 4]));
+        }
+    }
+    // A handful of long lines crossing many rectangles.
+    let extent = side as f64 * 10.0;
+    for k in 0..4 {
+        let y = 2.0 + k as f64 * extent / 4.0;
+        id += 1;
+        out.push(line(id, Vec2::new(-5.0, y), Vec2::new(extent, y)));
+    }
+    out
+}
+
+fn bench_build_topology(c: &mut Criterion) {
+    type Build = fn(&mut View2D, &Tolerances) -> cadconvert_algo::Result<()>;
+    let impls: [(&str, Build); 2] = [
+        ("rtree", build_topology),
+        ("all_pairs", build_topology_all_pairs),
+    ];
+
+    let mut group = c.benchmark_group("build_topology");
+    group.sample_size(10);
+    for segments in [1_000usize, 4_000, 16_000, 64_000] {
+        let entities = lattice(segments);
+        group.throughput(Throughput::Elements(entities.len() as u64));
+        for (name, build) in impls {
+            group.bench_with_input(BenchmarkId::new(name, segments), &entities, |b, ents| {
+                b.iter(|| {
+                    let mut view = View2D::new(ViewPlane::XY);
+                    view.raw_entities = ents.clone();
+                    build(&mut view, &Tolerances::default()).unwrap();
+                    view.edges.len()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_build_topology);
+criterion_main!(benches);
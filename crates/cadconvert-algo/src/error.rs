@@ -0,0 +1,68 @@
+use crate::progress::Stage;
+use crate::structs::ViewPlane;
+use thiserror::Error;
+
+pub type Result<T, E = ReconstructionError> = std::result::Result<T, E>;
+
+/// Failure kinds of the reconstruction pipeline.
+///
+/// Each variant carries enough context for a caller to explain the problem to the
+/// user without parsing the message.
+#[derive(Debug, Error)]
+pub enum ReconstructionError {
+    /// No geometry could be assigned to any view (neither by layer nor spatially).
+    #[error("no views found: {considered} geometric entities considered ({reason})")]
+    ViewsNotFound { considered: usize, reason: String },
+
+    /// View separation found a number of views other than the three it needs.
+    #[error("found {found} view clusters, need {expected} (top, front, side); check view spacing or put views on TOP/FRONT/SIDE layers")]
+    ViewCountMismatch { found: usize, expected: usize },
+
+    /// A view was found but none of its entities yielded a vertex.
+    #[error("{plane:?} view has no vertices ({raw_entities} raw entities)")]
+    EmptyView {
+        plane: ViewPlane,
+        raw_entities: usize,
+    },
+
+    /// Views were found but their extents disagree, so no shift aligns them.
+    #[error("could not register {plane:?} view: {reason}")]
+    RegistrationFailed { plane: ViewPlane, reason: String },
+
+    /// No triple of 2D vertices matched across the three views.
+    #[error("no candidate 3D vertices: {xy} top, {xz} front and {yz} side vertices did not match within {tolerance}")]
+    NoCandidateVertices {
+        xy: usize,
+        xz: usize,
+        yz: usize,
+        tolerance: f64,
+    },
+
+    /// The candidate wireframe does not bound a closed solid.
+    #[error("solid is not closed: {} vertices have fewer than 3 incident edges (first: {:?})", dangling_vertices.len(), dangling_vertices.first())]
+    SolidNotClosed { dangling_vertices: Vec<usize> },
+
+    /// A view outline holds an arc or curve; meshes are only built from
+    /// straight edges.
+    #[error("{plane:?} view has curved entity {entity}; only straight edges can be meshed")]
+    CurvedGeometry { plane: ViewPlane, entity: u64 },
+
+    /// No edge loop of the wireframe bounds a planar face.
+    #[error("no faces found among {edges} wireframe edges")]
+    NoFaces { edges: usize },
+
+    /// The reconstructed model contradicts the input drawing; see
+    /// [`crate::ValidationReport::ensure_valid`].
+    #[error("validation failed ({check}): {detail}")]
+    ValidationFailed { check: String, detail: String },
+
+    /// Cancelled through [`crate::ProgressHandle::cancel`].
+    #[error("reconstruction cancelled during {}", stage.label())]
+    Cancelled { stage: Stage },
+
+    #[error("invalid STEP data: {0}")]
+    Step(#[from] cadconvert_core::step::StepError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
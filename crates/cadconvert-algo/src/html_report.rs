@@ -0,0 +1,468 @@
+use crate::validate::ValidationReport;
+use cadconvert_core::dimension::{check_dimension, DimensionStatus};
+use cadconvert_core::geom::{bulge_arc, Vec2};
+use cadconvert_core::model::{Drawing2D, EntityKind, Primitive2D, TextAlign};
+use cadconvert_core::report::AnalysisReport;
+use cadconvert_core::view::{ProjectionScheme, ViewRole};
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+/// Cluster colors, cycled.
+const PALETTE: [&str; 8] = [
+    "#1f77b4", "#2ca02c", "#9467bd", "#ff7f0e", "#17becf", "#8c564b", "#bcbd22", "#e377c2",
+];
+
+const STYLE: &str = r#"
+body { font: 14px/1.4 system-ui, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; } h2 { font-size: 1.15em; margin-top: 2em; }
+table { border-collapse: collapse; } td, th { border: 1px solid #ccc; padding: 2px 8px; text-align: left; }
+.ok { color: #2a7d2a; } .bad { color: #c00; }
+svg.drawing { width: 100%; max-height: 80vh; border: 1px solid #ccc; background: #fff; }
+svg.drawing * { fill: none; stroke-width: 1.2px; vector-effect: non-scaling-stroke; }
+svg.drawing text { fill: currentColor; stroke: none; font-family: sans-serif; }
+.k-unknown, .k-object { stroke: currentColor; }
+.k-hidden { stroke-dasharray: 6 4; }
+.k-center { stroke-dasharray: 14 4 3 4; }
+.k-phantom { stroke-dasharray: 14 4 3 4 3 4; }
+.k-dimension, .k-text, .k-hatch { color: #999; stroke: currentColor; }
+.cluster { stroke: currentColor; stroke-dasharray: 3 3; }
+.role { fill: #000; font-weight: bold; }
+.dim { stroke: #c90; }
+svg.drawing :target { color: #e00; stroke: #e00; stroke-width: 4px; }
+tr:target { background: #fdd; }
+.legend span { display: inline-block; margin-right: 1.5em; }
+"#;
+
+/// Renders a self-contained HTML page for reviewers: the drawing as inline SVG,
+/// colored by view cluster and dashed by [`EntityKind`], the view roles,
+/// warnings linked to the entities they are about, a dimension health table
+/// and, with `validation`, the result of checking a reconstruction.
+pub fn render_html_report(
+    title: &str,
+    drawing: &Drawing2D,
+    report: &AnalysisReport,
+    validation: Option<&ValidationReport>,
+) -> String {
+    let mut out = String::new();
+    let title = escape(title);
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>cadconvert report: {title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>cadconvert report: {title}</h1>\n"
+    );
+    summary(&mut out, drawing, report);
+    let drawn = drawing_svg(&mut out, drawing, report);
+    warnings(&mut out, drawing, report, &drawn);
+    dimensions(&mut out, drawing);
+    if let Some(validation) = validation {
+        validation_section(&mut out, validation);
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Short decimal for SVG coordinates.
+fn num(v: f64) -> String {
+    let s = format!("{v:.4}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    match s {
+        "-0" | "" => "0".to_string(),
+        _ => s.to_string(),
+    }
+}
+
+/// SVG is y-down; drawings are y-up.
+fn pt(p: Vec2) -> String {
+    format!("{} {}", num(p.x), num(-p.y))
+}
+
+fn role_name(role: ViewRole) -> &'static str {
+    match role {
+        ViewRole::Front => "FRONT",
+        ViewRole::Top => "TOP",
+        ViewRole::Right => "RIGHT",
+    }
+}
+
+fn kind_class(kind: &EntityKind) -> &'static str {
+    match kind {
+        EntityKind::Unknown => "k-unknown",
+        EntityKind::Object => "k-object",
+        EntityKind::Hidden => "k-hidden",
+        EntityKind::Center => "k-center",
+        EntityKind::Phantom => "k-phantom",
+        EntityKind::Dimension => "k-dimension",
+        EntityKind::Text => "k-text",
+        EntityKind::Hatch => "k-hatch",
+    }
+}
+
+fn summary(out: &mut String, drawing: &Drawing2D, report: &AnalysisReport) {
+    let stats = &report.stats;
+    let views = match &report.view_assignment {
+        Some(a) => {
+            let scheme = match a.scheme {
+                ProjectionScheme::ThirdAngle => "third angle",
+                ProjectionScheme::FirstAngle => "first angle",
+            };
+            format!("{scheme}, confidence {:.2}", a.confidence)
+        }
+        None => "not assigned".to_string(),
+    };
+    let rows = [
+        ("Input format", escape(&report.input_format)),
+        ("Units", format!("{:?}", drawing.units)),
+        (
+            "Entities",
+            format!(
+                "{} ({} after normalization)",
+                stats.entities_total, stats.entities_normalized
+            ),
+        ),
+        ("Dimensions", stats.dims_total.to_string()),
+        ("Texts", stats.texts_total.to_string()),
+        ("View clusters", report.view_clusters.len().to_string()),
+        ("View roles", views),
+        (
+            "Tolerances",
+            format!(
+                "snap {}, view match {}",
+                num(report.tolerances.snap),
+                num(report.tolerances.view_match)
+            ),
+        ),
+    ];
+    out.push_str("<h2>Summary</h2>\n<table>\n");
+    for (name, value) in rows {
+        let _ = writeln!(out, "<tr><th>{name}</th><td>{value}</td></tr>");
+    }
+    out.push_str("</table>\n");
+}
+
+/// Writes the drawing and returns the ids of the elements drawn, which
+/// warnings can link to.
+fn drawing_svg(out: &mut String, drawing: &Drawing2D, report: &AnalysisReport) -> HashSet<u64> {
+    let mut drawn = HashSet::new();
+    out.push_str("<h2>Drawing</h2>\n");
+    let Some(extents) = report.extents.or_else(|| drawing.extents()) else {
+        out.push_str("<p>Nothing to draw.</p>\n");
+        return drawn;
+    };
+    let margin = (extents.diag() * 0.05).max(1.0);
+    let font = (extents.diag() * 0.015).max(0.5);
+    let view = extents.expand(margin);
+    let _ = writeln!(
+        out,
+        "<svg class=\"drawing\" xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+        num(view.min.x),
+        num(-view.max.y),
+        num(view.width()),
+        num(view.height())
+    );
+
+    for (i, cluster) in report.view_clusters.iter().enumerate() {
+        let b = cluster.bbox;
+        let _ = writeln!(
+            out,
+            "<rect class=\"cluster\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" style=\"color: {}\"/>",
+            num(b.min.x),
+            num(-b.max.y),
+            num(b.width()),
+            num(b.height()),
+            PALETTE[i % PALETTE.len()]
+        );
+    }
+
+    for e in &drawing.entities {
+        let center = e.bbox().center();
+        let color = report
+            .view_clusters
+            .iter()
+            .position(|c| c.bbox.contains(center))
+            .map(|i| format!(" style=\"color: {}\"", PALETTE[i % PALETTE.len()]))
+            .unwrap_or_default();
+        let class = kind_class(&e.kind);
+        let id = e.id;
+        let shape = match &e.primitive {
+            Primitive2D::Line(l) => format!("<path d=\"M {} L {}\"", pt(l.a), pt(l.b)),
+            Primitive2D::Circle(c) => format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"",
+                num(c.center.x),
+                num(-c.center.y),
+                num(c.radius)
+            ),
+            Primitive2D::Arc(a) => {
+                let (a0, sweep) = (a.start_angle_deg, a.sweep_deg());
+                // Counter-clockwise in the drawing is sweep flag 0 once y is flipped.
+                let mut d = format!("M {}", pt(a.start_point()));
+                if sweep >= 359.999 {
+                    let half = pt(a.point_at(a0 + 180.0));
+                    let _ = write!(d, " A {0} {0} 0 1 0 {half}", num(a.radius));
+                }
+                let large = u8::from(sweep > 180.0 && sweep < 359.999);
+                let end = pt(a.end_point());
+                let _ = write!(d, " A {0} {0} 0 {large} 0 {end}", num(a.radius));
+                format!("<path d=\"{d}\"")
+            }
+            Primitive2D::Polyline(p) => {
+                let mut d = String::new();
+                let n = p.vertices.len();
+                let segments = if p.closed { n } else { n.saturating_sub(1) };
+                if let Some(first) = p.vertices.first() {
+                    d = format!("M {}", pt(first.pos));
+                }
+                for i in 0..segments {
+                    let (v0, v1) = (&p.vertices[i], &p.vertices[(i + 1) % n]);
+                    let Some((_, r, _, theta)) = bulge_arc(v0.pos, v1.pos, v0.bulge) else {
+                        let _ = write!(d, " L {}", pt(v1.pos));
+                        continue;
+                    };
+                    let large = u8::from(theta.abs() > std::f64::consts::PI);
+                    let sweep = u8::from(v0.bulge < 0.0);
+                    let _ = write!(d, " A {0} {0} 0 {large} {sweep} {1}", num(r), pt(v1.pos));
+                }
+                if p.closed {
+                    d.push_str(" Z");
+                }
+                format!("<path d=\"{d}\"")
+            }
+            Primitive2D::CubicBezier(b) => format!(
+                "<path d=\"M {} C {} {} {}\"",
+                pt(b.p0),
+                pt(b.p1),
+                pt(b.p2),
+                pt(b.p3)
+            ),
+            Primitive2D::Ellipse(el) => {
+                let rx = el.major_axis.length();
+                let radii = format!(
+                    "{} {} {}",
+                    num(rx),
+                    num(rx * el.ratio),
+                    num(-el.major_axis.angle().to_degrees())
+                );
+                let (t0, sweep) = (el.start_param_deg, el.sweep_deg());
+                let mut d = format!("M {}", pt(el.start_point()));
+                if el.is_closed() {
+                    let _ = write!(d, " A {radii} 1 0 {}", pt(el.point_at(t0 + 180.0)));
+                }
+                let large = u8::from(sweep > 180.0 && !el.is_closed());
+                let _ = write!(d, " A {radii} {large} 0 {}", pt(el.end_point()));
+                format!("<path d=\"{d}\"")
+            }
+            Primitive2D::Nurbs(n) => {
+                let mut d = String::new();
+                for (i, p) in n.tessellate(font * 0.02).into_iter().enumerate() {
+                    let _ = write!(d, "{}{}", if i == 0 { "M " } else { " L " }, pt(p));
+                }
+                format!("<path d=\"{d}\"")
+            }
+            Primitive2D::Point(p) => format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"",
+                num(p.x),
+                num(-p.y),
+                num(font * 0.2)
+            ),
+        };
+        let _ = writeln!(out, "{shape} id=\"e{id}\" class=\"{class}\"{color}/>");
+        drawn.insert(id);
+    }
+
+    for t in &drawing.texts {
+        let size = t.height.filter(|h| *h > 0.0).unwrap_or(font);
+        let anchor = match t.align {
+            TextAlign::Left => "start",
+            TextAlign::Center => "middle",
+            TextAlign::Right => "end",
+        };
+        let (x, y) = (num(t.at.x), num(-t.at.y));
+        let rotate = if t.rotation_deg != 0.0 {
+            format!(" transform=\"rotate({} {x} {y})\"", num(-t.rotation_deg))
+        } else {
+            String::new()
+        };
+        let _ = writeln!(
+            out,
+            "<text id=\"e{}\" class=\"k-text\" x=\"{x}\" y=\"{y}\" font-size=\"{}\" text-anchor=\"{anchor}\"{rotate}>{}</text>",
+            t.id,
+            num(size),
+            escape(&t.text)
+        );
+        drawn.insert(t.id);
+    }
+
+    for d in &drawing.dims {
+        let Some(at) = d.at else {
+            continue;
+        };
+        let _ = writeln!(
+            out,
+            "<circle id=\"e{}\" class=\"dim\" cx=\"{}\" cy=\"{}\" r=\"{}\"><title>dimension {}: {}</title></circle>",
+            d.id,
+            num(at.x),
+            num(-at.y),
+            num(font * 0.6),
+            d.id,
+            escape(d.text.as_deref().unwrap_or(""))
+        );
+        drawn.insert(d.id);
+    }
+
+    if let Some(assignment) = &report.view_assignment {
+        for role in &assignment.roles {
+            let Some(cluster) = report
+                .view_clusters
+                .iter()
+                .find(|c| c.id == role.cluster_id)
+            else {
+                continue;
+            };
+            let _ = writeln!(
+                out,
+                "<text class=\"role\" x=\"{}\" y=\"{}\" font-size=\"{}\">{}</text>",
+                num(cluster.bbox.min.x),
+                num(-cluster.bbox.max.y - 0.5 * font),
+                num(font * 1.5),
+                role_name(role.role)
+            );
+        }
+    }
+    out.push_str("</svg>\n<p class=\"legend\">");
+    for (i, cluster) in report.view_clusters.iter().enumerate() {
+        let _ = write!(
+            out,
+            "<span style=\"color: {}\">&#9632; cluster {} ({} entities)</span>",
+            PALETTE[i % PALETTE.len()],
+            cluster.id,
+            cluster.entity_count
+        );
+    }
+    out.push_str(
+        "<span>solid: object</span><span>dashed: hidden</span><span>dash-dot: center</span>\
+         <span>dash-dot-dot: phantom</span></p>\n",
+    );
+    drawn
+}
+
+fn warnings(out: &mut String, drawing: &Drawing2D, report: &AnalysisReport, drawn: &HashSet<u64>) {
+    out.push_str("<h2>Warnings</h2>\n");
+    if report.warnings.is_empty() {
+        out.push_str("<p class=\"ok\">None.</p>\n");
+        return;
+    }
+    out.push_str("<ul>\n");
+    for w in &report.warnings {
+        let _ = write!(
+            out,
+            "<li><code>{}</code> {}",
+            escape(&w.code),
+            escape(&w.message)
+        );
+        if !w.entity_ids.is_empty() {
+            out.push_str(" &mdash;");
+            for id in &w.entity_ids {
+                // Dimensions without a position only have a table row, and
+                // anything else that was not drawn has nothing to link to.
+                if drawn.contains(id) {
+                    let _ = write!(out, " <a href=\"#e{id}\">#{id}</a>");
+                } else if drawing.dims.iter().any(|d| d.id == *id) {
+                    let _ = write!(out, " <a href=\"#dim-{id}\">#{id}</a>");
+                } else {
+                    let _ = write!(out, " #{id}");
+                }
+            }
+        }
+        out.push_str("</li>\n");
+    }
+    out.push_str("</ul>\n");
+}
+
+fn dimensions(out: &mut String, drawing: &Drawing2D) {
+    out.push_str("<h2>Dimensions</h2>\n");
+    if drawing.dims.is_empty() {
+        out.push_str("<p>None.</p>\n");
+        return;
+    }
+    out.push_str(
+        "<table>\n<tr><th>Id</th><th>Layer</th><th>Text</th><th>Measurement</th><th>Status</th></tr>\n",
+    );
+    for d in &drawing.dims {
+        let status = match check_dimension(d) {
+            DimensionStatus::Ok => "<span class=\"ok\">ok</span>".to_string(),
+            DimensionStatus::Unresolved => "<span class=\"bad\">no value</span>".to_string(),
+            DimensionStatus::Conflict { measured } => {
+                format!(
+                    "<span class=\"bad\">text disagrees with {}</span>",
+                    num(measured)
+                )
+            }
+        };
+        let _ = writeln!(
+            out,
+            "<tr id=\"dim-{0}\"><td>{0}</td><td>{1}</td><td>{2}</td><td>{3}</td><td>{4}</td></tr>",
+            d.id,
+            escape(d.style.layer.as_deref().unwrap_or("")),
+            escape(d.text.as_deref().unwrap_or("")),
+            d.measurement.map(num).unwrap_or_default(),
+            status
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn validation_section(out: &mut String, validation: &ValidationReport) {
+    let verdict = if !validation.is_checked() {
+        "<span class=\"bad\">has no vertices and edges to check</span>"
+    } else if validation.is_valid() {
+        "<span class=\"ok\">matches the drawing</span>"
+    } else {
+        "<span class=\"bad\">does not match the drawing</span>"
+    };
+    let _ = write!(
+        out,
+        "<h2>Reconstruction validation</h2>\n<p>The STEP model {verdict}.</p>\n<table>\n\
+         <tr><th></th><th>Matched</th><th>Total</th></tr>\n\
+         <tr><th>Vertices</th><td>{}</td><td>{}</td></tr>\n\
+         <tr><th>Edges</th><td>{}</td><td>{}</td></tr>\n</table>\n",
+        validation.vertices - validation.unmatched_vertices.len(),
+        validation.vertices,
+        validation.edges - validation.unmatched_edges.len(),
+        validation.edges
+    );
+    let point = |p: &[f64; 3]| format!("({}, {}, {})", num(p[0]), num(p[1]), num(p[2]));
+    if !validation.unmatched_vertices.is_empty() {
+        out.push_str("<h3>Unmatched vertices</h3>\n<ul>\n");
+        for p in &validation.unmatched_vertices {
+            let _ = writeln!(out, "<li>{}</li>", point(p));
+        }
+        out.push_str("</ul>\n");
+    }
+    if !validation.unmatched_edges.is_empty() {
+        out.push_str("<h3>Unmatched edges</h3>\n<ul>\n");
+        for e in &validation.unmatched_edges {
+            let _ = writeln!(
+                out,
+                "<li>{} {} &ndash; {}</li>",
+                e.id,
+                point(&e.start),
+                point(&e.end)
+            );
+        }
+        out.push_str("</ul>\n");
+    }
+}
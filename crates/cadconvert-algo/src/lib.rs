@@ -1,13 +1,37 @@
-use anyhow::{Result, bail};
-use cadconvert_core::model::Drawing2D;
+use cadconvert_core::model::{Drawing2D, EntityKind, Primitive2D};
+use cadconvert_core::tolerance::Tolerances;
 
+pub mod error;
+pub mod progress;
 pub mod structs;
 pub mod view_separation;
 pub mod topology;
 pub mod reconstruction;
 pub mod solid_builder;
+pub mod mesh;
+pub mod mesh_writer;
 pub mod step_writer;
+pub mod validate;
+pub mod html_report;
 
+pub use error::{ReconstructionError, Result};
+pub use html_report::render_html_report;
+pub use mesh::Mesh;
+pub use mesh_writer::MeshFormat;
+pub use progress::{ProgressHandle, Stage};
+pub use validate::{validate_step, ValidationReport};
+pub use cadconvert_core::step::{Schema, StepOptions};
+
+use structs::View2D;
+
+#[derive(Debug, Clone, Default)]
+pub struct ReconstructionConfig {
+    /// In drawing units; see [`Tolerances::for_drawing`].
+    pub tolerances: Tolerances,
+    pub step: StepOptions,
+}
+
+#[derive(Debug, Clone)]
 pub struct StepModel {
     pub content: String,
 }
@@ -19,22 +43,127 @@ impl StepModel {
     }
 }
 
-pub fn reconstruct_solid(drawing: &Drawing2D) -> Result<StepModel> {
-    // 1. Separate views
-    let (mut v_xy, mut v_xz, mut v_yz) = view_separation::separate_views(drawing)?;
+/// Reconstructs a wireframe from the three orthographic views in `drawing`.
+#[tracing::instrument(
+    skip_all,
+    fields(
+        entities = drawing.entities.len(),
+        snap = cfg.tolerances.snap,
+        view_match = cfg.tolerances.view_match
+    )
+)]
+pub fn reconstruct_solid(
+    drawing: &Drawing2D,
+    cfg: &ReconstructionConfig,
+    progress: &ProgressHandle,
+) -> Result<StepModel> {
+    let (lambda, theta) = build_wireframe(drawing, cfg, progress)?;
+
+    // 4. Faces are only built for mesh output (see `reconstruct_mesh`).
 
-    // 2. Build 2D Topology
-    topology::build_topology(&mut v_xy)?;
-    topology::build_topology(&mut v_xz)?;
-    topology::build_topology(&mut v_yz)?;
-    
-    // 3. Build 3D Lambda/Theta
-    let (lambda, theta) = reconstruction::build_reconstruction(&v_xy, &v_xz, &v_yz)?;
-    
-    // 4. Generate Solid (TODO)
-    
     // Generate STEP content
-    let step_content = step_writer::write_step(&lambda, &theta)?;
+    progress.checkpoint(Stage::StepExport)?;
+    let step_content =
+        step_writer::write_step(&lambda, &theta, drawing.units, &cfg.tolerances, &cfg.step)?;
+    progress.report(Stage::StepExport, 1.0);
 
     Ok(StepModel { content: step_content })
 }
+
+/// Reconstructs the solid bounded by the wireframe and triangulates its faces.
+///
+/// Fails with [`ReconstructionError::CurvedGeometry`] if a view outline holds
+/// an arc or curve, since only faces bounded by straight edges are built.
+#[tracing::instrument(
+    skip_all,
+    fields(
+        entities = drawing.entities.len(),
+        snap = cfg.tolerances.snap,
+        view_match = cfg.tolerances.view_match
+    )
+)]
+pub fn reconstruct_mesh(
+    drawing: &Drawing2D,
+    cfg: &ReconstructionConfig,
+    progress: &ProgressHandle,
+) -> Result<Mesh> {
+    let views = separate_views(drawing, progress)?;
+    for view in &views {
+        ensure_straight(view)?;
+    }
+    let (lambda, theta) = wireframe_from_views(views, cfg, progress)?;
+
+    // 4. Generate Solid
+    progress.checkpoint(Stage::Solid)?;
+    let solid = solid_builder::build_solid(&lambda, &theta, &cfg.tolerances)?;
+    progress.report(Stage::Solid, 1.0);
+
+    // 5. Triangulate
+    progress.checkpoint(Stage::Mesh)?;
+    let mesh = mesh::triangulate(&solid, drawing.units);
+    progress.report(Stage::Mesh, 1.0);
+
+    Ok(mesh)
+}
+
+/// Steps 1-3: views, 2D topology, then candidate 3D vertices and edges.
+fn build_wireframe(
+    drawing: &Drawing2D,
+    cfg: &ReconstructionConfig,
+    progress: &ProgressHandle,
+) -> Result<(Vec<structs::LambdaRow>, Vec<structs::ThetaEdge>)> {
+    let views = separate_views(drawing, progress)?;
+    wireframe_from_views(views, cfg, progress)
+}
+
+/// Step 1: the top, front and side views.
+fn separate_views(drawing: &Drawing2D, progress: &ProgressHandle) -> Result<[View2D; 3]> {
+    progress.checkpoint(Stage::ViewSeparation)?;
+    progress.report(Stage::ViewSeparation, 0.0);
+    let (v_xy, v_xz, v_yz) = view_separation::separate_views(drawing)?;
+    progress.report(Stage::ViewSeparation, 1.0);
+    Ok([v_xy, v_xz, v_yz])
+}
+
+/// Rejects outline entities that topology would drop: arcs, circles, curves and
+/// bulged polylines. Center lines, phantom lines and hatches bound no face.
+fn ensure_straight(view: &View2D) -> Result<()> {
+    let curved = view.raw_entities.iter().find(|e| {
+        let outline = !matches!(
+            e.kind,
+            EntityKind::Center | EntityKind::Phantom | EntityKind::Hatch
+        );
+        let straight = match &e.primitive {
+            Primitive2D::Line(_) | Primitive2D::Point(_) => true,
+            Primitive2D::Polyline(poly) => poly.vertices.iter().all(|v| v.bulge == 0.0),
+            _ => false,
+        };
+        outline && !straight
+    });
+    match curved {
+        Some(entity) => Err(ReconstructionError::CurvedGeometry {
+            plane: view.plane,
+            entity: entity.id,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Steps 2-3 on separated views.
+fn wireframe_from_views(
+    [mut v_xy, mut v_xz, mut v_yz]: [View2D; 3],
+    cfg: &ReconstructionConfig,
+    progress: &ProgressHandle,
+) -> Result<(Vec<structs::LambdaRow>, Vec<structs::ThetaEdge>)> {
+    let tolerances = &cfg.tolerances;
+
+    // 2. Build 2D Topology
+    for (i, view) in [&mut v_xy, &mut v_xz, &mut v_yz].into_iter().enumerate() {
+        progress.checkpoint(Stage::Topology)?;
+        topology::build_topology(view, tolerances)?;
+        progress.report(Stage::Topology, (i + 1) as f32 / 3.0);
+    }
+
+    // 3. Build 3D Lambda/Theta
+    reconstruction::build_reconstruction(&v_xy, &v_xz, &v_yz, tolerances, progress)
+}
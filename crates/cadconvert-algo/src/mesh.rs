@@ -0,0 +1,195 @@
+use crate::solid_builder::{plane_basis, Solid, SolidFace};
+use cadconvert_core::model::Units;
+use nalgebra::Point2;
+
+/// A triangle mesh with flat-shaded faces.
+///
+/// Vertices are not shared between faces, so each carries its face's normal.
+/// Triangles wind counter-clockwise seen from outside. Positions are in drawing
+/// `units`.
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    pub units: Units,
+    pub positions: Vec<[f64; 3]>,
+    pub normals: Vec<[f64; 3]>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    /// Volume enclosed by the mesh; negative if it is inside out.
+    pub fn volume(&self) -> f64 {
+        self.triangles
+            .iter()
+            .map(|t| {
+                let [a, b, c] = t.map(|i| nalgebra::Vector3::from(self.positions[i as usize]));
+                a.dot(&b.cross(&c)) / 6.0
+            })
+            .sum()
+    }
+}
+
+/// Triangulates every face of `solid` by ear clipping, bridging holes into the
+/// outer loop first.
+pub fn triangulate(solid: &Solid, units: Units) -> Mesh {
+    let mut mesh = Mesh {
+        units,
+        positions: Vec::new(),
+        normals: Vec::new(),
+        triangles: Vec::new(),
+    };
+    for face in &solid.faces {
+        triangulate_face(solid, face, &mut mesh);
+    }
+    mesh
+}
+
+fn triangulate_face(solid: &Solid, face: &SolidFace, mesh: &mut Mesh) {
+    let (u, w) = plane_basis(&face.normal);
+    let base = mesh.positions.len() as u32;
+    let mut points = Vec::new();
+    let mut loops = Vec::new();
+    // `+ 0.0` keeps -0 (from flipped faces) out of the text formats.
+    let normal = face.normal.map(|c| c + 0.0);
+    for l in std::iter::once(&face.outer).chain(&face.holes) {
+        let start = points.len();
+        for &v in l {
+            let p = solid.vertices[v];
+            points.push(Point2::new(u.dot(&p.coords), w.dot(&p.coords)));
+            mesh.positions.push([p.x, p.y, p.z]);
+            mesh.normals.push([normal.x, normal.y, normal.z]);
+        }
+        loops.push((start..points.len()).collect::<Vec<usize>>());
+    }
+
+    let outer = loops.remove(0);
+    let ring = bridge_holes(&points, outer, loops);
+    for [a, b, c] in ear_clip(&points, ring) {
+        mesh.triangles
+            .push([base + a as u32, base + b as u32, base + c as u32]);
+    }
+}
+
+fn cross(o: Point2<f64>, a: Point2<f64>, b: Point2<f64>) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+/// Whether segments `ab` and `cd` cross at a point interior to both.
+fn crosses(a: Point2<f64>, b: Point2<f64>, c: Point2<f64>, d: Point2<f64>) -> bool {
+    let (d1, d2) = (cross(a, b, c), cross(a, b, d));
+    let (d3, d4) = (cross(c, d, a), cross(c, d, b));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+/// Splices each (clockwise) hole into the (counter-clockwise) outer ring through
+/// a bridge edge that crosses no other edge, rightmost hole first.
+fn bridge_holes(
+    points: &[Point2<f64>],
+    mut ring: Vec<usize>,
+    mut holes: Vec<Vec<usize>>,
+) -> Vec<usize> {
+    let rightmost = |hole: &Vec<usize>| {
+        hole.iter()
+            .copied()
+            .max_by(|&a, &b| points[a].x.total_cmp(&points[b].x))
+            .unwrap_or(0)
+    };
+    holes.sort_by(|a, b| points[rightmost(b)].x.total_cmp(&points[rightmost(a)].x));
+
+    for (h, hole) in holes.iter().enumerate() {
+        if hole.is_empty() {
+            continue;
+        }
+        let m = rightmost(hole);
+        let pm = points[m];
+        let mut candidates: Vec<usize> = (0..ring.len()).collect();
+        candidates.sort_by(|&a, &b| {
+            (points[ring[a]] - pm)
+                .norm_squared()
+                .total_cmp(&(points[ring[b]] - pm).norm_squared())
+        });
+        let blocked = |p: Point2<f64>| {
+            let edges = |l: &[usize]| {
+                (0..l.len())
+                    .map(|i| (points[l[i]], points[l[(i + 1) % l.len()]]))
+                    .collect::<Vec<_>>()
+            };
+            std::iter::once(edges(&ring))
+                .chain(holes[h..].iter().map(|l| edges(l)))
+                .flatten()
+                .any(|(a, b)| crosses(pm, p, a, b))
+        };
+        let at = candidates
+            .iter()
+            .copied()
+            .find(|&i| !blocked(points[ring[i]]))
+            .unwrap_or(candidates[0]);
+
+        let start = hole.iter().position(|&v| v == m).unwrap_or(0);
+        let mut spliced = Vec::with_capacity(hole.len() + 2);
+        spliced.extend(hole[start..].iter().chain(&hole[..start]));
+        spliced.push(m);
+        spliced.push(ring[at]);
+        ring.splice(at + 1..at + 1, spliced);
+    }
+    ring
+}
+
+fn inside_triangle(p: Point2<f64>, a: Point2<f64>, b: Point2<f64>, c: Point2<f64>) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+/// Ear clipping of a counter-clockwise ring, which may touch itself at bridge
+/// vertices. Returns triangles as indices into `points`.
+fn ear_clip(points: &[Point2<f64>], mut ring: Vec<usize>) -> Vec<[usize; 3]> {
+    let (min, max) = ring.iter().fold(
+        (
+            Point2::new(f64::MAX, f64::MAX),
+            Point2::new(f64::MIN, f64::MIN),
+        ),
+        |(lo, hi), &i| (lo.inf(&points[i]), hi.sup(&points[i])),
+    );
+    let eps = 1e-12 * (max - min).norm_squared();
+
+    let mut triangles = Vec::with_capacity(ring.len().saturating_sub(2));
+    while ring.len() > 3 {
+        let n = ring.len();
+        let corner = |i: usize| (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = corner(i);
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            cross(pa, pb, pc) > eps
+                && ring.iter().all(|&j| {
+                    let pj = points[j];
+                    pj == pa || pj == pb || pj == pc || !inside_triangle(pj, pa, pb, pc)
+                })
+        });
+        match ear {
+            Some(i) => {
+                let (a, b, c) = corner(i);
+                triangles.push([a, b, c]);
+                ring.remove(i);
+            }
+            None => {
+                // Degenerate ring: drop a collinear vertex if there is one (it
+                // encloses no area), otherwise clip anyway to make progress.
+                let i = (0..n)
+                    .find(|&i| {
+                        let (a, b, c) = corner(i);
+                        cross(points[a], points[b], points[c]).abs() <= eps
+                    })
+                    .unwrap_or_else(|| {
+                        let (a, b, c) = corner(0);
+                        triangles.push([a, b, c]);
+                        0
+                    });
+                ring.remove(i);
+            }
+        }
+    }
+    if let [a, b, c] = ring[..] {
+        if cross(points[a], points[b], points[c]) > eps {
+            triangles.push([a, b, c]);
+        }
+    }
+    triangles
+}
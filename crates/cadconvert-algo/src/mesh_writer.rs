@@ -0,0 +1,230 @@
+use crate::mesh::Mesh;
+use std::fmt::Write as _;
+
+/// Mesh file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshFormat {
+    StlBinary,
+    StlAscii,
+    Obj,
+    /// Binary glTF 2.0.
+    Glb,
+}
+
+impl MeshFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            MeshFormat::StlBinary | MeshFormat::StlAscii => "stl",
+            MeshFormat::Obj => "obj",
+            MeshFormat::Glb => "glb",
+        }
+    }
+
+    pub fn write(self, mesh: &Mesh, name: &str) -> Vec<u8> {
+        match self {
+            MeshFormat::StlBinary => write_stl_binary(mesh, name),
+            MeshFormat::StlAscii => write_stl_ascii(mesh, name).into_bytes(),
+            MeshFormat::Obj => write_obj(mesh, name).into_bytes(),
+            MeshFormat::Glb => write_glb(mesh, name),
+        }
+    }
+}
+
+/// STL and OBJ names are single tokens.
+fn token(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect();
+    if name.is_empty() {
+        "cadconvert".to_string()
+    } else {
+        name
+    }
+}
+
+fn triangle_normal(mesh: &Mesh, t: &[u32; 3]) -> [f64; 3] {
+    mesh.normals[t[0] as usize]
+}
+
+/// Binary STL, in drawing units. The 80-byte header never starts with `solid`, so
+/// readers do not mistake the file for ASCII.
+pub fn write_stl_binary(mesh: &Mesh, name: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(84 + 50 * mesh.triangles.len());
+    let mut header = format!("cadconvert binary STL: {}", token(name)).into_bytes();
+    header.resize(80, b' ');
+    out.extend_from_slice(&header);
+    out.extend_from_slice(&(mesh.triangles.len() as u32).to_le_bytes());
+    for t in &mesh.triangles {
+        let normal = triangle_normal(mesh, t);
+        let corners = t.map(|i| mesh.positions[i as usize]);
+        for v in std::iter::once(&normal).chain(&corners) {
+            for c in v {
+                out.extend_from_slice(&(*c as f32).to_le_bytes());
+            }
+        }
+        out.extend_from_slice(&0u16.to_le_bytes());
+    }
+    out
+}
+
+/// ASCII STL, in drawing units.
+pub fn write_stl_ascii(mesh: &Mesh, name: &str) -> String {
+    let name = token(name);
+    let mut out = format!("solid {name}\n");
+    for t in &mesh.triangles {
+        let [nx, ny, nz] = triangle_normal(mesh, t);
+        let _ = writeln!(out, "  facet normal {nx} {ny} {nz}");
+        out.push_str("    outer loop\n");
+        for &i in t {
+            let [x, y, z] = mesh.positions[i as usize];
+            let _ = writeln!(out, "      vertex {x} {y} {z}");
+        }
+        out.push_str("    endloop\n  endfacet\n");
+    }
+    let _ = writeln!(out, "endsolid {name}");
+    out
+}
+
+/// Wavefront OBJ with per-vertex normals, in drawing units.
+pub fn write_obj(mesh: &Mesh, name: &str) -> String {
+    let mut out = format!(
+        "# cadconvert {} ({:?})\no {}\n",
+        env!("CARGO_PKG_VERSION"),
+        mesh.units,
+        token(name)
+    );
+    for [x, y, z] in &mesh.positions {
+        let _ = writeln!(out, "v {x} {y} {z}");
+    }
+    for [x, y, z] in &mesh.normals {
+        let _ = writeln!(out, "vn {x} {y} {z}");
+    }
+    for t in &mesh.triangles {
+        let [a, b, c] = t.map(|i| i + 1);
+        let _ = writeln!(out, "f {a}//{a} {b}//{b} {c}//{c}");
+    }
+    out
+}
+
+/// Binary glTF 2.0 with one mesh of flat-shaded triangles.
+///
+/// glTF is Y-up and in metres: `(x, y, z)` is written as `(x, z, -y)` and scaled
+/// from drawing units (unknown units are taken as millimetres).
+pub fn write_glb(mesh: &Mesh, name: &str) -> Vec<u8> {
+    let scale = mesh.units.millimeters_per_unit().unwrap_or(1.0) / 1000.0;
+    let y_up = |[x, y, z]: [f64; 3]| [x, z, -y];
+
+    let mut bin = Vec::new();
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for p in &mesh.positions {
+        for (i, c) in y_up(*p).into_iter().enumerate() {
+            // `+ 0.0` turns -0 (from negating y) into 0.
+            let c = (c * scale) as f32 + 0.0;
+            min[i] = min[i].min(c);
+            max[i] = max[i].max(c);
+            bin.extend_from_slice(&c.to_le_bytes());
+        }
+    }
+    let positions_len = bin.len();
+    for n in &mesh.normals {
+        for c in y_up(*n) {
+            bin.extend_from_slice(&(c as f32).to_le_bytes());
+        }
+    }
+    let normals_len = bin.len() - positions_len;
+    for i in mesh.triangles.iter().flatten() {
+        bin.extend_from_slice(&i.to_le_bytes());
+    }
+    let indices_len = bin.len() - positions_len - normals_len;
+    if mesh.positions.is_empty() {
+        (min, max) = ([0.0; 3], [0.0; 3]);
+    }
+
+    let array_buffer = 34962;
+    let element_array_buffer = 34963;
+    let (float, unsigned_int) = (5126, 5125);
+    let mut json = serde_json::to_vec(&serde_json::json!({
+        "asset": {
+            "version": "2.0",
+            "generator": concat!("cadconvert ", env!("CARGO_PKG_VERSION")),
+        },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0, "name": name }],
+        "meshes": [{
+            "name": name,
+            "primitives": [{
+                "attributes": { "POSITION": 0, "NORMAL": 1 },
+                "indices": 2,
+                "mode": 4,
+            }],
+        }],
+        "buffers": [{ "byteLength": bin.len() }],
+        "bufferViews": [
+            {
+                "buffer": 0,
+                "byteOffset": 0,
+                "byteLength": positions_len,
+                "target": array_buffer,
+            },
+            {
+                "buffer": 0,
+                "byteOffset": positions_len,
+                "byteLength": normals_len,
+                "target": array_buffer,
+            },
+            {
+                "buffer": 0,
+                "byteOffset": positions_len + normals_len,
+                "byteLength": indices_len,
+                "target": element_array_buffer,
+            },
+        ],
+        "accessors": [
+            {
+                "bufferView": 0,
+                "componentType": float,
+                "count": mesh.positions.len(),
+                "type": "VEC3",
+                "min": min,
+                "max": max,
+            },
+            {
+                "bufferView": 1,
+                "componentType": float,
+                "count": mesh.positions.len(),
+                "type": "VEC3",
+            },
+            {
+                "bufferView": 2,
+                "componentType": unsigned_int,
+                "count": mesh.triangles.len() * 3,
+                "type": "SCALAR",
+            },
+        ],
+    }))
+    .expect("glTF JSON serializes");
+    // Chunks are 4-byte aligned: JSON padded with spaces, binary with zeros.
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    while !bin.len().is_multiple_of(4) {
+        bin.push(0);
+    }
+
+    let total = 12 + 8 + json.len() + 8 + bin.len();
+    let mut out = Vec::with_capacity(total);
+    out.extend_from_slice(b"glTF");
+    out.extend_from_slice(&2u32.to_le_bytes());
+    out.extend_from_slice(&(total as u32).to_le_bytes());
+    out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    out.extend_from_slice(b"JSON");
+    out.extend_from_slice(&json);
+    out.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    out.extend_from_slice(b"BIN\0");
+    out.extend_from_slice(&bin);
+    out
+}
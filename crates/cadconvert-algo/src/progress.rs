@@ -0,0 +1,90 @@
+use crate::error::{ReconstructionError, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Pipeline stage reported through [`ProgressHandle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    ViewSeparation,
+    Topology,
+    Lambda,
+    Theta,
+    Solid,
+    Mesh,
+    StepExport,
+}
+
+impl Stage {
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::ViewSeparation => "view separation",
+            Stage::Topology => "topology",
+            Stage::Lambda => "lambda (3D vertices)",
+            Stage::Theta => "theta (3D edges)",
+            Stage::Solid => "solid (faces)",
+            Stage::Mesh => "triangulation",
+            Stage::StepExport => "STEP export",
+        }
+    }
+}
+
+type ProgressCallback = dyn Fn(Stage, f32) + Send + Sync;
+
+/// Progress reporting and cooperative cancellation for `reconstruct_solid`.
+///
+/// Cheap to clone: clones share the same cancellation flag and callback, so a UI
+/// can keep one copy to call [`ProgressHandle::cancel`] while the pipeline runs
+/// on a worker thread with another.
+#[derive(Clone, Default)]
+pub struct ProgressHandle {
+    cancelled: Arc<AtomicBool>,
+    callback: Option<Arc<ProgressCallback>>,
+}
+
+impl ProgressHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `callback` receives the current stage and the fraction of that stage
+    /// completed, in `[0, 1]`.
+    pub fn with_callback(callback: impl Fn(Stage, f32) + Send + Sync + 'static) -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            callback: Some(Arc::new(callback)),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn report(&self, stage: Stage, fraction: f32) {
+        if let Some(cb) = &self.callback {
+            cb(stage, fraction.clamp(0.0, 1.0));
+        }
+    }
+
+    /// Returns [`ReconstructionError::Cancelled`] if cancellation was requested;
+    /// call between units of work.
+    pub(crate) fn checkpoint(&self, stage: Stage) -> Result<()> {
+        if self.is_cancelled() {
+            tracing::info!(stage = stage.label(), "reconstruction cancelled");
+            return Err(ReconstructionError::Cancelled { stage });
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for ProgressHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressHandle")
+            .field("cancelled", &self.is_cancelled())
+            .field("has_callback", &self.callback.is_some())
+            .finish()
+    }
+}
@@ -1,18 +1,96 @@
+use crate::error::{ReconstructionError, Result};
+use crate::progress::{ProgressHandle, Stage};
 use crate::structs::{LambdaRow, ThetaEdge, Vertex2D, View2D};
-use anyhow::Result;
+use cadconvert_core::tolerance::Tolerances;
 use nalgebra::{Point2, Point3, Vector2};
-use std::cmp::Ordering;
+use rayon::prelude::*;
 use std::collections::HashSet;
 
-const EPSILON: f64 = 1e-4;
-const MATCH_TOLERANCE: f64 = 1.0;
+/// How many outer-loop iterations run between progress reports / cancellation checks.
+const PROGRESS_STRIDE: usize = 256;
+/// Lambda rows handed to the thread pool between Theta progress reports / cancellation checks.
+const THETA_CHUNK: usize = 4096;
 
+#[tracing::instrument(
+    skip_all,
+    fields(xy = v_xy.vertices.len(), xz = v_xz.vertices.len(), yz = v_yz.vertices.len())
+)]
 pub fn build_reconstruction(
     v_xy: &View2D,
     v_xz: &View2D,
     v_yz: &View2D,
-) -> Result<(Vec<LambdaRow>, HashSet<ThetaEdge>)> {
-    // 1. Align Views (Centroid heuristic)
+    tolerances: &Tolerances,
+    progress: &ProgressHandle,
+) -> Result<(Vec<LambdaRow>, Vec<ThetaEdge>)> {
+    // 1. Align Views
+    let (shift_xy, shift_yz) = align_views(v_xy, v_xz, v_yz, tolerances.view_match)?;
+
+    // 2. Build Lambda (Candidate 3D Vertices) - Optimized with sorting
+    let lambda = build_lambda_optimized(
+        v_xy,
+        v_xz,
+        v_yz,
+        shift_xy,
+        shift_yz,
+        tolerances.view_match,
+        progress,
+    )?;
+    tracing::info!(count = lambda.len(), "built lambda candidates");
+    if lambda.is_empty() {
+        return Err(ReconstructionError::NoCandidateVertices {
+            xy: v_xy.vertices.len(),
+            xz: v_xz.vertices.len(),
+            yz: v_yz.vertices.len(),
+            tolerance: tolerances.view_match,
+        });
+    }
+
+    // 3. Build Theta (Candidate 3D Edges) - Optimized with hashing
+    let theta = build_theta_optimized(&lambda, v_xy, v_xz, v_yz, progress)?;
+    tracing::info!(count = theta.len(), "built theta edges");
+
+    Ok((lambda, theta))
+}
+
+/// Offsets that move the top (XY) and side (YZ) views' raw coordinates into the
+/// front view's frame, where a 3D point `p` projects to `(p.x, p.z)` in the front
+/// view, `(p.x, p.y) - shift_xy` in the top view and `(p.y, p.z) - shift_yz` in
+/// the side view.
+///
+/// Views of one solid share their extents: the top and front views are as wide
+/// along X, the front and side views as tall along Z, the top and side views as
+/// deep along Y. If any pair differs by more than `tolerance`, no shift lines
+/// the views up and registration fails.
+pub(crate) fn align_views(
+    v_xy: &View2D,
+    v_xz: &View2D,
+    v_yz: &View2D,
+    tolerance: f64,
+) -> Result<(Vector2<f64>, Vector2<f64>)> {
+    for view in [v_xy, v_xz, v_yz] {
+        if view.vertices.is_empty() {
+            return Err(ReconstructionError::EmptyView {
+                plane: view.plane,
+                raw_entities: view.raw_entities.len(),
+            });
+        }
+    }
+
+    let (size_xy, size_xz, size_yz) = (extent(v_xy), extent(v_xz), extent(v_yz));
+    for (view, axis, size, reference, other) in [
+        (v_xy, "X", size_xy.x, size_xz.x, "front"),
+        (v_yz, "Z", size_yz.y, size_xz.y, "front"),
+        (v_yz, "Y", size_yz.x, size_xy.y, "top"),
+    ] {
+        if (size - reference).abs() > tolerance {
+            return Err(ReconstructionError::RegistrationFailed {
+                plane: view.plane,
+                reason: format!("{axis} extent {size} differs from the {other} view's {reference}"),
+            });
+        }
+    }
+
+    // Centroid heuristic
     let center_xy = get_centroid(v_xy);
     let center_xz = get_centroid(v_xz);
     let center_yz = get_centroid(v_yz);
@@ -32,20 +110,26 @@ pub fn build_reconstruction(
     // YZ.x -> Y, YZ.y -> Z
     let shift_yz = Vector2::new(offset_yz_y, offset_yz_z);
 
-    println!(
-        "Auto-Aligning Views: Shift Top X by {:.2}, Shift Side Z by {:.2}, Shift Side Y by {:.2}",
-        offset_xy_x, offset_yz_z, offset_yz_y
+    tracing::debug!(
+        top_x = offset_xy_x,
+        side_z = offset_yz_z,
+        side_y = offset_yz_y,
+        "auto-aligned views by centroid"
     );
 
-    // 2. Build Lambda (Candidate 3D Vertices) - Optimized with sorting
-    let lambda = build_lambda_optimized(v_xy, v_xz, v_yz, shift_xy, shift_yz);
-    println!("Built {} Lambda candidates.", lambda.len());
-
-    // 3. Build Theta (Candidate 3D Edges) - Optimized with hashing
-    let theta = build_theta_optimized(&lambda, v_xy, v_xz, v_yz);
-    println!("Built {} Theta edges.", theta.len());
+    Ok((shift_xy, shift_yz))
+}
 
-    Ok((lambda, theta))
+/// Width and height of the view's vertex bounding box.
+fn extent(view: &View2D) -> Vector2<f64> {
+    let (min, max) = view.vertices.iter().fold(
+        (
+            Point2::new(f64::MAX, f64::MAX),
+            Point2::new(f64::MIN, f64::MIN),
+        ),
+        |(lo, hi), v| (lo.inf(&v.point), hi.sup(&v.point)),
+    );
+    max - min
 }
 
 fn get_centroid(view: &View2D) -> Point2<f64> {
@@ -68,7 +152,10 @@ fn build_lambda_optimized(
     v_yz: &View2D,
     shift_xy: Vector2<f64>,
     shift_yz: Vector2<f64>,
-) -> Vec<LambdaRow> {
+    tolerance: f64,
+    progress: &ProgressHandle,
+) -> Result<Vec<LambdaRow>> {
+    let _span = tracing::debug_span!("lambda").entered();
     let mut lambda = Vec::new();
 
     // V_xz: Sort by X
@@ -88,7 +175,12 @@ fn build_lambda_optimized(
     v_yz_sorted.sort_by(|a, b| a.point.x.partial_cmp(&b.point.x).unwrap());
 
     // Iterate Top View (XY)
-    for v1 in &v_xy.vertices {
+    let total = v_xy.vertices.len().max(1);
+    for (n, v1) in v_xy.vertices.iter().enumerate() {
+        if n % PROGRESS_STRIDE == 0 {
+            progress.checkpoint(Stage::Lambda)?;
+            progress.report(Stage::Lambda, n as f32 / total as f32);
+        }
         let p_xy = v1.point + shift_xy;
 
         // 1. Find candidates in XZ matching X
@@ -96,12 +188,11 @@ fn build_lambda_optimized(
         let target_x = p_xy.x;
         // The raw V_xz X matches Global X directly.
 
-        let start_idx = v_xz_sorted.partition_point(|v| v.point.x < target_x - MATCH_TOLERANCE);
+        let start_idx = v_xz_sorted.partition_point(|v| v.point.x < target_x - tolerance);
         // We iterate from start_idx until value > target + tol
 
-        for i in start_idx..v_xz_sorted.len() {
-            let v2 = v_xz_sorted[i];
-            if v2.point.x > target_x + MATCH_TOLERANCE {
+        for v2 in &v_xz_sorted[start_idx..] {
+            if v2.point.x > target_x + tolerance {
                 break;
             }
             // Candidate v2 found (matches X)
@@ -113,18 +204,17 @@ fn build_lambda_optimized(
             let target_yz_local_x = p_xy.y - shift_yz.x;
 
             let start_idy =
-                v_yz_sorted.partition_point(|v| v.point.x < target_yz_local_x - MATCH_TOLERANCE);
+                v_yz_sorted.partition_point(|v| v.point.x < target_yz_local_x - tolerance);
 
-            for j in start_idy..v_yz_sorted.len() {
-                let v3 = v_yz_sorted[j];
-                if v3.point.x > target_yz_local_x + MATCH_TOLERANCE {
+            for v3 in &v_yz_sorted[start_idy..] {
+                if v3.point.x > target_yz_local_x + tolerance {
                     break;
                 }
 
                 // Candidate v3 found (matches Y)
                 // Check Z match: V_yz.y (plus shift) should match p_xz.y (Global Z)
                 let p_yz = v3.point + shift_yz;
-                if (p_xz.y - p_yz.y).abs() <= MATCH_TOLERANCE {
+                if (p_xz.y - p_yz.y).abs() <= tolerance {
                     // All coordinates match!
                     lambda.push(LambdaRow {
                         p3: Point3::new(p_xy.x, p_xy.y, p_xz.y),
@@ -136,92 +226,102 @@ fn build_lambda_optimized(
             }
         }
     }
-    lambda
+    progress.report(Stage::Lambda, 1.0);
+    Ok(lambda)
 }
 
+/// Candidate 3D edges: pairs of Lambda rows whose projections are an edge or a
+/// single vertex in every view.
+///
+/// Rather than testing all Lambda pairs, each row's partners are drawn from the
+/// rows sitting on its own top-view vertex or on a top-view neighbour, and then
+/// checked against the front and side edges. Rows are processed in parallel in
+/// chunks; the result is sorted by `(start, end)` with `start < end`.
 fn build_theta_optimized(
     lambda: &[LambdaRow],
     v_xy: &View2D,
     v_xz: &View2D,
     v_yz: &View2D,
-) -> HashSet<ThetaEdge> {
-    let mut theta = HashSet::new();
+    progress: &ProgressHandle,
+) -> Result<Vec<ThetaEdge>> {
+    let _span = tracing::debug_span!("theta").entered();
 
-    // Pre-hash edges for true O(1) lookup
-    // Store as sorted pairs (min, max) to handle undirectedness
-    let edges_xy: HashSet<(usize, usize)> = v_xy
-        .edges
-        .iter()
-        .map(|e| {
-            if e.start < e.end {
-                (e.start, e.end)
-            } else {
-                (e.end, e.start)
-            }
-        })
-        .collect();
+    let adjacency_xy = vertex_adjacency(v_xy);
+    let edges_xz = undirected_edges(v_xz);
+    let edges_yz = undirected_edges(v_yz);
 
-    let edges_xz: HashSet<(usize, usize)> = v_xz
-        .edges
-        .iter()
-        .map(|e| {
-            if e.start < e.end {
-                (e.start, e.end)
-            } else {
-                (e.end, e.start)
-            }
-        })
-        .collect();
-
-    let edges_yz: HashSet<(usize, usize)> = v_yz
-        .edges
-        .iter()
-        .map(|e| {
-            if e.start < e.end {
-                (e.start, e.end)
-            } else {
-                (e.end, e.start)
-            }
-        })
-        .collect();
+    let mut rows_by_xy = vec![Vec::new(); v_xy.vertices.len()];
+    for (idx, row) in lambda.iter().enumerate() {
+        rows_by_xy[row.v_xy_id].push(idx);
+    }
 
-    let edge_exists = |edges: &HashSet<(usize, usize)>, id1: usize, id2: usize| -> bool {
-        if id1 == id2 {
-            return true;
-        } // Projecting an edge to a point is valid
-        let key = if id1 < id2 { (id1, id2) } else { (id2, id1) };
-        edges.contains(&key)
+    // Projecting an edge to a point is valid.
+    let edge_or_point = |edges: &HashSet<(usize, usize)>, a: usize, b: usize| {
+        a == b || edges.contains(&(a.min(b), a.max(b)))
     };
 
-    // Iterate all pairs of lambda
-    // O(Lambda^2) - can be optimized further with adjacency lists if needed,
-    // but this avoids the O(E) inner loop.
-    for i in 0..lambda.len() {
-        for j in (i + 1)..lambda.len() {
-            let L1 = &lambda[i];
-            let L2 = &lambda[j];
-
-            let ok_xy = edge_exists(&edges_xy, L1.v_xy_id, L2.v_xy_id);
-            if !ok_xy {
-                continue;
-            } // Fail fast
-
-            let ok_xz = edge_exists(&edges_xz, L1.v_xz_id, L2.v_xz_id);
-            if !ok_xz {
-                continue;
+    let partners = |i: usize| -> Vec<ThetaEdge> {
+        let l1 = &lambda[i];
+        let mut found = Vec::new();
+        let xy_candidates =
+            std::iter::once(l1.v_xy_id).chain(adjacency_xy[l1.v_xy_id].iter().copied());
+        for v in xy_candidates {
+            for &j in &rows_by_xy[v] {
+                if j <= i {
+                    continue;
+                }
+                let l2 = &lambda[j];
+                if edge_or_point(&edges_xz, l1.v_xz_id, l2.v_xz_id)
+                    && edge_or_point(&edges_yz, l1.v_yz_id, l2.v_yz_id)
+                {
+                    found.push(ThetaEdge {
+                        start_lambda_idx: i,
+                        end_lambda_idx: j,
+                    });
+                }
             }
+        }
+        found.sort_unstable_by_key(|e| e.end_lambda_idx);
+        found
+    };
 
-            let ok_yz = edge_exists(&edges_yz, L1.v_yz_id, L2.v_yz_id);
-            if !ok_yz {
-                continue;
-            }
+    let mut theta = Vec::new();
+    let total = lambda.len().max(1);
+    for chunk_start in (0..lambda.len()).step_by(THETA_CHUNK) {
+        progress.checkpoint(Stage::Theta)?;
+        progress.report(Stage::Theta, chunk_start as f32 / total as f32);
+        let chunk_end = (chunk_start + THETA_CHUNK).min(lambda.len());
+        let chunk: Vec<Vec<ThetaEdge>> = (chunk_start..chunk_end)
+            .into_par_iter()
+            .map(partners)
+            .collect();
+        theta.extend(chunk.into_iter().flatten());
+    }
 
-            theta.insert(ThetaEdge {
-                start_lambda_idx: i,
-                end_lambda_idx: j,
-            });
+    progress.report(Stage::Theta, 1.0);
+    Ok(theta)
+}
+
+/// Neighbours of each vertex, without duplicates or self-loops.
+fn vertex_adjacency(view: &View2D) -> Vec<Vec<usize>> {
+    let mut adjacency = vec![Vec::new(); view.vertices.len()];
+    for e in &view.edges {
+        if e.start != e.end {
+            adjacency[e.start].push(e.end);
+            adjacency[e.end].push(e.start);
         }
     }
+    for neighbours in &mut adjacency {
+        neighbours.sort_unstable();
+        neighbours.dedup();
+    }
+    adjacency
+}
 
-    theta
+/// Edges as `(min, max)` vertex pairs.
+fn undirected_edges(view: &View2D) -> HashSet<(usize, usize)> {
+    view.edges
+        .iter()
+        .map(|e| (e.start.min(e.end), e.start.max(e.end)))
+        .collect()
 }
@@ -1,6 +1,407 @@
-use anyhow::Result;
+use crate::error::{ReconstructionError, Result};
+use crate::structs::{LambdaRow, ThetaEdge};
+use cadconvert_core::tolerance::Tolerances;
+use nalgebra::{Point2, Point3, Vector3};
+use rayon::prelude::*;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
-pub fn build_solid() -> Result<()> {
-    // TODO: Implement solid generation
-    Ok(())
+/// A polyhedral solid over the Lambda vertices.
+///
+/// Reconstruction only yields straight edges, so every face is planar.
+#[derive(Debug, Clone)]
+pub struct Solid {
+    pub vertices: Vec<Point3<f64>>,
+    pub faces: Vec<SolidFace>,
+}
+
+/// A planar face with optional holes, as indices into [`Solid::vertices`].
+#[derive(Debug, Clone)]
+pub struct SolidFace {
+    /// Outward unit normal. `outer` runs counter-clockwise around it, holes clockwise.
+    pub normal: Vector3<f64>,
+    pub outer: Vec<usize>,
+    pub holes: Vec<Vec<usize>>,
+}
+
+impl SolidFace {
+    fn loops(&self) -> impl Iterator<Item = &Vec<usize>> {
+        std::iter::once(&self.outer).chain(&self.holes)
+    }
+
+    fn flip(&mut self) {
+        self.normal = -self.normal;
+        for l in std::iter::once(&mut self.outer).chain(&mut self.holes) {
+            l.reverse();
+        }
+    }
+}
+
+/// Finds the faces bounded by the wireframe and orients them outwards.
+///
+/// Every plane spanned by two edges at a vertex is a candidate; the edges lying in
+/// it form a planar graph whose bounded faces, with any loops nested inside them
+/// as holes, are candidate faces. Candidates whose every edge is also bounded by
+/// two other faces (the opening of a pocket, the footprint of a boss) are dropped.
+/// Faces are then oriented consistently across shared edges, and each connected
+/// shell is flipped if needed so that it encloses positive volume.
+#[tracing::instrument(skip_all, fields(vertices = lambda.len(), edges = theta.len()))]
+pub fn build_solid(
+    lambda: &[LambdaRow],
+    theta: &[ThetaEdge],
+    tolerances: &Tolerances,
+) -> Result<Solid> {
+    // A closed polyhedral wireframe has at least 3 edges at every vertex.
+    let mut degree = vec![0usize; lambda.len()];
+    for edge in theta {
+        degree[edge.start_lambda_idx] += 1;
+        degree[edge.end_lambda_idx] += 1;
+    }
+    let dangling_vertices: Vec<usize> = degree
+        .iter()
+        .enumerate()
+        .filter(|(_, &d)| d > 0 && d < 3)
+        .map(|(i, _)| i)
+        .collect();
+    if !dangling_vertices.is_empty() {
+        return Err(ReconstructionError::SolidNotClosed { dangling_vertices });
+    }
+
+    let vertices: Vec<Point3<f64>> = lambda.iter().map(|row| row.p3).collect();
+    let edges: Vec<(usize, usize)> = theta
+        .iter()
+        .map(|e| (e.start_lambda_idx, e.end_lambda_idx))
+        .collect();
+
+    let mut faces = find_faces(&vertices, &edges, tolerances.view_match);
+    drop_pseudo_faces(&vertices, &mut faces);
+    if faces.is_empty() {
+        return Err(ReconstructionError::NoFaces { edges: edges.len() });
+    }
+    orient_faces(&vertices, &mut faces);
+    tracing::info!(count = faces.len(), "built solid faces");
+
+    Ok(Solid { vertices, faces })
+}
+
+/// Orthonormal `(u, w)` with `u × w = normal`, for 2D coordinates in a plane.
+pub(crate) fn plane_basis(normal: &Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    let axis = if normal.x.abs() <= normal.y.abs() && normal.x.abs() <= normal.z.abs() {
+        Vector3::x()
+    } else if normal.y.abs() <= normal.z.abs() {
+        Vector3::y()
+    } else {
+        Vector3::z()
+    };
+    let u = normal.cross(&axis).normalize();
+    let w = normal.cross(&u);
+    (u, w)
+}
+
+fn find_faces(points: &[Point3<f64>], edges: &[(usize, usize)], tolerance: f64) -> Vec<SolidFace> {
+    let mut adjacency = vec![Vec::new(); points.len()];
+    for &(a, b) in edges {
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+
+    // Candidate planes, deduplicated on a grid of normal directions and offsets.
+    // Planes that straddle a grid cell boundary come out twice; their faces are
+    // deduplicated below.
+    let mut seen = HashSet::new();
+    let mut planes = Vec::new();
+    for (v, neighbours) in adjacency.iter().enumerate() {
+        for (i, &a) in neighbours.iter().enumerate() {
+            for &b in &neighbours[i + 1..] {
+                let (da, db) = (points[a] - points[v], points[b] - points[v]);
+                let cross = da.cross(&db);
+                if cross.norm() <= 1e-9 * da.norm() * db.norm() {
+                    continue;
+                }
+                let mut normal = cross.normalize();
+                let largest = normal.iamax();
+                if normal[largest] < 0.0 {
+                    normal = -normal;
+                }
+                let offset = normal.dot(&points[v].coords);
+                let key = (
+                    (normal.x * 1e6).round() as i64,
+                    (normal.y * 1e6).round() as i64,
+                    (normal.z * 1e6).round() as i64,
+                    (offset / tolerance).round() as i64,
+                );
+                if seen.insert(key) {
+                    planes.push((normal, offset));
+                }
+            }
+        }
+    }
+    tracing::debug!(count = planes.len(), "candidate face planes");
+
+    let faces: Vec<SolidFace> = planes
+        .par_iter()
+        .flat_map_iter(|(normal, offset)| plane_faces(points, edges, normal, *offset, tolerance))
+        .collect();
+
+    let mut unique = HashSet::new();
+    faces
+        .into_iter()
+        .filter(|f| {
+            let mut key: Vec<usize> = f.loops().flatten().copied().collect();
+            key.sort_unstable();
+            unique.insert(key)
+        })
+        .collect()
+}
+
+/// Bounded faces of the planar graph formed by the edges in one plane.
+fn plane_faces(
+    points: &[Point3<f64>],
+    edges: &[(usize, usize)],
+    normal: &Vector3<f64>,
+    offset: f64,
+    tolerance: f64,
+) -> Vec<SolidFace> {
+    let on_plane = |i: usize| (normal.dot(&points[i].coords) - offset).abs() <= tolerance;
+    let mut adjacency: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for &(a, b) in edges {
+        if on_plane(a) && on_plane(b) {
+            adjacency.entry(a).or_default().push(b);
+            adjacency.entry(b).or_default().push(a);
+        }
+    }
+
+    // Edges hanging off by one end cannot bound a face in this plane.
+    loop {
+        let leaves: Vec<usize> = adjacency
+            .iter()
+            .filter(|(_, n)| n.len() < 2)
+            .map(|(&v, _)| v)
+            .collect();
+        if leaves.is_empty() {
+            break;
+        }
+        for v in leaves {
+            for n in adjacency.remove(&v).unwrap_or_default() {
+                if let Some(list) = adjacency.get_mut(&n) {
+                    list.retain(|&x| x != v);
+                }
+            }
+        }
+    }
+    if adjacency.len() < 3 {
+        return Vec::new();
+    }
+
+    let (u, w) = plane_basis(normal);
+    let project = |i: usize| Point2::new(u.dot(&points[i].coords), w.dot(&points[i].coords));
+    for (&v, neighbours) in adjacency.iter_mut() {
+        let origin = project(v);
+        neighbours.sort_by(|&a, &b| {
+            let (pa, pb) = (project(a) - origin, project(b) - origin);
+            pa.y.atan2(pa.x).total_cmp(&pb.y.atan2(pb.x))
+        });
+    }
+
+    // Walk every half-edge, keeping the face on the left: from `a -> b`, continue
+    // with the neighbour of `b` that comes just before `a` counter-clockwise.
+    // Bounded faces come out counter-clockwise, outer boundaries clockwise.
+    let mut visited = HashSet::new();
+    let mut cycles = Vec::new();
+    for (&start, neighbours) in &adjacency {
+        for &next in neighbours {
+            if visited.contains(&(start, next)) {
+                continue;
+            }
+            let mut cycle = Vec::new();
+            let (mut a, mut b) = (start, next);
+            while visited.insert((a, b)) {
+                cycle.push(a);
+                let around = &adjacency[&b];
+                let i = around.iter().position(|&x| x == a).unwrap_or(0);
+                let c = around[(i + around.len() - 1) % around.len()];
+                (a, b) = (b, c);
+            }
+            let ring: Vec<Point2<f64>> = cycle.iter().map(|&i| project(i)).collect();
+            cycles.push((signed_area(&ring), cycle, ring));
+        }
+    }
+
+    // Connected components, so a boundary is never treated as a hole of its own faces.
+    let mut component: HashMap<usize, usize> = HashMap::new();
+    for &root in adjacency.keys() {
+        if component.contains_key(&root) {
+            continue;
+        }
+        let id = component.len();
+        let mut queue = VecDeque::from([root]);
+        component.insert(root, id);
+        while let Some(v) = queue.pop_front() {
+            for &n in &adjacency[&v] {
+                if let Entry::Vacant(e) = component.entry(n) {
+                    e.insert(id);
+                    queue.push_back(n);
+                }
+            }
+        }
+    }
+
+    let min_area = tolerance * tolerance;
+    let mut faces: Vec<(f64, Vec<Point2<f64>>, SolidFace)> = cycles
+        .iter()
+        .filter(|(area, _, _)| *area > min_area)
+        .map(|(area, cycle, ring)| {
+            let face = SolidFace {
+                normal: *normal,
+                outer: cycle.clone(),
+                holes: Vec::new(),
+            };
+            (*area, ring.clone(), face)
+        })
+        .collect();
+    for (area, cycle, ring) in &cycles {
+        if *area >= -min_area {
+            continue;
+        }
+        let own = component[&cycle[0]];
+        let host = faces
+            .iter_mut()
+            .filter(|(_, _, f)| component[&f.outer[0]] != own)
+            .filter(|(_, outer, _)| contains(outer, ring[0]))
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, _, face)) = host {
+            face.holes.push(cycle.clone());
+        }
+    }
+    faces.into_iter().map(|(_, _, f)| f).collect()
+}
+
+fn signed_area(ring: &[Point2<f64>]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        * 0.5
+}
+
+/// Even-odd point-in-polygon test.
+fn contains(ring: &[Point2<f64>], p: Point2<f64>) -> bool {
+    let n = ring.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (a, b) = (ring[i], ring[(i + 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn undirected(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+fn face_edges(face: &SolidFace) -> impl Iterator<Item = (usize, usize)> + '_ {
+    face.loops()
+        .flat_map(|l| (0..l.len()).map(move |i| (l[i], l[(i + 1) % l.len()])))
+}
+
+fn drop_pseudo_faces(points: &[Point3<f64>], faces: &mut Vec<SolidFace>) {
+    let mut uses: HashMap<(usize, usize), usize> = HashMap::new();
+    for face in faces.iter() {
+        for (a, b) in face_edges(face) {
+            *uses.entry(undirected(a, b)).or_default() += 1;
+        }
+    }
+    while let Some(i) = faces
+        .iter()
+        .position(|f| face_edges(f).all(|(a, b)| uses[&undirected(a, b)] >= 3))
+    {
+        let face = faces.remove(i);
+        tracing::debug!(
+            vertices = face.outer.len(),
+            first = ?points[face.outer[0]],
+            "dropped pseudo-face"
+        );
+        for (a, b) in face_edges(&face) {
+            *uses.get_mut(&undirected(a, b)).unwrap() -= 1;
+        }
+    }
+}
+
+/// Orients faces so that neighbours traverse a shared edge in opposite
+/// directions, then flips each connected shell that encloses negative volume.
+fn orient_faces(points: &[Point3<f64>], faces: &mut [SolidFace]) {
+    let mut by_edge: HashMap<(usize, usize), Vec<(usize, bool)>> = HashMap::new();
+    for (f, face) in faces.iter().enumerate() {
+        for (a, b) in face_edges(face) {
+            by_edge
+                .entry(undirected(a, b))
+                .or_default()
+                .push((f, a < b));
+        }
+    }
+
+    let mut flip: Vec<Option<bool>> = vec![None; faces.len()];
+    let mut shells = Vec::new();
+    for root in 0..faces.len() {
+        if flip[root].is_some() {
+            continue;
+        }
+        flip[root] = Some(false);
+        let mut shell = vec![root];
+        let mut queue = VecDeque::from([root]);
+        while let Some(f) = queue.pop_front() {
+            let flipped = flip[f] == Some(true);
+            for (a, b) in face_edges(&faces[f]) {
+                let forward = a < b;
+                for &(g, g_forward) in &by_edge[&undirected(a, b)] {
+                    if flip[g].is_none() {
+                        flip[g] = Some(flipped ^ (forward == g_forward));
+                        shell.push(g);
+                        queue.push_back(g);
+                    }
+                }
+            }
+        }
+        shells.push(shell);
+    }
+    for (face, flip) in faces.iter_mut().zip(&flip) {
+        if *flip == Some(true) {
+            face.flip();
+        }
+    }
+
+    // Divergence theorem: V = 1/3 Σ (area vector · point on face).
+    for shell in shells {
+        let volume: f64 = shell
+            .iter()
+            .map(|&f| {
+                let face = &faces[f];
+                let area: Vector3<f64> = face.loops().map(|l| newell(points, l)).sum();
+                area.dot(&points[face.outer[0]].coords) / 3.0
+            })
+            .sum();
+        if volume < 0.0 {
+            for &f in &shell {
+                faces[f].flip();
+            }
+        }
+    }
+}
+
+/// Area vector of a planar loop: its direction follows the right-hand rule.
+fn newell(points: &[Point3<f64>], ring: &[usize]) -> Vector3<f64> {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            points[ring[i]]
+                .coords
+                .cross(&points[ring[(i + 1) % n]].coords)
+        })
+        .sum::<Vector3<f64>>()
+        * 0.5
 }
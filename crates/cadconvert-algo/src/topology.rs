@@ -1,10 +1,14 @@
-use anyhow::Result;
+use crate::error::Result;
 use crate::structs::{View2D, Vertex2D, Edge2D};
 use cadconvert_core::model::{Primitive2D, Entity2D};
+use cadconvert_core::tolerance::Tolerances;
 use nalgebra::{Point2, Vector2};
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
 use std::collections::{HashMap, HashSet};
 
-const EPSILON: f64 = 1e-4;
+/// Segments whose direction vectors differ by less than this sine are treated as parallel.
+const PARALLEL_SIN: f64 = 1e-9;
 
 #[derive(Clone, Debug)]
 struct RawSegment {
@@ -13,21 +17,73 @@ struct RawSegment {
     original_id: u64,
 }
 
-pub fn build_topology(view: &mut View2D) -> Result<()> {
-    let mut segments = extract_segments(&view.raw_entities);
+#[tracing::instrument(skip_all, fields(plane = ?view.plane))]
+pub fn build_topology(view: &mut View2D, tolerances: &Tolerances) -> Result<()> {
+    let segments = extract_segments(&view.raw_entities);
+    let split_points_map = split_points(&segments, tolerances.snap);
+    assemble(view, &segments, &split_points_map, tolerances.snap);
+    Ok(())
+}
 
-    // 1. Intersect segments (Naively O(N^2))
+/// [`build_topology`] with the original all-pairs intersection sweep instead of
+/// the R-tree, as a baseline for the topology benchmark.
+#[cfg(feature = "bench")]
+pub fn build_topology_all_pairs(view: &mut View2D, tolerances: &Tolerances) -> Result<()> {
+    let eps = tolerances.snap;
+    let segments = extract_segments(&view.raw_entities);
     let mut split_points_map: HashMap<usize, Vec<Point2<f64>>> = HashMap::new();
-    
-    for i in 0..segments.len() {
-        for j in (i + 1)..segments.len() {
-            if let Some(pt) = intersect_segment_segment(&segments[i], &segments[j]) {
+    for (i, seg) in segments.iter().enumerate() {
+        for (j, other) in segments.iter().enumerate().skip(i + 1) {
+            for pt in intersect_segment_segment(seg, other, eps) {
                 split_points_map.entry(i).or_default().push(pt);
                 split_points_map.entry(j).or_default().push(pt);
             }
         }
     }
+    assemble(view, &segments, &split_points_map, eps);
+    Ok(())
+}
+
+/// Step 1: the points each segment has to be split at.
+fn split_points(segments: &[RawSegment], eps: f64) -> HashMap<usize, Vec<Point2<f64>>> {
+    // Candidate pairs come from an R-tree over the (tolerance-expanded) segment
+    // boxes; pairs are visited in (i, j) order so the split points are identical
+    // to an all-pairs sweep.
+    let mut split_points_map: HashMap<usize, Vec<Point2<f64>>> = HashMap::new();
+    let tree = RTree::bulk_load(
+        segments
+            .iter()
+            .enumerate()
+            .map(|(i, seg)| GeomWithData::new(Rectangle::from_aabb(segment_envelope(seg, eps)), i))
+            .collect(),
+    );
 
+    let mut candidates = Vec::new();
+    for (i, seg) in segments.iter().enumerate() {
+        candidates.clear();
+        candidates.extend(
+            tree.locate_in_envelope_intersecting(&segment_envelope(seg, eps))
+                .map(|c| c.data)
+                .filter(|&j| j > i),
+        );
+        candidates.sort_unstable();
+        for &j in &candidates {
+            for pt in intersect_segment_segment(seg, &segments[j], eps) {
+                split_points_map.entry(i).or_default().push(pt);
+                split_points_map.entry(j).or_default().push(pt);
+            }
+        }
+    }
+    split_points_map
+}
+
+/// Steps 2 and 3: split the segments and snap their ends into `view`'s graph.
+fn assemble(
+    view: &mut View2D,
+    segments: &[RawSegment],
+    split_points_map: &HashMap<usize, Vec<Point2<f64>>>,
+    eps: f64,
+) {
     // 2. Split segments
     let mut final_segments = Vec::new();
 
@@ -38,8 +94,6 @@ pub fn build_topology(view: &mut View2D) -> Result<()> {
             pts.push(seg.p2);
             
             // Sort points along the segment vector
-            let dir = seg.p2 - seg.p1;
-            
             pts.sort_by(|a, b| {
                 let da = (*a - seg.p1).norm();
                 let db = (*b - seg.p1).norm();
@@ -47,13 +101,13 @@ pub fn build_topology(view: &mut View2D) -> Result<()> {
             });
 
             // Deduplicate points
-            pts.dedup_by(|a, b| (*a - *b).norm() < EPSILON);
+            pts.dedup_by(|a, b| (*a - *b).norm() < eps);
 
             // Create sub-segments
             for window in pts.windows(2) {
                 let p_start = window[0];
                 let p_end = window[1];
-                if (p_start - p_end).norm() > EPSILON {
+                if (p_start - p_end).norm() > eps {
                     final_segments.push(RawSegment {
                         p1: p_start,
                         p2: p_end,
@@ -67,26 +121,18 @@ pub fn build_topology(view: &mut View2D) -> Result<()> {
         }
     }
     
-    // 3. Snap vertices and build graph
-    let mut unique_points: Vec<Point2<f64>> = Vec::new();
-    // Helper to find or add point
-    let mut get_point_id = |p: Point2<f64>, points: &mut Vec<Point2<f64>>| -> usize {
-        for (idx, existing) in points.iter().enumerate() {
-            if (p - *existing).norm() < EPSILON {
-                return idx;
-            }
-        }
-        points.push(p);
-        points.len() - 1
-    };
-
+    // 3. Snap vertices and build graph. Overlapping and duplicate segments have
+    // been split at the same points, so they now map to the same vertex pair;
+    // keep the first one.
+    let mut snap = SnapGrid::new(eps);
     let mut edges = Vec::new();
-    
+    let mut seen_edges: HashSet<(usize, usize)> = HashSet::new();
+
     for (seg_idx, seg) in final_segments.iter().enumerate() {
-        let id1 = get_point_id(seg.p1, &mut unique_points);
-        let id2 = get_point_id(seg.p2, &mut unique_points);
-        
-        if id1 != id2 {
+        let id1 = snap.get_or_insert(seg.p1);
+        let id2 = snap.get_or_insert(seg.p2);
+
+        if id1 != id2 && seen_edges.insert((id1.min(id2), id1.max(id2))) {
             edges.push(Edge2D {
                 id: seg_idx, // This ID is temporary, will refine
                 start: id1,
@@ -97,7 +143,8 @@ pub fn build_topology(view: &mut View2D) -> Result<()> {
     }
 
     // Populate View
-    view.vertices = unique_points
+    view.vertices = snap
+        .points
         .into_iter()
         .enumerate()
         .map(|(id, point)| Vertex2D { id, point })
@@ -109,7 +156,79 @@ pub fn build_topology(view: &mut View2D) -> Result<()> {
     }
     view.edges = edges;
 
-    Ok(())
+    tracing::debug!(
+        segments = segments.len(),
+        vertices = view.vertices.len(),
+        edges = view.edges.len(),
+        "built planar topology"
+    );
+}
+
+fn segment_envelope(seg: &RawSegment, eps: f64) -> AABB<[f64; 2]> {
+    AABB::from_corners(
+        [
+            seg.p1.x.min(seg.p2.x) - eps,
+            seg.p1.y.min(seg.p2.y) - eps,
+        ],
+        [
+            seg.p1.x.max(seg.p2.x) + eps,
+            seg.p1.y.max(seg.p2.y) + eps,
+        ],
+    )
+}
+
+/// Hashed grid for vertex snapping.
+///
+/// Cells are `tolerance` wide, so any point within `tolerance` of a query lies in
+/// the 3x3 block around it. Returns the lowest matching index, which is what a
+/// linear scan in insertion order would find.
+struct SnapGrid {
+    tolerance: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    points: Vec<Point2<f64>>,
+}
+
+impl SnapGrid {
+    fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            cells: HashMap::new(),
+            points: Vec::new(),
+        }
+    }
+
+    fn cell(&self, p: Point2<f64>) -> (i64, i64) {
+        (
+            (p.x / self.tolerance).floor() as i64,
+            (p.y / self.tolerance).floor() as i64,
+        )
+    }
+
+    fn get_or_insert(&mut self, p: Point2<f64>) -> usize {
+        let (cx, cy) = self.cell(p);
+        let mut best: Option<usize> = None;
+        for gx in (cx - 1)..=(cx + 1) {
+            for gy in (cy - 1)..=(cy + 1) {
+                let Some(ids) = self.cells.get(&(gx, gy)) else {
+                    continue;
+                };
+                for &idx in ids {
+                    if (p - self.points[idx]).norm() < self.tolerance
+                        && best.is_none_or(|b| idx < b)
+                    {
+                        best = Some(idx);
+                    }
+                }
+            }
+        }
+        if let Some(idx) = best {
+            return idx;
+        }
+        let idx = self.points.len();
+        self.points.push(p);
+        self.cells.entry((cx, cy)).or_default().push(idx);
+        idx
+    }
 }
 
 fn extract_segments(entities: &[Entity2D]) -> Vec<RawSegment> {
@@ -146,27 +265,79 @@ fn extract_segments(entities: &[Entity2D]) -> Vec<RawSegment> {
     segs
 }
 
-fn intersect_segment_segment(s1: &RawSegment, s2: &RawSegment) -> Option<Point2<f64>> {
+/// Points where `s1` and `s2` touch, within `eps` (a distance, not a curve
+/// parameter).
+///
+/// - crossing or T-junction: the single contact point;
+/// - collinear overlap (including duplicates): both ends of the shared stretch, so
+///   each segment is split where the other one starts or stops.
+fn intersect_segment_segment(s1: &RawSegment, s2: &RawSegment, eps: f64) -> Vec<Point2<f64>> {
     let p = s1.p1;
     let r = s1.p2 - s1.p1;
     let q = s2.p1;
     let s = s2.p2 - s2.p1;
+    let r_len = r.norm();
+    let s_len = s.norm();
+    if r_len <= eps || s_len <= eps {
+        return Vec::new();
+    }
 
     let r_cross_s = perp_dot(r, s);
     let q_minus_p = q - p;
 
-    if r_cross_s.abs() < EPSILON {
-        return None; 
+    if (r_cross_s / (r_len * s_len)).abs() < PARALLEL_SIN {
+        return collinear_overlap(s1, s2, eps);
     }
 
     let t = perp_dot(q_minus_p, s) / r_cross_s;
     let u = perp_dot(q_minus_p, r) / r_cross_s;
 
-    if t >= -EPSILON && t <= 1.0 + EPSILON && u >= -EPSILON && u <= 1.0 + EPSILON {
-        return Some(p + r * t);
+    let t_tol = eps / r_len;
+    let u_tol = eps / s_len;
+    if (-t_tol..=1.0 + t_tol).contains(&t) && (-u_tol..=1.0 + u_tol).contains(&u) {
+        // Snap contacts near an endpoint onto it so T-junctions land exactly on
+        // the stem's end instead of a nearby point.
+        let contact = p + r * t.clamp(0.0, 1.0);
+        for end in [s1.p1, s1.p2, s2.p1, s2.p2] {
+            if (contact - end).norm() < eps {
+                return vec![end];
+            }
+        }
+        return vec![contact];
     }
 
-    None
+    Vec::new()
+}
+
+fn collinear_overlap(s1: &RawSegment, s2: &RawSegment, eps: f64) -> Vec<Point2<f64>> {
+    let r = s1.p2 - s1.p1;
+    let r_len = r.norm();
+    let dir = r / r_len;
+
+    // Parallel but offset by more than the tolerance: no contact.
+    let offset = |pt: Point2<f64>| perp_dot(dir, pt - s1.p1).abs();
+    if offset(s2.p1) >= eps || offset(s2.p2) >= eps {
+        return Vec::new();
+    }
+
+    // Project s2 onto s1 (in length units along s1).
+    let a = (s2.p1 - s1.p1).dot(&dir);
+    let b = (s2.p2 - s1.p1).dot(&dir);
+    let lo = a.min(b).max(0.0);
+    let hi = a.max(b).min(r_len);
+    if hi < lo - eps {
+        return Vec::new();
+    }
+
+    let mut pts = Vec::with_capacity(2);
+    for pt in [s1.p1, s1.p2, s2.p1, s2.p2] {
+        let d = (pt - s1.p1).dot(&dir);
+        let inside = d >= lo - eps && d <= hi + eps;
+        if inside && !pts.iter().any(|x: &Point2<f64>| (*x - pt).norm() < eps) {
+            pts.push(pt);
+        }
+    }
+    pts
 }
 
 fn perp_dot(v1: Vector2<f64>, v2: Vector2<f64>) -> f64 {
@@ -0,0 +1,183 @@
+use crate::error::{ReconstructionError, Result};
+use crate::reconstruction::align_views;
+use crate::structs::View2D;
+use crate::{topology, view_separation};
+use cadconvert_core::model::Drawing2D;
+use cadconvert_core::step::{Edge, StepFile};
+use cadconvert_core::tolerance::Tolerances;
+use nalgebra::{Point2, Vector2};
+use rstar::primitives::GeomWithData;
+use rstar::RTree;
+use std::collections::HashSet;
+
+/// Outcome of [`validate_step`].
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub vertices: usize,
+    pub edges: usize,
+    /// STEP vertices that miss a drawing vertex in at least one view.
+    pub unmatched_vertices: Vec<[f64; 3]>,
+    /// STEP edges that project to neither a drawing edge nor a point in at least
+    /// one view.
+    pub unmatched_edges: Vec<Edge>,
+}
+
+impl ValidationReport {
+    /// Whether the file had a wireframe to compare: a file without vertices
+    /// or edges, such as a 2D curve set or faces alone, checks nothing.
+    pub fn is_checked(&self) -> bool {
+        self.vertices > 0 && self.edges > 0
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.is_checked() && self.unmatched_vertices.is_empty() && self.unmatched_edges.is_empty()
+    }
+
+    /// [`ReconstructionError::ValidationFailed`] unless [`Self::is_valid`].
+    pub fn ensure_valid(&self) -> Result<()> {
+        if !self.is_checked() {
+            return Err(ReconstructionError::ValidationFailed {
+                check: "wireframe".into(),
+                detail: format!(
+                    "nothing to compare: the STEP file has {} vertices and {} edges",
+                    self.vertices, self.edges
+                ),
+            });
+        }
+        if let Some(p) = self.unmatched_vertices.first() {
+            return Err(ReconstructionError::ValidationFailed {
+                check: "vertices".into(),
+                detail: format!(
+                    "{} of {} STEP vertices miss a drawing vertex (first: {p:?})",
+                    self.unmatched_vertices.len(),
+                    self.vertices
+                ),
+            });
+        }
+        if let Some(edge) = self.unmatched_edges.first() {
+            return Err(ReconstructionError::ValidationFailed {
+                check: "edges".into(),
+                detail: format!(
+                    "{} of {} STEP edges miss a drawing edge (first: {})",
+                    self.unmatched_edges.len(),
+                    self.edges,
+                    edge.id
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A view's topology, indexed for projection lookups.
+struct ViewIndex {
+    tree: RTree<GeomWithData<[f64; 2], usize>>,
+    edges: HashSet<(usize, usize)>,
+    shift: Vector2<f64>,
+}
+
+impl ViewIndex {
+    fn new(view: &View2D, shift: Vector2<f64>) -> Self {
+        Self {
+            tree: RTree::bulk_load(
+                view.vertices
+                    .iter()
+                    .map(|v| GeomWithData::new([v.point.x, v.point.y], v.id))
+                    .collect(),
+            ),
+            edges: view
+                .edges
+                .iter()
+                .map(|e| (e.start.min(e.end), e.start.max(e.end)))
+                .collect(),
+            shift,
+        }
+    }
+
+    fn vertices_near(&self, p: Point2<f64>, tolerance: f64) -> Vec<usize> {
+        let p = p - self.shift;
+        self.tree
+            .locate_within_distance([p.x, p.y], tolerance * tolerance)
+            .map(|v| v.data)
+            .collect()
+    }
+
+    fn has_edge(&self, a: Point2<f64>, b: Point2<f64>, tolerance: f64) -> bool {
+        if (a - b).norm() <= tolerance {
+            return true;
+        }
+        let ends = self.vertices_near(b, tolerance);
+        self.vertices_near(a, tolerance).iter().any(|&i| {
+            ends.iter()
+                .any(|&j| self.edges.contains(&(i.min(j), i.max(j))))
+        })
+    }
+}
+
+/// Checks a STEP wireframe against the three views of `drawing`.
+///
+/// Every `VERTEX_POINT` must project onto a vertex of the top, front and side
+/// views, and every `EDGE_CURVE` onto an edge or a single point of each, within
+/// `tolerances.view_match`. Views are separated and aligned the same way
+/// [`crate::reconstruct_solid`] does, so its output validates against its input.
+/// A file with no vertices or no edges is reported, but never valid.
+#[tracing::instrument(skip_all, fields(entities = step.len()))]
+pub fn validate_step(
+    step: &StepFile,
+    drawing: &Drawing2D,
+    tolerances: &Tolerances,
+) -> Result<ValidationReport> {
+    let (mut v_xy, mut v_xz, mut v_yz) = view_separation::separate_views(drawing)?;
+    for view in [&mut v_xy, &mut v_xz, &mut v_yz] {
+        topology::build_topology(view, tolerances)?;
+    }
+    let (shift_xy, shift_yz) = align_views(&v_xy, &v_xz, &v_yz, tolerances.view_match)?;
+    let views = [
+        ViewIndex::new(&v_xy, shift_xy),
+        ViewIndex::new(&v_xz, Vector2::zeros()),
+        ViewIndex::new(&v_yz, shift_yz),
+    ];
+    let project = |p: [f64; 3]| {
+        [
+            Point2::new(p[0], p[1]),
+            Point2::new(p[0], p[2]),
+            Point2::new(p[1], p[2]),
+        ]
+    };
+    let tolerance = tolerances.view_match;
+
+    let vertices = step.vertices()?;
+    let edges = step.edges()?;
+    let mut report = ValidationReport {
+        vertices: vertices.len(),
+        edges: edges.len(),
+        ..ValidationReport::default()
+    };
+    for (_, p) in vertices {
+        let matched = views
+            .iter()
+            .zip(project(p))
+            .all(|(view, q)| !view.vertices_near(q, tolerance).is_empty());
+        if !matched {
+            report.unmatched_vertices.push(p);
+        }
+    }
+    for edge in edges {
+        let matched = views
+            .iter()
+            .zip(project(edge.start).into_iter().zip(project(edge.end)))
+            .all(|(view, (a, b))| view.has_edge(a, b, tolerance));
+        if !matched {
+            report.unmatched_edges.push(edge);
+        }
+    }
+
+    tracing::debug!(
+        vertices = report.vertices,
+        edges = report.edges,
+        unmatched_vertices = report.unmatched_vertices.len(),
+        unmatched_edges = report.unmatched_edges.len(),
+        "validated STEP against drawing"
+    );
+    Ok(report)
+}
@@ -1,7 +1,8 @@
+use crate::error::{ReconstructionError, Result};
 use crate::structs::{View2D, ViewPlane};
-use anyhow::{bail, Result};
 use cadconvert_core::model::{Drawing2D, EntityKind};
 
+#[tracing::instrument(skip_all)]
 pub fn separate_views(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
     let mut v_xy = View2D::new(ViewPlane::XY);
     let mut v_xz = View2D::new(ViewPlane::XZ);
@@ -13,8 +14,6 @@ pub fn separate_views(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
         let layer_raw = entity.style.layer.as_deref().unwrap_or("0");
         let layer = layer_raw.to_ascii_uppercase();
 
-        // println!("Entity {} on layer '{}'", entity.id, layer_raw);
-
         if entity.kind == EntityKind::Dimension || entity.kind == EntityKind::Text {
             continue;
         }
@@ -31,28 +30,33 @@ pub fn separate_views(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
             v_xz.raw_entities.push(entity.clone());
         } else if layer.contains("YZ") || layer.contains("RIGHT") || layer.contains("SIDE") {
             v_yz.raw_entities.push(entity.clone());
-            // Optional: what to do with unassigned layers?
-            // println!("Warning: Unassigned entity {} on layer '{}'", entity.id, layer_raw);
+        } else {
+            tracing::trace!(
+                entity = entity.id,
+                layer = layer_raw,
+                "entity not assigned to a view by layer"
+            );
         }
     }
 
-    println!(
-        "View Separation Stats: XY={}, XZ={}, YZ={}",
-        v_xy.raw_entities.len(),
-        v_xz.raw_entities.len(),
-        v_yz.raw_entities.len()
+    tracing::debug!(
+        xy = v_xy.raw_entities.len(),
+        xz = v_xz.raw_entities.len(),
+        yz = v_yz.raw_entities.len(),
+        "layer-based view separation"
     );
 
     // If logical layers failed, try spatial separation
     if v_xy.raw_entities.is_empty() && v_xz.raw_entities.is_empty() && v_yz.raw_entities.is_empty()
     {
-        println!("Layer separation failed. Attempting spatial clustering...");
+        tracing::info!("layer separation found no views; falling back to spatial clustering");
         return separate_spatially(drawing);
     }
 
     Ok((v_xy, v_xz, v_yz))
 }
 
+#[tracing::instrument(skip_all)]
 fn separate_spatially(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
     // 1. Collect all valid geometric entities
     let mut valid_ents = Vec::new();
@@ -63,7 +67,10 @@ fn separate_spatially(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
     }
 
     if valid_ents.is_empty() {
-        bail!("No geometry found in drawing");
+        return Err(ReconstructionError::ViewsNotFound {
+            considered: drawing.entities.len(),
+            reason: "drawing contains no geometric entities".to_string(),
+        });
     }
 
     // 2. Simple clustering: Group by connectivity or proximity
@@ -71,8 +78,6 @@ fn separate_spatially(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
     // Assuming 3 distinct views separated by whitespace.
 
     // We can merge entities that are close to each other.
-    let mut groups: Vec<Vec<cadconvert_core::model::Entity2D>> = Vec::new();
-
     // Naive O(N^2) merge loop (acceptable for N < 20000)
     // Or use a grid? Let's use bounding box expansion intersection.
     let mut definitions: Vec<(
@@ -108,14 +113,14 @@ fn separate_spatially(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
     }
 
     // We hope for exactly 3 groups.
-    println!("Found {} spatial clusters.", definitions.len());
+    tracing::debug!(clusters = definitions.len(), "spatial clustering");
 
     // If not 3, try K-Means fallback if we have just 1 giant cluster
     if definitions.len() == 1 {
-        println!("Only 1 cluster found. Trying K-Means(k=3) force split...");
+        tracing::info!("only 1 cluster found; forcing k-means (k=3) split");
         definitions = run_kmeans_k3(&definitions[0].1);
     } else if definitions.len() == 2 {
-        println!("Only 2 clusters found. Splitting the largest one...");
+        tracing::info!("only 2 clusters found; splitting the largest one");
         // Find largest
         let (max_idx, _) = definitions
             .iter()
@@ -127,11 +132,11 @@ fn separate_spatially(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
         let split_clusters = run_kmeans_k2(&large_cluster.1);
         if split_clusters.len() == 2 {
             definitions.extend(split_clusters);
-            println!("Split successful. Now have {} clusters.", definitions.len());
+            tracing::debug!(clusters = definitions.len(), "split successful");
         } else {
             // Split failed? logic error?
             definitions.push(large_cluster);
-            println!("Split failed to produce 2 distinct groups.");
+            tracing::warn!("k-means split failed to produce 2 distinct groups");
         }
     }
 
@@ -139,13 +144,13 @@ fn separate_spatially(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
         // Fallback: Just take the 3 largest groups?
         definitions.sort_by_key(|g| std::cmp::Reverse(g.1.len()));
         if definitions.len() > 3 {
-            println!("Using top 3 largest clusters.");
+            tracing::info!(clusters = definitions.len(), "using the 3 largest clusters");
             definitions.truncate(3);
         } else if definitions.len() < 3 {
-            bail!(
-                "Found only {} clusters. Need 3 views (XY, XZ, YZ).",
-                definitions.len()
-            );
+            return Err(ReconstructionError::ViewCountMismatch {
+                found: definitions.len(),
+                expected: 3,
+            });
         }
     }
 
@@ -170,7 +175,7 @@ fn separate_spatially(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
     // The other two (0 and 1) are Front and Side.
     // Side is usually Right of Front.
     // Sort remaining by X.
-    let mut bottom_row = vec![indices[0], indices[1]];
+    let mut bottom_row = [indices[0], indices[1]];
     bottom_row.sort_by(|&a, &b| centers[a].x.partial_cmp(&centers[b].x).unwrap());
 
     let front_idx = bottom_row[0]; // Left-most of bottom row
@@ -183,9 +188,11 @@ fn separate_spatially(drawing: &Drawing2D) -> Result<(View2D, View2D, View2D)> {
     // Let's refine based on X alignment if possible.
     // But failing that, simple position is best guess.
 
-    println!(
-        "Assigned views: Top (Cluster {}), Front (Cluster {}), Side (Cluster {})",
-        top_idx, front_idx, side_idx
+    tracing::debug!(
+        top = top_idx,
+        front = front_idx,
+        side = side_idx,
+        "assigned views to clusters"
     );
 
     let mut v_xy = View2D::new(ViewPlane::XY);
@@ -226,7 +233,7 @@ fn run_kmeans_k3(
     // C2 (Front): Bottom-Left -> (min + w*0.25, min + h*0.25)
     // C3 (Side): Bottom-Right -> (max - w*0.25, min + h*0.25)
 
-    let mut centers = vec![
+    let mut centers = [
         cadconvert_core::geom::Vec2::new(min.x + w * 0.25, max.y - h * 0.25), // Top
         cadconvert_core::geom::Vec2::new(min.x + w * 0.25, min.y + h * 0.25), // Front
         cadconvert_core::geom::Vec2::new(max.x - w * 0.25, min.y + h * 0.25), // Side
@@ -236,15 +243,15 @@ fn run_kmeans_k3(
     let mut assignments = vec![0; entities.len()];
     for _iter in 0..10 {
         // Assign
-        let mut sums = vec![cadconvert_core::geom::Vec2::new(0.0, 0.0); 3];
-        let mut counts = vec![0; 3];
+        let mut sums = [cadconvert_core::geom::Vec2::new(0.0, 0.0); 3];
+        let mut counts = [0; 3];
 
         for (i, ent) in entities.iter().enumerate() {
             let c = ent.bbox().center();
             let mut best_dist = f64::INFINITY;
             let mut best_k = 0;
-            for k in 0..3 {
-                let d = (c.x - centers[k].x).hypot(c.y - centers[k].y);
+            for (k, center) in centers.iter().enumerate() {
+                let d = (c.x - center.x).hypot(c.y - center.y);
                 if d < best_dist {
                     best_dist = d;
                     best_k = k;
@@ -331,15 +338,15 @@ fn run_kmeans_k2(
     // Iterate
     let mut assignments = vec![0; entities.len()];
     for _iter in 0..10 {
-        let mut sums = vec![cadconvert_core::geom::Vec2::new(0.0, 0.0); 2];
-        let mut counts = vec![0; 2];
+        let mut sums = [cadconvert_core::geom::Vec2::new(0.0, 0.0); 2];
+        let mut counts = [0; 2];
 
         for (i, ent) in entities.iter().enumerate() {
             let c = ent.bbox().center();
             let mut best_dist = f64::INFINITY;
             let mut best_k = 0;
-            for k in 0..2 {
-                let d = (c.x - centers[k].x).hypot(c.y - centers[k].y);
+            for (k, center) in centers.iter().enumerate() {
+                let d = (c.x - center.x).hypot(c.y - center.y);
                 if d < best_dist {
                     best_dist = d;
                     best_k = k;
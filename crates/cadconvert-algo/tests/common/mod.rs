@@ -0,0 +1,81 @@
+//! Drawings shared by the pipeline tests and the topology benchmark.
+#![allow(dead_code)]
+
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{
+    Drawing2D, Entity2D, EntityKind, ImportLog, LineSeg2D, Primitive2D, Provenance, Style, Units,
+};
+
+/// An object line on no layer.
+pub fn line(id: u64, a: Vec2, b: Vec2) -> Entity2D {
+    Entity2D {
+        id,
+        kind: EntityKind::Object,
+        primitive: Primitive2D::Line(LineSeg2D { a, b }),
+        style: Style::default(),
+        source: Provenance::default(),
+    }
+}
+
+/// `count` lines scattered pseudo-randomly over a 50 x 40 box, crossing and
+/// overlapping each other.
+pub fn scattered_lines(count: u64) -> Vec<Entity2D> {
+    (0..count)
+        .map(|k| {
+            let t = k as f64;
+            line(
+                k,
+                Vec2::new((t * 7.3) % 50.0, (t * 3.1) % 40.0),
+                Vec2::new((t * 2.9) % 50.0, (t * 5.7) % 40.0),
+            )
+        })
+        .collect()
+}
+
+/// Four object lines around a `size` square with its corner at `origin`.
+pub fn square(id: &mut u64, layer: &str, origin: Vec2, size: f64) -> Vec<Entity2D> {
+    let corners = [
+        Vec2::new(origin.x, origin.y),
+        Vec2::new(origin.x + size, origin.y),
+        Vec2::new(origin.x + size, origin.y + size),
+        Vec2::new(origin.x, origin.y + size),
+    ];
+    (0..4)
+        .map(|i| {
+            *id += 1;
+            Entity2D {
+                id: *id,
+                kind: EntityKind::Object,
+                primitive: Primitive2D::Line(LineSeg2D {
+                    a: corners[i],
+                    b: corners[(i + 1) % 4],
+                }),
+                style: Style {
+                    layer: Some(layer.to_string()),
+                    ..Style::default()
+                },
+                source: Provenance::default(),
+            }
+        })
+        .collect()
+}
+
+/// A 10 mm cube, one square per view layer.
+pub fn cube_drawing() -> Drawing2D {
+    scaled_cube_drawing(10.0, Units::Millimeters)
+}
+
+pub fn scaled_cube_drawing(size: f64, units: Units) -> Drawing2D {
+    let mut id = 0;
+    let mut entities = Vec::new();
+    for layer in ["TOP", "FRONT", "SIDE"] {
+        entities.extend(square(&mut id, layer, Vec2::new(0.0, 0.0), size));
+    }
+    Drawing2D {
+        units,
+        entities,
+        dims: Vec::new(),
+        texts: Vec::new(),
+        import_log: ImportLog::default(),
+    }
+}
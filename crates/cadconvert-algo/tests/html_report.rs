@@ -0,0 +1,132 @@
+mod common;
+
+use cadconvert_algo::{
+    reconstruct_solid, render_html_report, validate_step, ProgressHandle, ReconstructionConfig,
+    ValidationReport,
+};
+use cadconvert_core::analysis::{AnalysisConfig, Analyzer};
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{
+    DimensionEntity, Drawing2D, ImportLog, Provenance, Style, TextAlign, TextEntity, Units,
+};
+use cadconvert_core::report::Warning;
+use cadconvert_core::step::StepFile;
+use cadconvert_core::tolerance::Tolerances;
+use common::square;
+
+/// A 10 mm cube in third angle: front at the origin, top above, right beside.
+fn cube_sheet() -> Drawing2D {
+    let mut id = 0;
+    let mut entities = Vec::new();
+    entities.extend(square(&mut id, "FRONT", Vec2::new(0.0, 0.0), 10.0));
+    entities.extend(square(&mut id, "TOP", Vec2::new(0.0, 20.0), 10.0));
+    entities.extend(square(&mut id, "SIDE", Vec2::new(20.0, 0.0), 10.0));
+    let dim = |id, text: &str, measurement, at| DimensionEntity {
+        id,
+        raw_type: Some(0),
+        text: Some(text.to_string()),
+        measurement,
+        at,
+        definition: None,
+        style: Style::default(),
+        source: Provenance::default(),
+    };
+    Drawing2D {
+        units: Units::Millimeters,
+        entities,
+        dims: vec![
+            dim(100, "<>", Some(10.0), Some(Vec2::new(5.0, -3.0))),
+            dim(101, "12", Some(10.0), Some(Vec2::new(-3.0, 5.0))),
+            dim(102, "A", None, None),
+        ],
+        texts: vec![TextEntity {
+            id: 200,
+            text: "Bracket <rev A> & co".to_string(),
+            at: Vec2::new(0.0, -8.0),
+            height: Some(1.5),
+            rotation_deg: 90.0,
+            align: TextAlign::Center,
+            style: Style::default(),
+            source: Provenance::default(),
+        }],
+        import_log: ImportLog::default(),
+    }
+}
+
+fn analysis(drawing: &Drawing2D) -> cadconvert_core::report::AnalysisReport {
+    Analyzer::new(AnalysisConfig {
+        min_cluster_entities: 1,
+        ..AnalysisConfig::default()
+    })
+    .analyze("dxf", drawing)
+}
+
+#[test]
+fn report_draws_and_links_everything() {
+    let drawing = cube_sheet();
+    let mut report = analysis(&drawing);
+    report.warnings.push(Warning {
+        code: "unplaced".to_string(),
+        message: "neither drawn nor a dimension".to_string(),
+        entity_ids: vec![999],
+    });
+    assert_eq!(3, report.view_clusters.len());
+    assert!(report.view_assignment.is_some());
+
+    let html = render_html_report("cube", &drawing, &report, None);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.trim_end().ends_with("</html>"));
+    assert_eq!(1, html.matches("<svg").count());
+    for id in 1..=12 {
+        assert!(
+            html.contains(&format!("id=\"e{id}\"")),
+            "entity {id} not drawn"
+        );
+    }
+    for role in ["FRONT", "TOP", "RIGHT"] {
+        assert!(
+            html.contains(&format!(">{role}</text>")),
+            "{role} not labelled"
+        );
+    }
+    assert_eq!(3, html.matches("class=\"cluster\"").count());
+
+    // The conflict is drawn, so its warning links to the marker; the
+    // unresolved dimension has no position and links to its table row.
+    assert!(html.contains("dimensions_conflicting"));
+    assert!(html.contains("<a href=\"#e101\">"));
+    assert!(html.contains("<a href=\"#dim-102\">"));
+    assert!(html.contains("<tr id=\"dim-102\">"));
+    assert!(html.contains("neither drawn nor a dimension &mdash; #999</li>"));
+    assert!(!html.contains("href=\"#dim-999\""));
+    assert!(html.contains("Bracket &lt;rev A&gt; &amp; co"));
+    assert!(html.contains("text-anchor=\"middle\" transform=\"rotate(-90 0 8)\""));
+    assert!(html.contains("<span>dash-dot-dot: phantom</span></p>"));
+    assert!(!html.contains("Reconstruction validation"));
+}
+
+#[test]
+fn report_includes_validation_tables() {
+    let drawing = cube_sheet();
+    let cfg = ReconstructionConfig {
+        tolerances: Tolerances::for_drawing(&drawing),
+        ..ReconstructionConfig::default()
+    };
+    let model = reconstruct_solid(&drawing, &cfg, &ProgressHandle::new()).unwrap();
+    let step = StepFile::parse(&model.content).unwrap();
+    let validation = validate_step(&step, &drawing, &cfg.tolerances).unwrap();
+    assert!(validation.is_valid(), "{validation:?}");
+
+    let html = render_html_report("cube", &drawing, &analysis(&drawing), Some(&validation));
+    assert!(html.contains("Reconstruction validation"));
+    assert!(html.contains("matches the drawing"));
+    assert!(html.contains("<tr><th>Edges</th><td>12</td><td>12</td></tr>"));
+
+    let failed = ValidationReport {
+        unmatched_vertices: vec![[1.0, 2.0, 3.0]],
+        ..validation
+    };
+    let html = render_html_report("cube", &drawing, &analysis(&drawing), Some(&failed));
+    assert!(html.contains("does not match the drawing"));
+    assert!(html.contains("<li>(1, 2, 3)</li>"));
+}
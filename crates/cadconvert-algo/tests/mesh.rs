@@ -0,0 +1,175 @@
+mod common;
+
+use cadconvert_algo::mesh::triangulate;
+use cadconvert_algo::solid_builder::build_solid;
+use cadconvert_algo::structs::{LambdaRow, ThetaEdge, ViewPlane};
+use cadconvert_algo::{
+    reconstruct_mesh, MeshFormat, ProgressHandle, ReconstructionConfig, ReconstructionError,
+};
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{Circle2D, Primitive2D, Units};
+use cadconvert_core::tolerance::Tolerances;
+use common::cube_drawing;
+use nalgebra::{Point3, Vector3};
+
+/// An axis-aligned box's 8 corners (in `lambda`) and 12 edges (in `theta`).
+fn add_box(lambda: &mut Vec<LambdaRow>, theta: &mut Vec<ThetaEdge>, min: [f64; 3], max: [f64; 3]) {
+    let base = lambda.len();
+    for i in 0..8 {
+        let pick = |axis: usize| {
+            if i & (1 << axis) == 0 {
+                min[axis]
+            } else {
+                max[axis]
+            }
+        };
+        lambda.push(LambdaRow {
+            p3: Point3::new(pick(0), pick(1), pick(2)),
+            v_xy_id: 0,
+            v_xz_id: 0,
+            v_yz_id: 0,
+        });
+    }
+    for i in 0..8 {
+        for axis in 0..3 {
+            let j = i | (1 << axis);
+            if j != i {
+                theta.push(ThetaEdge {
+                    start_lambda_idx: base + i,
+                    end_lambda_idx: base + j,
+                });
+            }
+        }
+    }
+}
+
+fn assert_outward(mesh: &cadconvert_algo::Mesh, center: Vector3<f64>) {
+    for t in &mesh.triangles {
+        let [a, b, c] = t.map(|i| Vector3::from(mesh.positions[i as usize]));
+        let winding = (b - a).cross(&(c - a));
+        let normal = Vector3::from(mesh.normals[t[0] as usize]);
+        assert!(winding.dot(&normal) > 0.0, "winding disagrees with normal");
+        // Good enough for convex parts.
+        assert!(
+            normal.dot(&((a + b + c) / 3.0 - center)) > 0.0,
+            "normal points inwards"
+        );
+    }
+}
+
+#[test]
+fn cube_meshes_into_twelve_outward_triangles() {
+    let drawing = cube_drawing();
+    let cfg = ReconstructionConfig {
+        tolerances: Tolerances::for_drawing(&drawing),
+        ..ReconstructionConfig::default()
+    };
+    let mesh = reconstruct_mesh(&drawing, &cfg, &ProgressHandle::new()).unwrap();
+
+    assert_eq!(12, mesh.triangles.len());
+    assert!((mesh.volume() - 1000.0).abs() < 1e-9);
+    assert_outward(&mesh, Vector3::new(5.0, 5.0, 5.0));
+}
+
+#[test]
+fn curved_views_are_not_meshed() {
+    let mut drawing = cube_drawing();
+    let mut hole = drawing.entities[0].clone();
+    hole.id = 100;
+    hole.primitive = Primitive2D::Circle(Circle2D {
+        center: Vec2::new(5.0, 5.0),
+        radius: 2.0,
+    });
+    drawing.entities.push(hole);
+    let cfg = ReconstructionConfig {
+        tolerances: Tolerances::for_drawing(&drawing),
+        ..ReconstructionConfig::default()
+    };
+
+    let err = reconstruct_mesh(&drawing, &cfg, &ProgressHandle::new()).unwrap_err();
+    assert!(matches!(
+        err,
+        ReconstructionError::CurvedGeometry {
+            plane: ViewPlane::XY,
+            entity: 100
+        }
+    ));
+}
+
+#[test]
+fn boss_footprint_becomes_a_hole_not_a_face() {
+    let (mut lambda, mut theta) = (Vec::new(), Vec::new());
+    add_box(&mut lambda, &mut theta, [0.0; 3], [10.0; 3]);
+    add_box(&mut lambda, &mut theta, [3.0, 3.0, 10.0], [7.0, 7.0, 13.0]);
+
+    let solid = build_solid(&lambda, &theta, &Tolerances::default()).unwrap();
+    // 5 cube sides, the top with a hole, 4 boss sides and the boss top.
+    assert_eq!(11, solid.faces.len());
+    let top = solid
+        .faces
+        .iter()
+        .find(|f| !f.holes.is_empty())
+        .expect("face with a hole");
+    assert!((top.normal - Vector3::z()).norm() < 1e-12);
+
+    let mesh = triangulate(&solid, Units::Millimeters);
+    assert!((mesh.volume() - 1048.0).abs() < 1e-9);
+    let area: f64 = mesh
+        .triangles
+        .iter()
+        .map(|t| {
+            let [a, b, c] = t.map(|i| Vector3::from(mesh.positions[i as usize]));
+            (b - a).cross(&(c - a)).norm() / 2.0
+        })
+        .sum();
+    assert!((area - (600.0 + 4.0 * 12.0)).abs() < 1e-9);
+}
+
+#[test]
+fn writes_every_mesh_format() {
+    let (mut lambda, mut theta) = (Vec::new(), Vec::new());
+    add_box(&mut lambda, &mut theta, [0.0; 3], [2.0, 3.0, 4.0]);
+    let mesh = triangulate(
+        &build_solid(&lambda, &theta, &Tolerances::default()).unwrap(),
+        Units::Millimeters,
+    );
+    assert_outward(&mesh, Vector3::new(1.0, 1.5, 2.0));
+
+    let stl = MeshFormat::StlBinary.write(&mesh, "part");
+    assert_eq!(84 + 50 * 12, stl.len());
+    assert!(!stl.starts_with(b"solid"));
+    assert_eq!(12, u32::from_le_bytes(stl[80..84].try_into().unwrap()));
+
+    let ascii = String::from_utf8(MeshFormat::StlAscii.write(&mesh, "my part")).unwrap();
+    assert!(ascii.starts_with("solid my_part\n"));
+    assert_eq!(12, ascii.matches("facet normal").count());
+    assert!(ascii.ends_with("endsolid my_part\n"));
+
+    let obj = String::from_utf8(MeshFormat::Obj.write(&mesh, "part")).unwrap();
+    assert_eq!(12, obj.lines().filter(|l| l.starts_with("f ")).count());
+    assert_eq!(
+        mesh.positions.len(),
+        obj.lines().filter(|l| l.starts_with("v ")).count()
+    );
+
+    let glb = MeshFormat::Glb.write(&mesh, "part \"1\"\n\u{1}");
+    assert_eq!(b"glTF", &glb[0..4]);
+    assert_eq!(2, u32::from_le_bytes(glb[4..8].try_into().unwrap()));
+    assert_eq!(
+        glb.len(),
+        u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize
+    );
+    let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+    assert_eq!(0, json_len % 4);
+    assert_eq!(b"JSON", &glb[16..20]);
+    let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+    assert_eq!("part \"1\"\n\u{1}", json["nodes"][0]["name"]);
+    assert_eq!(36, json["accessors"][2]["count"]);
+    // Millimetres to metres, Z-up to Y-up.
+    let bound = |key: &str| -> Vec<f32> {
+        serde_json::from_value(json["accessors"][0][key].clone()).unwrap()
+    };
+    assert_eq!(vec![0.0, 0.0, -0.003], bound("min"));
+    assert_eq!(vec![0.002, 0.004, 0.0], bound("max"));
+    assert_eq!(b"BIN\0", &glb[24 + json_len..28 + json_len]);
+}
@@ -0,0 +1,253 @@
+mod common;
+
+use cadconvert_algo::structs::ViewPlane;
+use cadconvert_algo::{
+    reconstruct_solid, validate_step, ProgressHandle, ReconstructionConfig, ReconstructionError,
+    Stage, StepOptions,
+};
+use cadconvert_algo::{reconstruction, topology, view_separation};
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{Drawing2D, ImportLog, Units};
+use cadconvert_core::step::{wireframe_step, StepFile};
+use cadconvert_core::tolerance::Tolerances;
+use common::{cube_drawing, scaled_cube_drawing, square};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn reports_every_stage() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let progress = {
+        let seen = Arc::clone(&seen);
+        ProgressHandle::with_callback(move |stage, _| seen.lock().unwrap().push(stage))
+    };
+
+    let model = reconstruct_solid(&cube_drawing(), &ReconstructionConfig::default(), &progress)
+        .expect("reconstruction");
+    assert!(model.content.contains("EDGE_CURVE"));
+
+    let seen = seen.lock().unwrap();
+    for stage in [
+        Stage::ViewSeparation,
+        Stage::Topology,
+        Stage::Lambda,
+        Stage::Theta,
+        Stage::StepExport,
+    ] {
+        assert!(seen.contains(&stage), "missing progress for {stage:?}");
+    }
+}
+
+#[test]
+fn cancelled_handle_aborts() {
+    let progress = ProgressHandle::new();
+    progress.cancel();
+    let err = reconstruct_solid(&cube_drawing(), &ReconstructionConfig::default(), &progress)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ReconstructionError::Cancelled {
+            stage: Stage::ViewSeparation
+        }
+    ));
+}
+
+#[test]
+fn empty_drawing_reports_views_not_found() {
+    let drawing = Drawing2D {
+        units: Units::Millimeters,
+        entities: Vec::new(),
+        dims: Vec::new(),
+        texts: Vec::new(),
+        import_log: ImportLog::default(),
+    };
+    let err = reconstruct_solid(
+        &drawing,
+        &ReconstructionConfig::default(),
+        &ProgressHandle::new(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ReconstructionError::ViewsNotFound { considered: 0, .. }
+    ));
+}
+
+#[test]
+fn missing_view_reports_empty_view() {
+    let mut drawing = cube_drawing();
+    drawing
+        .entities
+        .retain(|e| e.style.layer.as_deref() != Some("SIDE"));
+    let err = reconstruct_solid(
+        &drawing,
+        &ReconstructionConfig::default(),
+        &ProgressHandle::new(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ReconstructionError::EmptyView {
+            plane: ViewPlane::YZ,
+            raw_entities: 0
+        }
+    ));
+}
+
+#[test]
+fn mismatched_views_fail_registration() {
+    let mut drawing = cube_drawing();
+    drawing
+        .entities
+        .retain(|e| e.style.layer.as_deref() != Some("SIDE"));
+    let mut id = 100;
+    drawing
+        .entities
+        .extend(square(&mut id, "SIDE", Vec2::new(0.0, 0.0), 12.0));
+    let err = reconstruct_solid(
+        &drawing,
+        &ReconstructionConfig::default(),
+        &ProgressHandle::new(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ReconstructionError::RegistrationFailed {
+            plane: ViewPlane::YZ,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn derived_tolerances_follow_drawing_scale() {
+    // A 10 mm cube drawn in metres: an absolute 1-unit match tolerance pairs every
+    // vertex with every other one.
+    let drawing = scaled_cube_drawing(0.01, Units::Meters);
+    let count_points = |tolerances: Tolerances| {
+        let cfg = ReconstructionConfig {
+            tolerances,
+            ..ReconstructionConfig::default()
+        };
+        reconstruct_solid(&drawing, &cfg, &ProgressHandle::new())
+            .expect("reconstruction")
+            .content
+            .matches("CARTESIAN_POINT")
+            .count()
+    };
+
+    assert_eq!(8, count_points(Tolerances::for_drawing(&drawing)));
+    assert!(count_points(Tolerances::default()) > 8);
+}
+
+#[test]
+fn theta_holds_cube_edges_in_sorted_order() {
+    let drawing = cube_drawing();
+    let tolerances = Tolerances::for_drawing(&drawing);
+    let (mut xy, mut xz, mut yz) = view_separation::separate_views(&drawing).unwrap();
+    for view in [&mut xy, &mut xz, &mut yz] {
+        topology::build_topology(view, &tolerances).unwrap();
+    }
+    let (lambda, theta) =
+        reconstruction::build_reconstruction(&xy, &xz, &yz, &tolerances, &ProgressHandle::new())
+            .unwrap();
+
+    assert_eq!(8, lambda.len());
+    assert_eq!(12, theta.len());
+    let pairs: Vec<_> = theta
+        .iter()
+        .map(|e| (e.start_lambda_idx, e.end_lambda_idx))
+        .collect();
+    assert!(pairs.windows(2).all(|w| w[0] < w[1]));
+    for (a, b) in pairs {
+        assert!(a < b);
+        // Cube edges are axis-aligned: exactly one coordinate changes.
+        let d = lambda[b].p3 - lambda[a].p3;
+        let moved = [d.x, d.y, d.z].iter().filter(|c| c.abs() > 1e-9).count();
+        assert_eq!(1, moved);
+    }
+}
+
+#[test]
+fn step_output_is_byte_identical_between_runs() {
+    let cfg = ReconstructionConfig {
+        step: StepOptions {
+            timestamp: Some("2024-05-01T12:00:00".to_string()),
+            author: Some("O'Brien".to_string()),
+            organization: Some("Acme".to_string()),
+            ..StepOptions::default()
+        },
+        ..ReconstructionConfig::default()
+    };
+    let run = || {
+        reconstruct_solid(&cube_drawing(), &cfg, &ProgressHandle::new())
+            .unwrap()
+            .content
+    };
+
+    let first = run();
+    assert_eq!(first, run());
+    assert!(first.contains("'2024-05-01T12:00:00',('O''Brien'),('Acme')"));
+
+    let default = reconstruct_solid(
+        &cube_drawing(),
+        &ReconstructionConfig::default(),
+        &ProgressHandle::new(),
+    )
+    .unwrap();
+    assert!(default.content.contains("'1970-01-01T00:00:00'"));
+}
+
+#[test]
+fn reconstructed_step_reads_back_and_validates() {
+    let drawing = cube_drawing();
+    let cfg = ReconstructionConfig {
+        tolerances: Tolerances::for_drawing(&drawing),
+        ..ReconstructionConfig::default()
+    };
+    let model = reconstruct_solid(&drawing, &cfg, &ProgressHandle::new()).unwrap();
+    let step = StepFile::parse(&model.content).unwrap();
+
+    assert_eq!(8, step.vertices().unwrap().len());
+    let edges = step.edges().unwrap();
+    assert_eq!(12, edges.len());
+    for edge in &edges {
+        let length: f64 = (0..3).map(|i| (edge.end[i] - edge.start[i]).powi(2)).sum();
+        assert!((length.sqrt() - 10.0).abs() < 1e-9);
+    }
+
+    let report = validate_step(&step, &drawing, &cfg.tolerances).unwrap();
+    assert!(report.is_valid(), "{report:?}");
+    assert_eq!((8, 12), (report.vertices, report.edges));
+
+    let smaller = scaled_cube_drawing(5.0, Units::Millimeters);
+    let report = validate_step(&step, &smaller, &cfg.tolerances).unwrap();
+    assert!(!report.is_valid());
+    assert!(matches!(
+        report.ensure_valid(),
+        Err(ReconstructionError::ValidationFailed { .. })
+    ));
+    assert!(!report.unmatched_vertices.is_empty());
+}
+
+#[test]
+fn validation_needs_a_wireframe() {
+    let drawing = cube_drawing();
+    let cfg = ReconstructionConfig::default();
+    // A 2D curve set has points but neither vertices nor edges.
+    let flat = wireframe_step(
+        &drawing,
+        "cube",
+        &[],
+        &cfg.tolerances,
+        &StepOptions::default(),
+    )
+    .unwrap();
+    let step = StepFile::parse(&flat).unwrap();
+    let report = validate_step(&step, &drawing, &cfg.tolerances).unwrap();
+    assert_eq!((0, 0), (report.vertices, report.edges));
+    assert!(!report.is_checked() && !report.is_valid());
+    assert!(matches!(
+        report.ensure_valid(),
+        Err(ReconstructionError::ValidationFailed { check, .. }) if check == "wireframe"
+    ));
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use cadconvert_algo::structs::{View2D, ViewPlane};
+use cadconvert_algo::topology::{build_topology, build_topology_all_pairs};
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::Entity2D;
+use cadconvert_core::tolerance::Tolerances;
+use common::{line, scattered_lines};
+
+fn view_of(entities: Vec<Entity2D>) -> View2D {
+    let mut view = View2D::new(ViewPlane::XY);
+    view.raw_entities = entities;
+    build_topology(&mut view, &Tolerances::default()).unwrap();
+    view
+}
+
+#[test]
+fn splits_crossing_lines_at_every_intersection() {
+    let mut entities = Vec::new();
+    for k in 0..5 {
+        let c = k as f64;
+        entities.push(line(k, Vec2::new(-1.0, c), Vec2::new(5.0, c)));
+        entities.push(line(10 + k, Vec2::new(c, -1.0), Vec2::new(c, 5.0)));
+    }
+    let view = view_of(entities);
+
+    // 25 crossings + 20 free endpoints; each line is cut into 6 pieces.
+    assert_eq!(45, view.vertices.len());
+    assert_eq!(60, view.edges.len());
+}
+
+#[test]
+fn snaps_nearly_coincident_endpoints() {
+    let view = view_of(vec![
+        line(1, Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+        line(2, Vec2::new(10.00001, 0.00002), Vec2::new(10.0, 10.0)),
+        line(3, Vec2::new(10.0, 10.0), Vec2::new(0.0, 0.0)),
+    ]);
+    assert_eq!(3, view.vertices.len());
+    assert_eq!(3, view.edges.len());
+}
+
+#[test]
+fn output_is_deterministic() {
+    let entities = scattered_lines(200);
+    let a = view_of(entities.clone());
+    let b = view_of(entities);
+    assert_eq!(a.vertices.len(), b.vertices.len());
+    for (va, vb) in a.vertices.iter().zip(&b.vertices) {
+        assert_eq!(va.point, vb.point);
+    }
+    let ea: Vec<_> = a.edges.iter().map(|e| (e.start, e.end)).collect();
+    let eb: Vec<_> = b.edges.iter().map(|e| (e.start, e.end)).collect();
+    assert_eq!(ea, eb);
+}
+
+#[test]
+fn rtree_sweep_matches_all_pairs() {
+    let entities = scattered_lines(200);
+    let a = view_of(entities.clone());
+    let mut b = View2D::new(ViewPlane::XY);
+    b.raw_entities = entities;
+    build_topology_all_pairs(&mut b, &Tolerances::default()).unwrap();
+
+    let pa: Vec<_> = a.vertices.iter().map(|v| v.point).collect();
+    let pb: Vec<_> = b.vertices.iter().map(|v| v.point).collect();
+    assert_eq!(pa, pb);
+    let ea: Vec<_> = a.edges.iter().map(|e| (e.start, e.end)).collect();
+    let eb: Vec<_> = b.edges.iter().map(|e| (e.start, e.end)).collect();
+    assert_eq!(ea, eb);
+}
+
+#[test]
+fn merges_collinear_overlaps_and_duplicates() {
+    let view = view_of(vec![
+        line(1, Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+        line(2, Vec2::new(4.0, 0.0), Vec2::new(14.0, 0.0)),
+        line(3, Vec2::new(10.0, 0.0), Vec2::new(0.0, 0.0)),
+    ]);
+    // 0 - 4 - 10 - 14 along the x axis, each piece once.
+    assert_eq!(4, view.vertices.len());
+    assert_eq!(3, view.edges.len());
+}
+
+#[test]
+fn splits_at_t_junction_within_tolerance() {
+    let view = view_of(vec![
+        line(1, Vec2::new(0.0, 0.0), Vec2::new(100.0, 0.0)),
+        // Stem stops 5e-5 short of the bar.
+        line(2, Vec2::new(50.0, 0.00005), Vec2::new(50.0, 20.0)),
+    ]);
+    assert_eq!(4, view.vertices.len());
+    assert_eq!(3, view.edges.len());
+    let junction = view
+        .vertices
+        .iter()
+        .find(|v| (v.point.x - 50.0).abs() < 1e-9 && v.point.y < 1.0)
+        .expect("junction vertex");
+    let degree = view
+        .edges
+        .iter()
+        .filter(|e| e.start == junction.id || e.end == junction.id)
+        .count();
+    assert_eq!(3, degree);
+}
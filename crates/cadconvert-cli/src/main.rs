@@ -1,18 +1,32 @@
 use anyhow::{bail, Context, Result};
+use cadconvert_algo::MeshFormat;
 use cadconvert_core::analysis::{AnalysisConfig, Analyzer};
-use clap::{Parser, Subcommand};
+use cadconvert_core::model::Drawing2D;
+use cadconvert_core::normalize::NormalizeConfig;
+use cadconvert_core::schema::{self, DocumentKind};
+use cadconvert_core::step::{Schema, StepFile, StepOptions};
+use cadconvert_core::tolerance::Tolerances;
+use cadconvert_import_dxf::{DxfImportOptions, DxfSpace};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Parser)]
 #[command(name = "cadconvert")]
 #[command(about = "Deterministic 2D drawing analysis + 3D reconstruction (WIP).")]
 struct Cli {
+    /// Increase log verbosity on stderr (-v debug, -vv trace).
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Only log warnings and errors.
+    #[arg(short, long, global = true)]
+    quiet: bool,
     #[command(subcommand)]
     cmd: Command,
 }
 
 #[derive(Debug, Subcommand)]
 enum Command {
+    /// Analyze a drawing: views, entity kinds and a JSON report.
     Analyze {
         input: PathBuf,
         #[arg(long)]
@@ -21,40 +35,255 @@ enum Command {
         dump_drawing: Option<PathBuf>,
         #[arg(long)]
         step: Option<PathBuf>,
+        /// Write the normalized drawing back out as DXF.
+        #[arg(long)]
+        dxf: Option<PathBuf>,
+        /// Draw clusters, view roles and dimension problems on extra layers of
+        /// the `--dxf` output.
+        #[arg(long, requires = "dxf")]
+        overlay: bool,
+        /// Write an HTML report with the drawing, warnings and dimension checks.
+        #[arg(long)]
+        html: Option<PathBuf>,
         #[arg(long, default_value_t = 0.02)]
         view_gap_factor: f64,
         #[arg(long, default_value_t = 10)]
         min_cluster_entities: usize,
+        #[command(flatten)]
+        tolerances: ToleranceArgs,
+        #[command(flatten)]
+        step_options: StepArgs,
+        #[command(flatten)]
+        dxf_options: DxfArgs,
     },
+    /// Reconstruct a solid from the drawing's views and write it as STEP.
     Step {
         input: PathBuf,
         output: PathBuf,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Step)]
+        format: OutputFormat,
+        #[command(flatten)]
+        tolerances: ToleranceArgs,
+        #[command(flatten)]
+        step_options: StepArgs,
+        #[command(flatten)]
+        dxf_options: DxfArgs,
     },
+    /// Summarize a STEP file and, with --drawing, check it against the drawing's views.
+    Validate {
+        input: PathBuf,
+        /// Drawing the STEP file should have been reconstructed from.
+        #[arg(long)]
+        drawing: Option<PathBuf>,
+        /// Write an HTML report of the drawing with the validation results.
+        #[arg(long, requires = "drawing")]
+        html: Option<PathBuf>,
+        #[command(flatten)]
+        tolerances: ToleranceArgs,
+        #[command(flatten)]
+        dxf_options: DxfArgs,
+    },
+    /// Print the JSON Schema of `report.json` or `drawing.json`.
+    Schema {
+        #[arg(value_enum)]
+        document: DocumentArg,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DocumentArg {
+    Report,
+    Drawing,
+}
+
+/// STEP schema and header fields.
+#[derive(Debug, Args)]
+struct StepArgs {
+    #[arg(long, value_enum, default_value_t = SchemaArg::Ap214)]
+    schema: SchemaArg,
+    /// ISO 8601 time stamp for the STEP header (default: the Unix epoch, so
+    /// identical input gives identical files).
+    #[arg(long)]
+    timestamp: Option<String>,
+    #[arg(long)]
+    author: Option<String>,
+    #[arg(long)]
+    organization: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// STEP wireframe.
+    Step,
+    /// Binary STL mesh.
+    Stl,
+    /// ASCII STL mesh.
+    StlAscii,
+    /// Wavefront OBJ mesh.
+    Obj,
+    /// Binary glTF 2.0 mesh.
+    Glb,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SchemaArg {
+    Ap203,
+    Ap214,
+    Ap242,
+}
+
+impl StepArgs {
+    fn options(&self) -> StepOptions {
+        StepOptions {
+            schema: match self.schema {
+                SchemaArg::Ap203 => Schema::Ap203,
+                SchemaArg::Ap214 => Schema::Ap214,
+                SchemaArg::Ap242 => Schema::Ap242,
+            },
+            timestamp: self.timestamp.clone(),
+            author: self.author.clone(),
+            organization: self.organization.clone(),
+        }
+    }
+}
+
+/// Which space of a DXF input to import.
+#[derive(Debug, Args)]
+struct DxfArgs {
+    /// Import a paper-space layout, by name or else the active one, instead
+    /// of model space: the sheet with model space seen through its viewports.
+    #[arg(long, num_args = 0..=1, value_name = "NAME")]
+    layout: Option<Option<String>>,
+}
+
+impl DxfArgs {
+    fn options(&self) -> DxfImportOptions {
+        DxfImportOptions {
+            space: match &self.layout {
+                None => DxfSpace::Model,
+                Some(name) => DxfSpace::Layout(name.clone()),
+            },
+        }
+    }
+}
+
+/// Overrides for the tolerances derived from the drawing (in drawing units).
+#[derive(Debug, Args)]
+struct ToleranceArgs {
+    /// Points closer than this are merged.
+    #[arg(long)]
+    snap_tol: Option<f64>,
+    /// Largest disagreement between views' projections of one 3D point.
+    #[arg(long)]
+    match_tol: Option<f64>,
+}
+
+impl ToleranceArgs {
+    fn resolve(&self, drawing: &Drawing2D) -> Result<Tolerances> {
+        let mut tolerances = Tolerances::for_drawing(drawing);
+        if let Some(snap) = self.snap_tol {
+            tolerances.snap = snap;
+        }
+        if let Some(view_match) = self.match_tol {
+            tolerances.view_match = view_match;
+        }
+        if !tolerances.is_valid() {
+            bail!("tolerances must be positive and finite: {tolerances:?}");
+        }
+        tracing::debug!(
+            snap = tolerances.snap,
+            view_match = tolerances.view_match,
+            "tolerances"
+        );
+        Ok(tolerances)
+    }
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    init_tracing(cli.verbose, cli.quiet);
     match cli.cmd {
         Command::Analyze {
             input,
             report,
             dump_drawing,
             step,
+            dxf,
+            overlay,
+            html,
             view_gap_factor,
             min_cluster_entities,
+            tolerances,
+            step_options,
+            dxf_options,
         } => analyze(
             &input,
-            report.as_deref(),
-            dump_drawing.as_deref(),
-            step.as_deref(),
-            view_gap_factor,
-            min_cluster_entities,
+            &AnalyzeOutputs {
+                report: report.as_deref(),
+                dump_drawing: dump_drawing.as_deref(),
+                step: step.as_deref(),
+                dxf: dxf.as_deref(),
+                overlay,
+                html: html.as_deref(),
+            },
+            AnalysisConfig {
+                view_gap_factor,
+                min_cluster_entities,
+                ..AnalysisConfig::default()
+            },
+            &tolerances,
+            &step_options.options(),
+            &dxf_options.options(),
         ),
-        Command::Step { input, output } => reconstruct(&input, &output),
+        Command::Step {
+            input,
+            output,
+            format,
+            tolerances,
+            step_options,
+            dxf_options,
+        } => reconstruct(
+            &input,
+            &output,
+            format,
+            &tolerances,
+            step_options.options(),
+            &dxf_options.options(),
+        ),
+        Command::Validate {
+            input,
+            drawing,
+            html,
+            tolerances,
+            dxf_options,
+        } => validate(
+            &input,
+            drawing.as_deref(),
+            html.as_deref(),
+            &tolerances,
+            &dxf_options.options(),
+        ),
+        Command::Schema { document } => {
+            let kind = match document {
+                DocumentArg::Report => DocumentKind::Report,
+                DocumentArg::Drawing => DocumentKind::Drawing,
+            };
+            let schema = serde_json::to_string_pretty(&schema::json_schema(kind))
+                .context("serialize schema")?;
+            println!("{schema}");
+            Ok(())
+        }
     }
 }
 
-fn reconstruct(input: &Path, output: &Path) -> Result<()> {
+fn reconstruct(
+    input: &Path,
+    output: &Path,
+    format: OutputFormat,
+    tolerance_args: &ToleranceArgs,
+    step: StepOptions,
+    dxf_options: &DxfImportOptions,
+) -> Result<()> {
     ensure_input_file(input)?;
     let ext = input
         .extension()
@@ -62,29 +291,168 @@ fn reconstruct(input: &Path, output: &Path) -> Result<()> {
         .unwrap_or("")
         .to_ascii_lowercase();
 
-    let (_format, drawing) = match ext.as_str() {
-        "dxf" => ("dxf", cadconvert_import_dxf::import_dxf(input)?),
+    let (_format, mut drawing) = match ext.as_str() {
+        "dxf" => (
+            "dxf",
+            cadconvert_import_dxf::import_dxf_with(input, dxf_options)?,
+        ),
+        "json" | "jsonl" => ("json", import_drawing_json(input)?),
         _ => bail!("Unsupported input extension for reconstruction: .{ext}"),
     };
 
-    println!("Reconstructing 3D solid from 2D views...");
-    let step_model = cadconvert_algo::reconstruct_solid(&drawing)?;
-    
+    let tolerances = tolerance_args.resolve(&drawing)?;
+    let normalize_cfg = NormalizeConfig::default().with_tolerances(&tolerances);
+    let _ = cadconvert_core::normalize::normalize_in_place(&mut drawing, &normalize_cfg);
+
+    tracing::info!("reconstructing 3D solid from 2D views");
+    let progress = cadconvert_algo::ProgressHandle::new();
+    let cfg = cadconvert_algo::ReconstructionConfig { tolerances, step };
+    let mesh_format = match format {
+        OutputFormat::Step => None,
+        OutputFormat::Stl => Some(MeshFormat::StlBinary),
+        OutputFormat::StlAscii => Some(MeshFormat::StlAscii),
+        OutputFormat::Obj => Some(MeshFormat::Obj),
+        OutputFormat::Glb => Some(MeshFormat::Glb),
+    };
+
     if let Some(parent) = output.parent() {
         std::fs::create_dir_all(parent).ok();
     }
-    step_model.save_to_file(output)?;
-    println!("Saved STEP file to {:?}", output);
+    match mesh_format {
+        None => {
+            let step_model = cadconvert_algo::reconstruct_solid(&drawing, &cfg, &progress)?;
+            step_model.save_to_file(output)?;
+            tracing::info!(path = %output.display(), "saved STEP file");
+        }
+        Some(mesh_format) => {
+            let mesh = cadconvert_algo::reconstruct_mesh(&drawing, &cfg, &progress)?;
+            let name = input.file_stem().and_then(|s| s.to_str()).unwrap_or("part");
+            std::fs::write(output, mesh_format.write(&mesh, name))
+                .with_context(|| format!("write mesh: {output:?}"))?;
+            tracing::info!(
+                path = %output.display(),
+                triangles = mesh.triangles.len(),
+                "saved {} mesh",
+                mesh_format.extension()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn validate(
+    input: &Path,
+    drawing: Option<&Path>,
+    html: Option<&Path>,
+    tolerance_args: &ToleranceArgs,
+    dxf_options: &DxfImportOptions,
+) -> Result<()> {
+    ensure_input_file(input)?;
+    let text = std::fs::read_to_string(input).with_context(|| format!("read step: {input:?}"))?;
+    let step = StepFile::parse(&text).with_context(|| format!("parse step: {input:?}"))?;
+
+    println!("entities: {}", step.len());
+    println!("schema: {}", step.header.schemas.join(", "));
+    println!("points: {}", step.points()?.len());
+    println!("vertices: {}", step.vertices()?.len());
+    println!("edges: {}", step.edges()?.len());
+    println!("faces: {}", step.faces()?.len());
+
+    let Some(drawing_path) = drawing else {
+        return Ok(());
+    };
+    ensure_input_file(drawing_path)?;
+    let ext = drawing_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let (format, raw_drawing) = match ext.as_str() {
+        "dxf" => (
+            "dxf",
+            cadconvert_import_dxf::import_dxf_with(drawing_path, dxf_options)?,
+        ),
+        "svg" => ("svg", cadconvert_import_svg::import_svg(drawing_path)?),
+        "json" | "jsonl" => ("json", import_drawing_json(drawing_path)?),
+        _ => bail!("Unsupported drawing extension: .{ext}"),
+    };
+
+    let tolerances = tolerance_args.resolve(&raw_drawing)?;
+    let normalize_cfg = NormalizeConfig::default().with_tolerances(&tolerances);
+    let mut drawing = raw_drawing.clone();
+    let _ = cadconvert_core::normalize::normalize_in_place(&mut drawing, &normalize_cfg);
+
+    let report = cadconvert_algo::validate_step(&step, &drawing, &tolerances)?;
+    println!(
+        "matched vertices: {}/{}",
+        report.vertices - report.unmatched_vertices.len(),
+        report.vertices
+    );
+    println!(
+        "matched edges: {}/{}",
+        report.edges - report.unmatched_edges.len(),
+        report.edges
+    );
+    if let Some(path) = html {
+        let analysis = Analyzer::new(AnalysisConfig {
+            tolerances: Some(tolerances),
+            ..AnalysisConfig::default()
+        })
+        .analyze(format, &raw_drawing);
+        let name = drawing_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("drawing");
+        let page = cadconvert_algo::render_html_report(name, &drawing, &analysis, Some(&report));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        std::fs::write(path, page).with_context(|| format!("write html: {path:?}"))?;
+    }
+    report.ensure_valid().with_context(|| {
+        format!(
+            "STEP does not match the drawing within {}",
+            tolerances.view_match
+        )
+    })?;
     Ok(())
 }
 
+fn init_tracing(verbose: u8, quiet: bool) {
+    let level = if quiet {
+        tracing::Level::WARN
+    } else {
+        match verbose {
+            0 => tracing::Level::INFO,
+            1 => tracing::Level::DEBUG,
+            _ => tracing::Level::TRACE,
+        }
+    };
+    // Logs go to stderr so stdout stays clean for JSON output.
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .init();
+}
+
+/// Files `analyze` writes besides the report.
+struct AnalyzeOutputs<'a> {
+    report: Option<&'a Path>,
+    dump_drawing: Option<&'a Path>,
+    step: Option<&'a Path>,
+    dxf: Option<&'a Path>,
+    overlay: bool,
+    html: Option<&'a Path>,
+}
+
 fn analyze(
     input: &Path,
-    report: Option<&Path>,
-    dump_drawing: Option<&Path>,
-    step: Option<&Path>,
-    view_gap_factor: f64,
-    min_cluster_entities: usize,
+    outputs: &AnalyzeOutputs,
+    mut cfg: AnalysisConfig,
+    tolerance_args: &ToleranceArgs,
+    step_options: &StepOptions,
+    dxf_options: &DxfImportOptions,
 ) -> Result<()> {
     ensure_input_file(input)?;
 
@@ -95,47 +463,87 @@ fn analyze(
         .to_ascii_lowercase();
 
     let (format, drawing) = match ext.as_str() {
-        "dxf" => ("dxf", cadconvert_import_dxf::import_dxf(input)?),
+        "dxf" => (
+            "dxf",
+            cadconvert_import_dxf::import_dxf_with(input, dxf_options)?,
+        ),
         "svg" => ("svg", cadconvert_import_svg::import_svg(input)?),
+        "json" | "jsonl" => ("json", import_drawing_json(input)?),
         "dwg" => bail!("DWG import not implemented yet (planned via ODA/Teigha adapter)."),
         _ => bail!("Unsupported input extension: .{ext}"),
     };
 
-    let cfg = AnalysisConfig {
-        view_gap_factor,
-        min_cluster_entities,
-        ..AnalysisConfig::default()
-    };
+    let tolerances = tolerance_args.resolve(&drawing)?;
+    cfg.tolerances = Some(tolerances);
 
+    let normalize_cfg = cfg.normalize.clone().with_tolerances(&tolerances);
     let mut normalized = drawing.clone();
-    let _ = cadconvert_core::normalize::normalize_in_place(&mut normalized, &cfg.normalize);
+    let _ = cadconvert_core::normalize::normalize_in_place(&mut normalized, &normalize_cfg);
 
-    if let Some(path) = dump_drawing {
-        let json = serde_json::to_string_pretty(&normalized).context("serialize drawing")?;
+    if let Some(path) = outputs.dump_drawing {
+        let json = if has_extension(path, "jsonl") {
+            let mut out = Vec::new();
+            schema::drawing_to_jsonl(&normalized, &mut out).context("serialize drawing")?;
+            out
+        } else {
+            schema::to_json_pretty(&normalized)
+                .context("serialize drawing")?
+                .into_bytes()
+        };
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).ok();
         }
         std::fs::write(path, &json).with_context(|| format!("write drawing: {path:?}"))?;
     }
 
-    if let Some(path) = step {
+    let analyzer = Analyzer::new(cfg);
+    let report_data = analyzer.analyze(format, &drawing);
+
+    if let Some(path) = outputs.step {
         let name = input
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("drawing");
-        let step_data = cadconvert_core::step::wireframe_step(&normalized, name);
+        let step_data = cadconvert_core::step::wireframe_step(
+            &normalized,
+            name,
+            &cadconvert_core::step::placed_views(&report_data),
+            &tolerances,
+            step_options,
+        )
+        .context("build STEP wireframe")?;
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).ok();
         }
         std::fs::write(path, &step_data).with_context(|| format!("write step: {path:?}"))?;
     }
 
-    let analyzer = Analyzer::new(cfg);
+    if let Some(path) = outputs.dxf {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        cadconvert_import_dxf::export_dxf(
+            &normalized,
+            outputs.overlay.then_some(&report_data),
+            path,
+        )?;
+    }
 
-    let report_data = analyzer.analyze(format, &drawing);
-    let json = serde_json::to_string_pretty(&report_data).context("serialize report")?;
+    if let Some(path) = outputs.html {
+        let name = input
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("drawing");
+        let page = cadconvert_algo::render_html_report(name, &normalized, &report_data, None);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        std::fs::write(path, page).with_context(|| format!("write html: {path:?}"))?;
+    }
+
+    let json = schema::to_json_pretty(&report_data).context("serialize report")?;
 
-    if let Some(path) = report {
+    if let Some(path) = outputs.report {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).ok();
         }
@@ -147,6 +555,26 @@ fn analyze(
     Ok(())
 }
 
+/// Reads a drawing written by `--dump-drawing`: `.jsonl` as JSON Lines, anything
+/// else as a single JSON document.
+fn import_drawing_json(path: &Path) -> Result<Drawing2D> {
+    let file = std::fs::File::open(path).with_context(|| format!("open drawing: {path:?}"))?;
+    let drawing = if has_extension(path, "jsonl") {
+        schema::drawing_from_jsonl(std::io::BufReader::new(file))
+    } else {
+        let json =
+            std::io::read_to_string(file).with_context(|| format!("read drawing: {path:?}"))?;
+        schema::drawing_from_json(&json)
+    };
+    drawing.with_context(|| format!("parse drawing: {path:?}"))
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|s| s.eq_ignore_ascii_case(ext))
+}
+
 fn ensure_input_file(input: &Path) -> Result<()> {
     match std::fs::metadata(input) {
         Ok(meta) => {
@@ -1,7 +1,9 @@
+use crate::dimension::{check_dimension, DimensionStatus};
 use crate::geom::BBox2;
 use crate::model::{Drawing2D, EntityKind};
 use crate::normalize::{normalize_in_place, NormalizeConfig};
 use crate::report::{AnalysisReport, StatsReport, ViewClusterReport, Warning};
+use crate::tolerance::Tolerances;
 use crate::view::assign_three_view_roles;
 use std::collections::HashMap;
 
@@ -10,6 +12,8 @@ pub struct AnalysisConfig {
     pub view_gap_factor: f64,
     pub min_cluster_entities: usize,
     pub normalize: NormalizeConfig,
+    /// `None` derives them from the drawing with [`Tolerances::for_drawing`].
+    pub tolerances: Option<Tolerances>,
 }
 
 impl Default for AnalysisConfig {
@@ -18,6 +22,7 @@ impl Default for AnalysisConfig {
             view_gap_factor: 0.02,
             min_cluster_entities: 10,
             normalize: NormalizeConfig::default(),
+            tolerances: None,
         }
     }
 }
@@ -32,8 +37,13 @@ impl Analyzer {
     }
 
     pub fn analyze(&self, input_format: &str, drawing: &Drawing2D) -> AnalysisReport {
+        let tolerances = self
+            .cfg
+            .tolerances
+            .unwrap_or_else(|| Tolerances::for_drawing(drawing));
+        let normalize_cfg = self.cfg.normalize.clone().with_tolerances(&tolerances);
         let mut normalized = drawing.clone();
-        let normalize_stats = normalize_in_place(&mut normalized, &self.cfg.normalize);
+        let normalize_stats = normalize_in_place(&mut normalized, &normalize_cfg);
 
         let extents = normalized.extents();
 
@@ -42,6 +52,7 @@ impl Analyzer {
             warnings.push(Warning {
                 code: "no_entities".to_string(),
                 message: "No drawable entities found.".to_string(),
+                entity_ids: Vec::new(),
             });
         }
 
@@ -53,6 +64,7 @@ impl Analyzer {
                 code: "views_not_detected".to_string(),
                 message: "Detected fewer than 2 view clusters; check layers/scale or clustering config."
                     .to_string(),
+                entity_ids: Vec::new(),
             });
         }
         if clusters.len() == 3 && view_assignment.is_none() {
@@ -60,6 +72,61 @@ impl Analyzer {
                 code: "view_assignment_ambiguous".to_string(),
                 message: "Detected 3 clusters but could not confidently assign front/top/right; will require user confirmation."
                     .to_string(),
+                entity_ids: Vec::new(),
+            });
+        }
+
+        let mut unresolved = Vec::new();
+        let mut conflicting = Vec::new();
+        for dim in &drawing.dims {
+            match check_dimension(dim) {
+                DimensionStatus::Ok => {}
+                DimensionStatus::Unresolved => unresolved.push(dim.id),
+                DimensionStatus::Conflict { .. } => conflicting.push(dim.id),
+            }
+        }
+        if !unresolved.is_empty() {
+            warnings.push(Warning {
+                code: "dimensions_unresolved".to_string(),
+                message: format!(
+                    "{} dimension(s) have neither a measurement nor a number in their text.",
+                    unresolved.len()
+                ),
+                entity_ids: unresolved,
+            });
+        }
+        if !conflicting.is_empty() {
+            warnings.push(Warning {
+                code: "dimensions_conflicting".to_string(),
+                message: format!(
+                    "{} dimension(s) have text that disagrees with their measurement.",
+                    conflicting.len()
+                ),
+                entity_ids: conflicting,
+            });
+        }
+
+        let log = &drawing.import_log;
+        if !log.skipped.is_empty() {
+            let counts: Vec<String> = log
+                .skipped
+                .iter()
+                .map(|(kind, n)| format!("{n} {kind}"))
+                .collect();
+            warnings.push(Warning {
+                code: "entities_skipped".to_string(),
+                message: format!("Entities not imported: {}.", counts.join(", ")),
+                entity_ids: Vec::new(),
+            });
+        }
+        if !log.projected.is_empty() {
+            warnings.push(Warning {
+                code: "entities_projected".to_string(),
+                message: format!(
+                    "{} 3D entity(ies) were flattened onto the XY plane.",
+                    log.projected.len()
+                ),
+                entity_ids: log.projected.clone(),
             });
         }
 
@@ -72,8 +139,10 @@ impl Analyzer {
                 inferred_kinds: normalize_stats.inferred_kinds,
                 dims_total: drawing.dims.len(),
                 texts_total: drawing.texts.len(),
+                skipped_entities: drawing.import_log.skipped.clone(),
             },
             extents,
+            tolerances,
             view_clusters: clusters,
             view_assignment,
             warnings,
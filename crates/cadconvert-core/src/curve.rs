@@ -0,0 +1,472 @@
+//! Evaluation of the curved primitives: points, exact bounds, lengths and
+//! tessellation. Points are exact; lengths of Béziers, ellipses and NURBS are
+//! numerical and NURBS bounds come from a fine flattening.
+
+use crate::geom::{bulge_arc, closest_on_segment, BBox2, Vec2};
+use crate::model::{Arc2D, Bezier2D, Circle2D, Ellipse2D, Nurbs2D, Polyline2D};
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+/// Deepest bisection of a Bézier or NURBS piece when flattening.
+const MAX_DEPTH: u32 = 10;
+
+impl Circle2D {
+    /// Points around the circle from angle zero back to it, with no chord
+    /// farther than `tolerance` from the curve.
+    pub fn tessellate(&self, tolerance: f64) -> Vec<Vec2> {
+        arc_points(self.center, self.radius, 0.0, TAU, tolerance)
+    }
+}
+
+impl Arc2D {
+    /// Swept angle in degrees, in (0, 360]; equal end angles are a full turn.
+    pub fn sweep_deg(&self) -> f64 {
+        sweep_deg(self.start_angle_deg, self.end_angle_deg)
+    }
+
+    pub fn point_at(&self, angle_deg: f64) -> Vec2 {
+        self.center + Vec2::from_angle(angle_deg.to_radians()) * self.radius
+    }
+
+    pub fn start_point(&self) -> Vec2 {
+        self.point_at(self.start_angle_deg)
+    }
+
+    pub fn end_point(&self) -> Vec2 {
+        self.point_at(self.start_angle_deg + self.sweep_deg())
+    }
+
+    /// The end points and whichever quadrant points the arc passes.
+    pub fn bbox(&self) -> BBox2 {
+        let mut bbox = BBox2::from_points([self.start_point(), self.end_point()]);
+        let sweep = self.sweep_deg();
+        for quadrant in [0.0, 90.0, 180.0, 270.0] {
+            if (quadrant - self.start_angle_deg).rem_euclid(360.0) <= sweep {
+                bbox.include_point(self.point_at(quadrant));
+            }
+        }
+        bbox
+    }
+
+    pub fn length(&self) -> f64 {
+        self.radius * self.sweep_deg().to_radians()
+    }
+
+    /// Points along the arc, ends included, with no chord farther than
+    /// `tolerance` from the curve.
+    pub fn tessellate(&self, tolerance: f64) -> Vec<Vec2> {
+        let start = self.start_angle_deg.to_radians();
+        let sweep = self.sweep_deg().to_radians();
+        arc_points(self.center, self.radius, start, sweep, tolerance)
+    }
+}
+
+impl Bezier2D {
+    pub fn point_at(&self, t: f64) -> Vec2 {
+        let u = 1.0 - t;
+        self.p0 * (u * u * u)
+            + self.p1 * (3.0 * u * u * t)
+            + self.p2 * (3.0 * u * t * t)
+            + self.p3 * (t * t * t)
+    }
+
+    /// The end points and wherever the curve turns back in x or y.
+    pub fn bbox(&self) -> BBox2 {
+        let mut bbox = BBox2::from_points([self.p0, self.p3]);
+        let (a, b, c) = (self.p1 - self.p0, self.p2 - self.p1, self.p3 - self.p2);
+        // The derivative is 3·((1-t)²·a + 2(1-t)t·b + t²·c).
+        for (a, b, c) in [(a.x, b.x, c.x), (a.y, b.y, c.y)] {
+            for t in quadratic_roots(a - 2.0 * b + c, 2.0 * (b - a), a) {
+                if t > 0.0 && t < 1.0 {
+                    bbox.include_point(self.point_at(t));
+                }
+            }
+        }
+        bbox
+    }
+
+    /// Length of a fine flattening.
+    pub fn length(&self) -> f64 {
+        let hull = BBox2::from_points([self.p0, self.p1, self.p2, self.p3]);
+        path_length(&self.tessellate(hull.diag() * 1e-6))
+    }
+
+    /// Points along the curve, ends included, bisected until its chords are
+    /// within `tolerance` of the curve at their middle.
+    pub fn tessellate(&self, tolerance: f64) -> Vec<Vec2> {
+        let mut out = vec![self.p0];
+        // A cubic can turn up to three times; start finer than that.
+        flatten_range(&|t| self.point_at(t), (0.0, 1.0), 4, tolerance, &mut out);
+        out
+    }
+}
+
+impl Polyline2D {
+    /// Each segment's ends and bulge, the closing one included.
+    fn segments(&self) -> impl Iterator<Item = (Vec2, Vec2, f64)> + '_ {
+        let n = self.vertices.len();
+        let count = if self.closed { n } else { n.saturating_sub(1) };
+        (0..count).map(move |i| {
+            let v0 = &self.vertices[i];
+            (v0.pos, self.vertices[(i + 1) % n].pos, v0.bulge)
+        })
+    }
+
+    /// The vertices and the extremes of any bulged segments.
+    pub fn bbox(&self) -> BBox2 {
+        let mut bbox = BBox2::from_points(self.vertices.iter().map(|v| v.pos));
+        for (p0, p1, bulge) in self.segments() {
+            if let Some((center, radius, start, sweep)) = bulge_arc(p0, p1, bulge) {
+                let (from, to) = (start + sweep.min(0.0), start + sweep.max(0.0));
+                let arc = Arc2D {
+                    center,
+                    radius,
+                    start_angle_deg: from.to_degrees(),
+                    end_angle_deg: to.to_degrees(),
+                };
+                bbox = bbox.union(&arc.bbox());
+            }
+        }
+        bbox
+    }
+
+    pub fn length(&self) -> f64 {
+        self.segments()
+            .map(|(p0, p1, bulge)| match bulge_arc(p0, p1, bulge) {
+                Some((_, radius, _, sweep)) => radius * sweep.abs(),
+                None => p0.distance(p1),
+            })
+            .sum()
+    }
+
+    /// The vertices, with bulged segments split so that no chord is farther
+    /// than `tolerance` from the arc. Closed polylines end at their first
+    /// vertex.
+    pub fn tessellate(&self, tolerance: f64) -> Vec<Vec2> {
+        let mut out: Vec<Vec2> = self.vertices.first().map(|v| v.pos).into_iter().collect();
+        for (p0, p1, bulge) in self.segments() {
+            if let Some((center, radius, start, sweep)) = bulge_arc(p0, p1, bulge) {
+                let points = arc_points(center, radius, start, sweep, tolerance);
+                out.extend_from_slice(&points[1..points.len() - 1]);
+            }
+            out.push(p1);
+        }
+        out
+    }
+}
+
+impl Ellipse2D {
+    /// The arc `center + u·cos t + v·sin t` for `t` from `start_deg` through
+    /// the signed `sweep_deg`, where `u` and `v` are any two conjugate
+    /// semi-diameters, such as the axes of an ellipse after an affine map.
+    /// `None` if they are parallel or not finite.
+    pub fn from_conjugate(
+        center: Vec2,
+        u: Vec2,
+        v: Vec2,
+        start_deg: f64,
+        sweep_deg: f64,
+    ) -> Option<Self> {
+        let cross = u.cross(v);
+        let size = u.length_squared().max(v.length_squared());
+        if !cross.is_finite() || cross.abs() <= 1e-12 * size || !(start_deg + sweep_deg).is_finite()
+        {
+            return None;
+        }
+        // The semi-diameter at t0 is the longest, so it and the one a quarter
+        // turn on are the axes.
+        let t0 = 0.5 * (2.0 * u.dot(v)).atan2(u.length_squared() - v.length_squared());
+        let (sin, cos) = t0.sin_cos();
+        let mut major = u * cos + v * sin;
+        let mut minor = v * cos - u * sin;
+        let mut offset = t0.to_degrees();
+        if minor.length_squared() > major.length_squared() {
+            (major, minor) = (minor, -major);
+            offset += 90.0;
+        }
+        let (mut start, mut sweep) = (start_deg - offset, sweep_deg);
+        // With the minor axis clockwise of the major one, the parameter runs
+        // the other way.
+        if cross < 0.0 {
+            (start, sweep) = (-start, -sweep);
+        }
+        if sweep < 0.0 {
+            (start, sweep) = (start + sweep, -sweep);
+        }
+        let (start, end) = if sweep >= 360.0 - 1e-9 {
+            (0.0, 360.0)
+        } else {
+            (start.rem_euclid(360.0), (start + sweep).rem_euclid(360.0))
+        };
+        Some(Self {
+            center,
+            major_axis: major,
+            ratio: minor.length() / major.length(),
+            start_param_deg: start,
+            end_param_deg: end,
+        })
+    }
+
+    /// End of the minor axis, relative to the center: the major axis turned a
+    /// quarter counter-clockwise and scaled by `ratio`.
+    pub fn minor_axis(&self) -> Vec2 {
+        self.major_axis.perp() * self.ratio
+    }
+
+    /// Swept parameter range in degrees, in (0, 360].
+    pub fn sweep_deg(&self) -> f64 {
+        sweep_deg(self.start_param_deg, self.end_param_deg)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sweep_deg() >= 360.0
+    }
+
+    pub fn point_at(&self, param_deg: f64) -> Vec2 {
+        let (sin, cos) = param_deg.to_radians().sin_cos();
+        self.center + self.major_axis * cos + self.minor_axis() * sin
+    }
+
+    pub fn start_point(&self) -> Vec2 {
+        self.point_at(self.start_param_deg)
+    }
+
+    pub fn end_point(&self) -> Vec2 {
+        self.point_at(self.start_param_deg + self.sweep_deg())
+    }
+
+    /// The end points and whichever extremes in x and y the arc passes.
+    pub fn bbox(&self) -> BBox2 {
+        let mut bbox = BBox2::from_points([self.start_point(), self.end_point()]);
+        let (major, minor) = (self.major_axis, self.minor_axis());
+        let sweep = self.sweep_deg();
+        // x' = -major.x·sin t + minor.x·cos t is zero at atan2(minor.x, major.x).
+        for t in [minor.x.atan2(major.x), minor.y.atan2(major.y)] {
+            for t in [t.to_degrees(), t.to_degrees() + 180.0] {
+                if (t - self.start_param_deg).rem_euclid(360.0) <= sweep {
+                    bbox.include_point(self.point_at(t));
+                }
+            }
+        }
+        bbox
+    }
+
+    /// Arc length, by Simpson's rule on the speed.
+    pub fn length(&self) -> f64 {
+        const STEPS: usize = 1024;
+        let (a2, b2) = (
+            self.major_axis.length_squared(),
+            self.minor_axis().length_squared(),
+        );
+        let speed = |t: f64| {
+            let (sin, cos) = t.sin_cos();
+            (a2 * sin * sin + b2 * cos * cos).sqrt()
+        };
+        let t0 = self.start_param_deg.to_radians();
+        let h = self.sweep_deg().to_radians() / STEPS as f64;
+        let inner: f64 = (1..STEPS)
+            .map(|i| speed(t0 + h * i as f64) * if i % 2 == 1 { 4.0 } else { 2.0 })
+            .sum();
+        (speed(t0) + inner + speed(t0 + h * STEPS as f64)) * h / 3.0
+    }
+
+    /// Points along the arc, ends included, with no chord farther than
+    /// `tolerance` from the curve.
+    pub fn tessellate(&self, tolerance: f64) -> Vec<Vec2> {
+        let sweep = self.sweep_deg();
+        // The ellipse is its major circle squashed, which only brings chords
+        // closer, so the circle's step is safe.
+        let n = arc_segments(self.major_axis.length(), sweep.to_radians(), tolerance);
+        (0..=n)
+            .map(|i| self.point_at(self.start_param_deg + sweep * i as f64 / n as f64))
+            .collect()
+    }
+}
+
+impl Nurbs2D {
+    /// Whether degree, control points, weights and knots fit together.
+    pub fn is_valid(&self) -> bool {
+        let (p, n) = (self.degree, self.control_points.len());
+        p >= 1
+            && n > p
+            && self.knots.len() == n + p + 1
+            && self.knots.iter().all(|k| k.is_finite())
+            && self.knots.windows(2).all(|w| w[0] <= w[1])
+            && self.knots[p] < self.knots[n]
+            && self
+                .control_points
+                .iter()
+                .all(|c| c.x.is_finite() && c.y.is_finite())
+            && (self.weights.is_empty()
+                || self.weights.len() == n
+                    && self.weights.iter().all(|w| w.is_finite() && *w > 0.0))
+    }
+
+    /// Parameter range the curve is defined over, if it is valid.
+    pub fn domain(&self) -> Option<(f64, f64)> {
+        self.is_valid().then(|| {
+            (
+                self.knots[self.degree],
+                self.knots[self.control_points.len()],
+            )
+        })
+    }
+
+    /// The point at `u`, clamped to the domain; `None` if the spline is not
+    /// valid.
+    pub fn point_at(&self, u: f64) -> Option<Vec2> {
+        let (lo, hi) = self.domain()?;
+        Some(self.eval(u.clamp(lo, hi)))
+    }
+
+    /// De Boor's algorithm on weighted points, for a valid spline and `u`
+    /// in its domain.
+    fn eval(&self, u: f64) -> Vec2 {
+        let (p, n, knots) = (self.degree, self.control_points.len(), &self.knots);
+        let k = (p..n)
+            .rev()
+            .find(|&k| knots[k] <= u && knots[k] < knots[k + 1])
+            .unwrap_or(p);
+        let weight = |i: usize| self.weights.get(i).copied().unwrap_or(1.0);
+        let mut d: Vec<[f64; 3]> = (k - p..=k)
+            .map(|i| {
+                let (c, w) = (self.control_points[i], weight(i));
+                [c.x * w, c.y * w, w]
+            })
+            .collect();
+        for r in 1..=p {
+            for j in (r..=p).rev() {
+                let i = j + k - p;
+                let den = knots[i + p + 1 - r] - knots[i];
+                let alpha = if den > 0.0 { (u - knots[i]) / den } else { 0.0 };
+                let prev = d[j - 1];
+                for (c, q) in d[j].iter_mut().zip(prev) {
+                    *c = (1.0 - alpha) * q + alpha * *c;
+                }
+            }
+        }
+        let [x, y, w] = d[p];
+        Vec2::new(x / w, y / w)
+    }
+
+    /// Points along the curve, ends included, with each knot span bisected
+    /// until its chords are within `tolerance` of the curve at their middle.
+    /// An invalid spline gives its control polygon.
+    pub fn tessellate(&self, tolerance: f64) -> Vec<Vec2> {
+        if !self.is_valid() {
+            return self.control_points.clone();
+        }
+        let (p, n) = (self.degree, self.control_points.len());
+        let f = |u: f64| self.eval(u);
+        let mut out = vec![f(self.knots[p])];
+        for k in p..n {
+            let (u0, u1) = (self.knots[k], self.knots[k + 1]);
+            if u0 < u1 {
+                // A span can turn up to its degree times; start finer than that.
+                flatten_range(&f, (u0, u1), p + 1, tolerance, &mut out);
+            }
+        }
+        out
+    }
+
+    /// Bounds of the curve, to a ten-thousandth of its control polygon's.
+    pub fn bbox(&self) -> BBox2 {
+        let hull = BBox2::from_points(self.control_points.iter().copied());
+        if !self.is_valid() {
+            return hull;
+        }
+        BBox2::from_points(self.tessellate(hull.diag() * 1e-4))
+    }
+
+    /// Length of a fine flattening; that of the control polygon if the
+    /// spline is not valid.
+    pub fn length(&self) -> f64 {
+        let hull = BBox2::from_points(self.control_points.iter().copied());
+        path_length(&self.tessellate(hull.diag() * 1e-6))
+    }
+}
+
+/// Swept angle from `start_deg` counter-clockwise to `end_deg`, in (0, 360].
+fn sweep_deg(start_deg: f64, end_deg: f64) -> f64 {
+    match (end_deg - start_deg).rem_euclid(360.0) {
+        s if s < 1e-9 => 360.0,
+        s => s,
+    }
+}
+
+/// Chords needed for `sweep` radians of a circle of `radius` to stay within
+/// `tolerance` of it, a quarter turn each at most.
+fn arc_segments(radius: f64, sweep: f64, tolerance: f64) -> usize {
+    let step = if tolerance > 0.0 && tolerance < radius {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        FRAC_PI_2
+    };
+    (sweep.abs() / step.min(FRAC_PI_2))
+        .ceil()
+        .clamp(1.0, 4096.0) as usize
+}
+
+/// Points of the circle (`center`, `radius`) from angle `start` through the
+/// signed `sweep` (radians), ends included.
+fn arc_points(center: Vec2, radius: f64, start: f64, sweep: f64, tolerance: f64) -> Vec<Vec2> {
+    let n = arc_segments(radius, sweep, tolerance);
+    (0..=n)
+        .map(|i| center + Vec2::from_angle(start + sweep * i as f64 / n as f64) * radius)
+        .collect()
+}
+
+/// Real roots of `a·t² + b·t + c`.
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() <= 1e-12 * (b.abs() + c.abs()) {
+        return if b == 0.0 { Vec::new() } else { vec![-c / b] };
+    }
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return Vec::new();
+    }
+    let root = disc.sqrt();
+    vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+}
+
+fn path_length(points: &[Vec2]) -> f64 {
+    points.windows(2).map(|w| w[0].distance(w[1])).sum()
+}
+
+/// Appends points of `f` after `u0` up to and including `u1`, starting from
+/// `pieces` equal steps and bisecting each with [`flatten`].
+fn flatten_range(
+    f: &impl Fn(f64) -> Vec2,
+    (u0, u1): (f64, f64),
+    pieces: usize,
+    tolerance: f64,
+    out: &mut Vec<Vec2>,
+) {
+    let mut from = (u0, f(u0));
+    for i in 1..=pieces {
+        let u = u0 + (u1 - u0) * i as f64 / pieces as f64;
+        let to = (u, f(u));
+        flatten(f, from, to, tolerance, 0, out);
+        from = to;
+    }
+}
+
+/// Appends points of `f` after `from` up to and including `to`, bisecting
+/// while the middle of the curve is more than `tolerance` off the chord.
+fn flatten(
+    f: &impl Fn(f64) -> Vec2,
+    from: (f64, Vec2),
+    to: (f64, Vec2),
+    tolerance: f64,
+    depth: u32,
+    out: &mut Vec<Vec2>,
+) {
+    let t = 0.5 * (from.0 + to.0);
+    let mid = (t, f(t));
+    let off = closest_on_segment(mid.1, from.1, to.1).distance(mid.1);
+    if depth < MAX_DEPTH && off > tolerance {
+        flatten(f, from, mid, tolerance, depth + 1, out);
+        flatten(f, mid, to, tolerance, depth + 1, out);
+    } else {
+        out.push(to.1);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::model::DimensionEntity;
+
+/// Whether a dimension's text and measurement give it a usable value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DimensionStatus {
+    Ok,
+    /// Neither a measurement nor a number in the text.
+    Unresolved,
+    /// The text overrides the measurement with numbers that all disagree with it.
+    Conflict {
+        measured: f64,
+    },
+}
+
+/// Numbers written in a dimension text, with half the step of their last
+/// decimal (`"R12.5"` gives `(12.5, 0.05)`).
+pub fn text_numbers(text: &str) -> Vec<(f64, f64)> {
+    let mut numbers = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if !c.is_ascii_digit() {
+            continue;
+        }
+        let mut end = start + 1;
+        let mut decimals = None;
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_ascii_digit() {
+                decimals = decimals.map(|d: i32| d + 1);
+            } else if c == '.' && decimals.is_none() {
+                decimals = Some(0);
+            } else {
+                break;
+            }
+            end = i + 1;
+            chars.next();
+        }
+        if let Ok(value) = text[start..end].trim_end_matches('.').parse::<f64>() {
+            numbers.push((value, 0.5 * 10f64.powi(-decimals.unwrap_or(0))));
+        }
+    }
+    numbers
+}
+
+/// The nominal value written in a dimension text, as in [`text_numbers`].
+///
+/// That is the first number once MTEXT formatting (`\H2.5;`, `{\C1;...}`),
+/// stacked tolerances (`\S+0.1^-0.2;`) and a leading feature count (`"4X "`)
+/// are left out, so `"M10x1.5"` gives 10 and `"12.5 %%p0.02"` gives 12.5. A text
+/// showing the measurement through `<>` has none.
+pub fn nominal_value(text: &str) -> Option<(f64, f64)> {
+    if text.contains("<>") {
+        return None;
+    }
+    let plain = strip_mtext_codes(text);
+    let plain = plain.trim_start();
+    let digits = plain.len() - plain.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let after_count = plain[digits..].trim_start();
+    let body = match after_count.strip_prefix(['X', 'x']) {
+        Some(rest) if digits > 0 && rest.starts_with([' ', '%']) => rest,
+        _ => plain,
+    };
+    text_numbers(body).first().copied()
+}
+
+/// `text` without MTEXT format codes, braces and stacked fractions.
+fn strip_mtext_codes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {}
+            '\\' => match chars.next() {
+                Some(c @ ('\\' | '{' | '}')) => out.push(c),
+                Some('P' | 'N') => out.push('\n'),
+                Some('~') => out.push(' '),
+                Some('L' | 'l' | 'O' | 'o' | 'K' | 'k') | None => {}
+                // \A1; \C1; \H2.5x; \fArial|b0; \S1/2; ...: up to the semicolon.
+                Some(_) => {
+                    for c in chars.by_ref() {
+                        if c == ';' {
+                            break;
+                        }
+                    }
+                }
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Checks `dim`'s text against its measurement.
+///
+/// A measurement of exactly zero is taken as missing: it is what DXF writers
+/// leave when they omit it. An empty text or one with DXF's `<>` placeholder
+/// shows the measurement, so only a missing measurement is a problem there.
+pub fn check_dimension(dim: &DimensionEntity) -> DimensionStatus {
+    let measured = dim.measurement.filter(|m| m.is_finite() && *m != 0.0);
+    let text = dim.text.as_deref().unwrap_or("");
+    if text.trim().is_empty() || text.contains("<>") {
+        return match measured {
+            Some(_) => DimensionStatus::Ok,
+            None => DimensionStatus::Unresolved,
+        };
+    }
+    let numbers = text_numbers(text);
+    match measured {
+        None if numbers.is_empty() => DimensionStatus::Unresolved,
+        Some(measured)
+            if !numbers.is_empty()
+                && numbers
+                    .iter()
+                    .all(|(value, tol)| (value - measured.abs()).abs() > tol + 1e-9) =>
+        {
+            DimensionStatus::Conflict { measured }
+        }
+        _ => DimensionStatus::Ok,
+    }
+}
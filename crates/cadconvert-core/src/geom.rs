@@ -1,18 +1,135 @@
+//! Plane geometry: vector arithmetic, bounding boxes, and the queries every
+//! primitive answers — length, closest point, tessellation and intersection.
+//! Per-curve evaluation lives in [`crate::curve`].
+
+use crate::model::{Arc2D, Ellipse2D, LineSeg2D, Polyline2D, PolylineVertex2D, Primitive2D};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+/// Flattening tolerance for queries without one, relative to the size of the
+/// curve.
+const RELATIVE_TOLERANCE: f64 = 1e-6;
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Vec2 {
     pub x: f64,
     pub y: f64,
 }
 
 impl Vec2 {
+    pub const ZERO: Self = Self::new(0.0, 0.0);
+
     pub const fn new(x: f64, y: f64) -> Self {
         Self { x, y }
     }
+
+    /// Unit vector at `angle` radians from the X axis.
+    pub fn from_angle(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(cos, sin)
+    }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Z component of the 3D cross product: positive when `other` turns left
+    /// from `self`.
+    pub fn cross(self, other: Self) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    pub fn length_squared(self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn distance(self, other: Self) -> f64 {
+        (other - self).length()
+    }
+
+    /// Angle from the X axis in radians, in (-π, π].
+    pub fn angle(self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
+    /// The unit vector along `self`, or zero if it has no direction.
+    pub fn normalized(self) -> Self {
+        let len = self.length();
+        if !len.is_finite() || len < 1e-12 {
+            return Self::ZERO;
+        }
+        self / len
+    }
+
+    /// `self` turned a quarter counter-clockwise.
+    pub fn perp(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<f64> for Vec2 {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Div<f64> for Vec2 {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self {
+        Self::new(self.x / rhs, self.y / rhs)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct BBox2 {
     pub min: Vec2,
     pub max: Vec2,
@@ -30,6 +147,14 @@ impl BBox2 {
         }
     }
 
+    pub fn from_points(points: impl IntoIterator<Item = Vec2>) -> Self {
+        let mut bbox = Self::empty();
+        for p in points {
+            bbox.include_point(p);
+        }
+        bbox
+    }
+
     pub fn is_empty(&self) -> bool {
         self.min.x > self.max.x || self.min.y > self.max.y
     }
@@ -41,6 +166,14 @@ impl BBox2 {
         self.max.y = self.max.y.max(point.y);
     }
 
+    /// Whether `point` is inside or on the edge.
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+    }
+
     pub fn union(&self, other: &Self) -> Self {
         if self.is_empty() {
             return *other;
@@ -98,3 +231,585 @@ impl BBox2 {
     }
 }
 
+/// `(center, radius, start angle, signed sweep)` of a DXF bulge segment from
+/// `p0` to `p1`, angles in radians, or `None` if the segment is straight.
+pub fn bulge_arc(p0: Vec2, p1: Vec2, bulge: f64) -> Option<(Vec2, f64, f64, f64)> {
+    let chord = p1 - p0;
+    let c = chord.length();
+    if !c.is_finite() || c < 1e-12 || !bulge.is_finite() || bulge.abs() < 1e-10 {
+        return None;
+    }
+
+    let theta = 4.0 * bulge.atan(); // signed sweep angle
+    let r = c * (1.0 + bulge * bulge) / (4.0 * bulge.abs());
+
+    let mid = p0.lerp(p1, 0.5);
+    let d = r * (theta * 0.5).cos() * bulge.signum();
+    let center = mid + chord.perp().normalized() * d;
+
+    Some((center, r, (p0 - center).angle(), theta))
+}
+
+impl Primitive2D {
+    pub fn length(&self) -> f64 {
+        match self {
+            Primitive2D::Line(l) => l.a.distance(l.b),
+            Primitive2D::Circle(c) => TAU * c.radius,
+            Primitive2D::Arc(a) => a.length(),
+            Primitive2D::Polyline(p) => p.length(),
+            Primitive2D::CubicBezier(b) => b.length(),
+            Primitive2D::Point(_) => 0.0,
+            Primitive2D::Ellipse(e) => e.length(),
+            Primitive2D::Nurbs(n) => n.length(),
+        }
+    }
+
+    /// Points along the primitive, ends included, with no chord farther than
+    /// `tolerance` from it. Closed outlines end where they start.
+    pub fn tessellate(&self, tolerance: f64) -> Vec<Vec2> {
+        match self {
+            Primitive2D::Line(l) => vec![l.a, l.b],
+            Primitive2D::Circle(c) => c.tessellate(tolerance),
+            Primitive2D::Arc(a) => a.tessellate(tolerance),
+            Primitive2D::Polyline(p) => p.tessellate(tolerance),
+            Primitive2D::CubicBezier(b) => b.tessellate(tolerance),
+            Primitive2D::Point(p) => vec![*p],
+            Primitive2D::Ellipse(e) => e.tessellate(tolerance),
+            Primitive2D::Nurbs(n) => n.tessellate(tolerance),
+        }
+    }
+
+    /// The point of the primitive nearest `point`. Exact for lines, circles,
+    /// arcs and polylines; within a millionth of the curve's size otherwise.
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        self.pieces()
+            .iter()
+            .map(|piece| piece.closest_point(point))
+            .min_by(|a, b| a.distance(point).total_cmp(&b.distance(point)))
+            .unwrap_or(point)
+    }
+
+    /// Points where the two primitives meet, touching included. Overlapping
+    /// runs give their ends. Exact for lines, circles, arcs and polylines;
+    /// curves are flattened to a millionth of their size first.
+    pub fn intersections(&self, other: &Primitive2D) -> Vec<Vec2> {
+        let size = self.bbox().union(&other.bbox()).diag();
+        let eps = (size * 1e-9).max(1e-12);
+        let mine = self.pieces();
+        let theirs = other.pieces();
+        let mut out: Vec<Vec2> = Vec::new();
+        for a in &mine {
+            for b in &theirs {
+                for p in a.intersections(b, eps) {
+                    // Flattened curves meet a crossing line in every piece
+                    // ending at it; keep one point per crossing.
+                    if out.iter().all(|q| q.distance(p) > eps * 1e3) {
+                        out.push(p);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// The parts of the primitive inside `rect`, its edges included. Lines,
+    /// circles, arcs, ellipses and polylines are cut exactly; Béziers and
+    /// NURBS that cross an edge become polylines through a fine flattening.
+    pub fn clip(&self, rect: &BBox2) -> Vec<Primitive2D> {
+        let bbox = self.bbox();
+        if rect.contains(bbox.min) && rect.contains(bbox.max) {
+            return vec![self.clone()];
+        }
+        if bbox.is_empty() || bbox.distance_to(rect) > 0.0 {
+            return Vec::new();
+        }
+        let eps = (rect.diag().max(bbox.diag()) * 1e-9).max(1e-12);
+        let inside = |p: Vec2| rect.expand(eps).contains(p);
+        match self {
+            Primitive2D::Line(l) => {
+                let at = |t: f64| if t == 1.0 { l.b } else { l.a.lerp(l.b, t) };
+                kept_ranges(line_cuts(l.a, l.b, rect), 1.0, false, |t| inside(at(t)))
+                    .into_iter()
+                    .map(|(t0, t1)| {
+                        Primitive2D::Line(LineSeg2D {
+                            a: at(t0),
+                            b: at(t1),
+                        })
+                    })
+                    .collect()
+            }
+            Primitive2D::Circle(c) => {
+                let arc = Arc2D {
+                    center: c.center,
+                    radius: c.radius,
+                    start_angle_deg: 0.0,
+                    end_angle_deg: 360.0,
+                };
+                clip_arc(&arc, rect, inside).unwrap_or_else(|| vec![self.clone()])
+            }
+            Primitive2D::Arc(a) => clip_arc(a, rect, inside).unwrap_or_else(|| vec![self.clone()]),
+            Primitive2D::Ellipse(e) => {
+                let start = e.start_param_deg.to_radians();
+                let sweep = e.sweep_deg().to_radians();
+                let cuts = conic_cuts(e.center, e.major_axis, e.minor_axis(), rect)
+                    .into_iter()
+                    .map(|t| (t - start).rem_euclid(TAU))
+                    .collect();
+                let at = |offset: f64| e.point_at((start + offset).to_degrees());
+                let ranges = kept_ranges(cuts, sweep, e.is_closed(), |o| inside(at(o)));
+                if e.is_closed() && ranges == [(0.0, sweep)] {
+                    return vec![self.clone()];
+                }
+                ranges
+                    .into_iter()
+                    .map(|(o0, o1)| {
+                        Primitive2D::Ellipse(Ellipse2D {
+                            start_param_deg: (start + o0).to_degrees(),
+                            end_param_deg: (start + o1).to_degrees(),
+                            ..e.clone()
+                        })
+                    })
+                    .collect()
+            }
+            Primitive2D::Polyline(p) => clip_polyline(p, rect, inside),
+            Primitive2D::Point(_) => Vec::new(),
+            Primitive2D::CubicBezier(_) | Primitive2D::Nurbs(_) => {
+                let vertices = self
+                    .tessellate(bbox.diag() * RELATIVE_TOLERANCE)
+                    .into_iter()
+                    .map(|pos| PolylineVertex2D { pos, bulge: 0.0 })
+                    .collect();
+                let flat = Polyline2D {
+                    vertices,
+                    closed: false,
+                };
+                clip_polyline(&flat, rect, inside)
+            }
+        }
+    }
+
+    /// Straight and circular pieces, exact where the primitive is made of
+    /// them and a fine flattening where it is not.
+    fn pieces(&self) -> Vec<Piece> {
+        let flattened = |points: Vec<Vec2>| -> Vec<Piece> {
+            match points[..] {
+                [p] => vec![Piece::Line(p, p)],
+                _ => points.windows(2).map(|w| Piece::Line(w[0], w[1])).collect(),
+            }
+        };
+        let tolerance = self.bbox().diag() * RELATIVE_TOLERANCE;
+        match self {
+            Primitive2D::Line(l) => vec![Piece::Line(l.a, l.b)],
+            Primitive2D::Circle(c) => vec![Piece::Arc {
+                center: c.center,
+                radius: c.radius,
+                start: 0.0,
+                sweep: TAU,
+            }],
+            Primitive2D::Arc(a) => vec![Piece::Arc {
+                center: a.center,
+                radius: a.radius,
+                start: a.start_angle_deg.to_radians(),
+                sweep: a.sweep_deg().to_radians(),
+            }],
+            Primitive2D::Polyline(p) => {
+                let n = p.vertices.len();
+                let segments = if p.closed { n } else { n.saturating_sub(1) };
+                let mut pieces: Vec<Piece> = (0..segments)
+                    .map(|i| {
+                        let (v0, p1) = (&p.vertices[i], p.vertices[(i + 1) % n].pos);
+                        match bulge_arc(v0.pos, p1, v0.bulge) {
+                            Some((center, radius, start, sweep)) => Piece::Arc {
+                                center,
+                                radius,
+                                start,
+                                sweep,
+                            },
+                            None => Piece::Line(v0.pos, p1),
+                        }
+                    })
+                    .collect();
+                if let ([], [v]) = (&pieces[..], &p.vertices[..]) {
+                    pieces.push(Piece::Line(v.pos, v.pos));
+                }
+                pieces
+            }
+            Primitive2D::Point(p) => vec![Piece::Line(*p, *p)],
+            Primitive2D::CubicBezier(b) => flattened(b.tessellate(tolerance)),
+            Primitive2D::Ellipse(e) => flattened(e.tessellate(tolerance)),
+            Primitive2D::Nurbs(n) => flattened(n.tessellate(tolerance)),
+        }
+    }
+}
+
+/// A line segment, or an arc from `start` through the signed `sweep`
+/// (radians).
+enum Piece {
+    Line(Vec2, Vec2),
+    Arc {
+        center: Vec2,
+        radius: f64,
+        start: f64,
+        sweep: f64,
+    },
+}
+
+impl Piece {
+    fn closest_point(&self, p: Vec2) -> Vec2 {
+        match *self {
+            Piece::Line(a, b) => closest_on_segment(p, a, b),
+            Piece::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                let at = |angle: f64| center + Vec2::from_angle(angle) * radius;
+                let angle = (p - center).angle();
+                if p != center && on_arc(angle, start, sweep, 0.0) {
+                    return at(angle);
+                }
+                let (a, b) = (at(start), at(start + sweep));
+                if a.distance(p) <= b.distance(p) {
+                    a
+                } else {
+                    b
+                }
+            }
+        }
+    }
+
+    /// Where the pieces meet, each end of an overlap included, to within
+    /// `eps`.
+    fn intersections(&self, other: &Piece, eps: f64) -> Vec<Vec2> {
+        match (self, other) {
+            (Piece::Line(a0, a1), Piece::Line(b0, b1)) => segment_segment(*a0, *a1, *b0, *b1, eps),
+            (Piece::Line(a, b), arc @ Piece::Arc { .. })
+            | (arc @ Piece::Arc { .. }, Piece::Line(a, b)) => segment_arc(*a, *b, arc, eps),
+            (Piece::Arc { .. }, Piece::Arc { .. }) => arc_arc(self, other, eps),
+        }
+    }
+
+    /// Whether a point of the piece's circle at `angle` is on the piece.
+    fn covers(&self, angle: f64, eps: f64) -> bool {
+        match *self {
+            Piece::Line(..) => true,
+            Piece::Arc {
+                radius,
+                start,
+                sweep,
+                ..
+            } => on_arc(angle, start, sweep, eps / radius.max(eps)),
+        }
+    }
+}
+
+pub(crate) fn closest_on_segment(p: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let d = b - a;
+    let len2 = d.length_squared();
+    if len2 <= 0.0 {
+        return a;
+    }
+    a + d * ((p - a).dot(d) / len2).clamp(0.0, 1.0)
+}
+
+/// Whether `angle` lies within the arc from `start` through the signed
+/// `sweep`, give or take `slack`; all in radians.
+fn on_arc(angle: f64, start: f64, sweep: f64, slack: f64) -> bool {
+    if sweep.abs() >= TAU - slack {
+        return true;
+    }
+    let along = ((angle - start) * sweep.signum()).rem_euclid(TAU);
+    along <= sweep.abs() + slack || along >= TAU - slack
+}
+
+fn segment_segment(a0: Vec2, a1: Vec2, b0: Vec2, b1: Vec2, eps: f64) -> Vec<Vec2> {
+    let (d, e) = (a1 - a0, b1 - b0);
+    let den = d.cross(e);
+    if den.abs() > 1e-12 * d.length() * e.length() {
+        let w = b0 - a0;
+        let (t, u) = (w.cross(e) / den, w.cross(d) / den);
+        let (ta, tb) = (eps / d.length(), eps / e.length());
+        if (-ta..=1.0 + ta).contains(&t) && (-tb..=1.0 + tb).contains(&u) {
+            return vec![a0 + d * t.clamp(0.0, 1.0)];
+        }
+        return Vec::new();
+    }
+    // Parallel or degenerate: the ends lying on the other segment.
+    let mut out = Vec::new();
+    let ends = [(a0, b0, b1), (a1, b0, b1), (b0, a0, a1), (b1, a0, a1)];
+    for (p, c0, c1) in ends {
+        if closest_on_segment(p, c0, c1).distance(p) <= eps {
+            out.push(p);
+        }
+    }
+    out
+}
+
+fn segment_arc(a: Vec2, b: Vec2, arc: &Piece, eps: f64) -> Vec<Vec2> {
+    let Piece::Arc { center, radius, .. } = *arc else {
+        return Vec::new();
+    };
+    let d = b - a;
+    let len = d.length();
+    if len <= eps {
+        let on_circle = (a.distance(center) - radius).abs() <= eps;
+        if on_circle && arc.covers((a - center).angle(), eps) {
+            return vec![a];
+        }
+        return Vec::new();
+    }
+    let dir = d / len;
+    let foot = a + dir * (center - a).dot(dir);
+    let off = foot.distance(center);
+    if off > radius + eps {
+        return Vec::new();
+    }
+    let half = (radius * radius - off * off).max(0.0).sqrt();
+    let candidates = if half <= eps {
+        vec![foot]
+    } else {
+        vec![foot - dir * half, foot + dir * half]
+    };
+    candidates
+        .into_iter()
+        .filter(|p| {
+            let t = (*p - a).dot(dir);
+            t >= -eps && t <= len + eps && arc.covers((*p - center).angle(), eps)
+        })
+        .collect()
+}
+
+fn arc_arc(a: &Piece, b: &Piece, eps: f64) -> Vec<Vec2> {
+    let (
+        Piece::Arc {
+            center: c1,
+            radius: r1,
+            start: s1,
+            sweep: w1,
+        },
+        Piece::Arc {
+            center: c2,
+            radius: r2,
+            start: s2,
+            sweep: w2,
+        },
+    ) = (a, b)
+    else {
+        return Vec::new();
+    };
+    let (c1, r1, c2, r2) = (*c1, *r1, *c2, *r2);
+    let between = c2 - c1;
+    let d = between.length();
+    if d <= eps {
+        // Concentric: the same circle overlaps where each arc's ends lie on
+        // the other.
+        if (r1 - r2).abs() > eps {
+            return Vec::new();
+        }
+        let mut out = Vec::new();
+        for (angle, on) in [(*s1, b), (s1 + w1, b), (*s2, a), (s2 + w2, a)] {
+            if on.covers(angle, eps) {
+                out.push(c1 + Vec2::from_angle(angle) * r1);
+            }
+        }
+        return out;
+    }
+    if d > r1 + r2 + eps || d < (r1 - r2).abs() - eps {
+        return Vec::new();
+    }
+    let dir = between / d;
+    let along = (d * d + r1 * r1 - r2 * r2) / (2.0 * d);
+    let half = (r1 * r1 - along * along).max(0.0).sqrt();
+    let base = c1 + dir * along;
+    let candidates = if half <= eps {
+        vec![base]
+    } else {
+        vec![base - dir.perp() * half, base + dir.perp() * half]
+    };
+    candidates
+        .into_iter()
+        .filter(|p| a.covers((*p - c1).angle(), eps) && b.covers((*p - c2).angle(), eps))
+        .collect()
+}
+
+/// Parameters in (0, 1) where the segment from `a` to `b` crosses the lines
+/// through the edges of `rect`.
+fn line_cuts(a: Vec2, b: Vec2, rect: &BBox2) -> Vec<f64> {
+    let d = b - a;
+    let edges = [
+        (a.x, d.x, rect.min.x),
+        (a.x, d.x, rect.max.x),
+        (a.y, d.y, rect.min.y),
+        (a.y, d.y, rect.max.y),
+    ];
+    edges
+        .into_iter()
+        .filter(|(_, delta, _)| *delta != 0.0)
+        .map(|(from, delta, edge)| (edge - from) / delta)
+        .filter(|t| *t > 0.0 && *t < 1.0)
+        .collect()
+}
+
+/// Angles in radians where `center + u·cos t + v·sin t` crosses the lines
+/// through the edges of `rect`.
+fn conic_cuts(center: Vec2, u: Vec2, v: Vec2, rect: &BBox2) -> Vec<f64> {
+    let edges = [
+        (center.x, u.x, v.x, rect.min.x),
+        (center.x, u.x, v.x, rect.max.x),
+        (center.y, u.y, v.y, rect.min.y),
+        (center.y, u.y, v.y, rect.max.y),
+    ];
+    let mut out = Vec::new();
+    for (c, a, b, edge) in edges {
+        // a·cos t + b·sin t = r·cos(t - φ)
+        let r = a.hypot(b);
+        let k = (edge - c) / r;
+        if r > 0.0 && k.abs() <= 1.0 {
+            let (phi, half) = (b.atan2(a), k.acos());
+            out.extend([phi - half, phi + half]);
+        }
+    }
+    out
+}
+
+/// The ranges of `[0, total]` between cuts whose middle `keep` accepts,
+/// joined where they meet, and across the seam when `closed`.
+fn kept_ranges(
+    mut cuts: Vec<f64>,
+    total: f64,
+    closed: bool,
+    keep: impl Fn(f64) -> bool,
+) -> Vec<(f64, f64)> {
+    cuts.retain(|c| *c > 0.0 && *c < total);
+    cuts.extend([0.0, total]);
+    cuts.sort_by(f64::total_cmp);
+    let mut out: Vec<(f64, f64)> = Vec::new();
+    for w in cuts.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        if b <= a || !keep(0.5 * (a + b)) {
+            continue;
+        }
+        match out.last_mut() {
+            Some(last) if last.1 == a => last.1 = b,
+            _ => out.push((a, b)),
+        }
+    }
+    if closed && out.len() > 1 && out[0].0 == 0.0 && out[out.len() - 1].1 == total {
+        let first = out.remove(0);
+        if let Some(last) = out.last_mut() {
+            last.1 = total + first.1;
+        }
+    }
+    out
+}
+
+/// The kept arcs, or `None` if that is the whole of a full circle.
+fn clip_arc(arc: &Arc2D, rect: &BBox2, inside: impl Fn(Vec2) -> bool) -> Option<Vec<Primitive2D>> {
+    let start = arc.start_angle_deg.to_radians();
+    let sweep = arc.sweep_deg().to_radians();
+    let closed = arc.sweep_deg() >= 360.0;
+    let radius = Vec2::new(arc.radius, 0.0);
+    let cuts = conic_cuts(arc.center, radius, radius.perp(), rect)
+        .into_iter()
+        .map(|t| (t - start).rem_euclid(TAU))
+        .collect();
+    let at = |offset: f64| arc.center + Vec2::from_angle(start + offset) * arc.radius;
+    let ranges = kept_ranges(cuts, sweep, closed, |o| inside(at(o)));
+    if closed && ranges == [(0.0, sweep)] {
+        return None;
+    }
+    let arcs = ranges.into_iter().map(|(o0, o1)| {
+        Primitive2D::Arc(Arc2D {
+            start_angle_deg: (start + o0).to_degrees(),
+            end_angle_deg: (start + o1).to_degrees(),
+            ..arc.clone()
+        })
+    });
+    Some(arcs.collect())
+}
+
+/// Runs of kept segment parts, as open polylines; a closed polyline that
+/// keeps everything stays whole.
+fn clip_polyline(
+    polyline: &Polyline2D,
+    rect: &BBox2,
+    inside: impl Fn(Vec2) -> bool,
+) -> Vec<Primitive2D> {
+    let n = polyline.vertices.len();
+    let count = if polyline.closed {
+        n
+    } else {
+        n.saturating_sub(1)
+    };
+    // (segment, starts at its first vertex, ends at its second, from, to, bulge)
+    let mut parts: Vec<(usize, bool, bool, Vec2, Vec2, f64)> = Vec::new();
+    for i in 0..count {
+        let (v0, p1) = (&polyline.vertices[i], polyline.vertices[(i + 1) % n].pos);
+        match bulge_arc(v0.pos, p1, v0.bulge) {
+            Some((center, radius, start, sweep)) => {
+                let turn = sweep.signum();
+                let at = |offset: f64| center + Vec2::from_angle(start + turn * offset) * radius;
+                let r = Vec2::new(radius, 0.0);
+                let cuts = conic_cuts(center, r, r.perp(), rect)
+                    .into_iter()
+                    .map(|t| ((t - start) * turn).rem_euclid(TAU))
+                    .collect();
+                let total = sweep.abs();
+                for (o0, o1) in kept_ranges(cuts, total, false, |o| inside(at(o))) {
+                    let (from, to) = (o0 == 0.0, o1 == total);
+                    let a = if from { v0.pos } else { at(o0) };
+                    let b = if to { p1 } else { at(o1) };
+                    parts.push((i, from, to, a, b, (turn * (o1 - o0) / 4.0).tan()));
+                }
+            }
+            None => {
+                let at = |t: f64| if t == 1.0 { p1 } else { v0.pos.lerp(p1, t) };
+                let cuts = line_cuts(v0.pos, p1, rect);
+                for (t0, t1) in kept_ranges(cuts, 1.0, false, |t| inside(at(t))) {
+                    parts.push((i, t0 == 0.0, t1 == 1.0, at(t0), at(t1), 0.0));
+                }
+            }
+        }
+    }
+    if polyline.closed && parts.len() == count && parts.iter().all(|p| p.1 && p.2) {
+        return vec![Primitive2D::Polyline(polyline.clone())];
+    }
+
+    let mut runs: Vec<Vec<PolylineVertex2D>> = Vec::new();
+    let mut previous: Option<(usize, bool)> = None;
+    for &(segment, from, to, a, b, bulge) in &parts {
+        let joins = matches!(previous, Some((s, true)) if s + 1 == segment && from);
+        match runs.last_mut() {
+            Some(run) if joins => {
+                if let Some(last) = run.last_mut() {
+                    last.bulge = bulge;
+                }
+            }
+            _ => runs.push(vec![PolylineVertex2D { pos: a, bulge }]),
+        }
+        if let Some(run) = runs.last_mut() {
+            run.push(PolylineVertex2D { pos: b, bulge: 0.0 });
+        }
+        previous = Some((segment, to));
+    }
+    // A closed outline cut open starts its first run mid-way round.
+    if let (true, Some(first), Some(last)) = (polyline.closed, parts.first(), parts.last()) {
+        if runs.len() > 1 && first.0 == 0 && first.1 && last.0 == count - 1 && last.2 {
+            let head = runs.remove(0);
+            if let Some(tail) = runs.last_mut() {
+                tail.pop();
+                tail.extend(head);
+            }
+        }
+    }
+    runs.into_iter()
+        .filter(|run| run.len() >= 2)
+        .map(|vertices| {
+            Primitive2D::Polyline(Polyline2D {
+                vertices,
+                closed: false,
+            })
+        })
+        .collect()
+}
@@ -1,7 +1,11 @@
 pub mod analysis;
+pub mod curve;
+pub mod dimension;
 pub mod geom;
 pub mod model;
 pub mod normalize;
 pub mod report;
+pub mod schema;
 pub mod step;
+pub mod tolerance;
 pub mod view;
@@ -1,7 +1,9 @@
 use crate::geom::{BBox2, Vec2};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum Units {
     Unknown,
     Inches,
@@ -10,37 +12,53 @@ pub enum Units {
     Meters,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+impl Units {
+    pub fn millimeters_per_unit(self) -> Option<f64> {
+        match self {
+            Units::Unknown => None,
+            Units::Inches => Some(25.4),
+            Units::Millimeters => Some(1.0),
+            Units::Centimeters => Some(10.0),
+            Units::Meters => Some(1000.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum EntityKind {
     Unknown,
     Object,
     Hidden,
     Center,
+    Phantom,
     Dimension,
     Text,
     Hatch,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 pub struct Style {
     pub layer: Option<String>,
     pub linetype: Option<String>,
     pub color_index: Option<i16>,
+    /// Line width in drawing units, if the source gives one.
+    #[serde(default)]
+    pub lineweight: Option<f64>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct LineSeg2D {
     pub a: Vec2,
     pub b: Vec2,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Circle2D {
     pub center: Vec2,
     pub radius: f64,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Arc2D {
     pub center: Vec2,
     pub radius: f64,
@@ -48,19 +66,19 @@ pub struct Arc2D {
     pub end_angle_deg: f64,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct PolylineVertex2D {
     pub pos: Vec2,
     pub bulge: f64,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Polyline2D {
     pub vertices: Vec<PolylineVertex2D>,
     pub closed: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Bezier2D {
     pub p0: Vec2,
     pub p1: Vec2,
@@ -68,13 +86,43 @@ pub struct Bezier2D {
     pub p3: Vec2,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// An ellipse, or the arc of it counter-clockwise from `start_param_deg` to
+/// `end_param_deg`; equal parameters mean the whole ellipse. Parameters are
+/// eccentric angles from the major axis: the point at `t` is
+/// `center + major_axis·cos t + minor_axis()·sin t`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Ellipse2D {
+    pub center: Vec2,
+    /// End of the major axis, relative to `center`.
+    pub major_axis: Vec2,
+    /// Minor to major axis length, in (0, 1].
+    pub ratio: f64,
+    pub start_param_deg: f64,
+    pub end_param_deg: f64,
+}
+
+/// A non-uniform rational B-spline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Nurbs2D {
+    pub degree: usize,
+    pub control_points: Vec<Vec2>,
+    /// One per control point; empty for a non-rational spline.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weights: Vec<f64>,
+    /// `control_points.len() + degree + 1` non-decreasing values.
+    pub knots: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Primitive2D {
     Line(LineSeg2D),
     Circle(Circle2D),
     Arc(Arc2D),
     Polyline(Polyline2D),
     CubicBezier(Bezier2D),
+    Point(Vec2),
+    Ellipse(Ellipse2D),
+    Nurbs(Nurbs2D),
 }
 
 impl Primitive2D {
@@ -90,38 +138,49 @@ impl Primitive2D {
                 Vec2::new(circle.center.x - circle.radius, circle.center.y - circle.radius),
                 Vec2::new(circle.center.x + circle.radius, circle.center.y + circle.radius),
             ),
-            Primitive2D::Arc(arc) => {
-                let mut bbox = BBox2::empty();
-                let r = arc.radius;
-                bbox.include_point(Vec2::new(arc.center.x - r, arc.center.y - r));
-                bbox.include_point(Vec2::new(arc.center.x + r, arc.center.y + r));
-                bbox
-            }
-            Primitive2D::Polyline(poly) => {
-                let mut bbox = BBox2::empty();
-                for v in &poly.vertices {
-                    bbox.include_point(v.pos);
-                }
-                bbox
-            }
-            Primitive2D::CubicBezier(b) => {
-                let mut bbox = BBox2::empty();
-                bbox.include_point(b.p0);
-                bbox.include_point(b.p1);
-                bbox.include_point(b.p2);
-                bbox.include_point(b.p3);
-                bbox
-            }
+            Primitive2D::Arc(arc) => arc.bbox(),
+            Primitive2D::Polyline(poly) => poly.bbox(),
+            Primitive2D::CubicBezier(b) => b.bbox(),
+            Primitive2D::Point(p) => BBox2::new(*p, *p),
+            Primitive2D::Ellipse(e) => e.bbox(),
+            Primitive2D::Nurbs(n) => n.bbox(),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Where an entity came from in the source file, to point back at it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Provenance {
+    /// The source object's handle as the file writes it, hex for DXF. Every
+    /// insert of a block shares the handles of the block's entities.
+    #[serde(default)]
+    pub handle: Option<String>,
+    /// Blocks inserted to reach it, outermost first.
+    #[serde(default)]
+    pub block_path: Vec<String>,
+    /// The outermost insert it came through, the object to pick in a CAD
+    /// program.
+    #[serde(default)]
+    pub insert_handle: Option<String>,
+    /// The tag of the block attribute a text shows.
+    #[serde(default)]
+    pub attribute_tag: Option<String>,
+}
+
+impl Provenance {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Entity2D {
     pub id: u64,
     pub kind: EntityKind,
     pub primitive: Primitive2D,
     pub style: Style,
+    #[serde(default, skip_serializing_if = "Provenance::is_empty")]
+    pub source: Provenance,
 }
 
 impl Entity2D {
@@ -130,30 +189,95 @@ impl Entity2D {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Which end of a text's baseline its `at` point is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct TextEntity {
     pub id: u64,
     pub text: String,
     pub at: Vec2,
     pub height: Option<f64>,
+    /// Baseline direction in degrees, counter-clockwise from +X.
+    #[serde(default)]
+    pub rotation_deg: f64,
+    #[serde(default)]
+    pub align: TextAlign,
     pub style: Style,
+    #[serde(default, skip_serializing_if = "Provenance::is_empty")]
+    pub source: Provenance,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct DimensionEntity {
     pub id: u64,
     pub raw_type: Option<i16>,
     pub text: Option<String>,
     pub measurement: Option<f64>,
+    /// Where the dimension text sits, if the source says.
+    #[serde(default)]
+    pub at: Option<Vec2>,
+    /// What the dimension measures, if the source says.
+    #[serde(default)]
+    pub definition: Option<DimensionDefinition>,
     pub style: Style,
+    #[serde(default, skip_serializing_if = "Provenance::is_empty")]
+    pub source: Provenance,
+}
+
+/// A dimension's definition points, as DXF lays them out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DimensionDefinition {
+    /// In DXF group code order: the dimension line point (10), then the type's
+    /// own points (13 to 16), e.g. the extension line origins of a linear
+    /// dimension or the point on the arc of a radius.
+    pub points: Vec<Vec2>,
+    /// Direction of a linear dimension's dimension line, in degrees.
+    #[serde(default)]
+    pub rotation_deg: f64,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Drawing2D {
     pub units: Units,
     pub entities: Vec<Entity2D>,
     pub dims: Vec<DimensionEntity>,
     pub texts: Vec<TextEntity>,
+    #[serde(default, skip_serializing_if = "ImportLog::is_empty")]
+    pub import_log: ImportLog,
+}
+
+/// What the importer could not carry over faithfully.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ImportLog {
+    /// Source entities left out, by source type (`"MESH"`, `"WIPEOUT"`).
+    #[serde(default)]
+    pub skipped: BTreeMap<String, usize>,
+    /// Entities flattened onto the XY plane from a 3D source.
+    #[serde(default)]
+    pub projected: Vec<u64>,
+    /// The paper-space layout imported instead of model space, if any.
+    #[serde(default)]
+    pub layout: Option<String>,
+    /// Entities drawn on a layout's sheet rather than in model space or seen
+    /// through a viewport: the imported layout's, else the active one's.
+    #[serde(default)]
+    pub paper_space: Vec<u64>,
+}
+
+impl ImportLog {
+    pub fn is_empty(&self) -> bool {
+        self.skipped.is_empty()
+            && self.projected.is_empty()
+            && self.layout.is_none()
+            && self.paper_space.is_empty()
+    }
 }
 
 impl Drawing2D {
@@ -1,4 +1,5 @@
 use crate::model::{Drawing2D, EntityKind, Primitive2D, Style};
+use crate::tolerance::Tolerances;
 
 #[derive(Debug, Clone)]
 pub struct NormalizeConfig {
@@ -17,6 +18,15 @@ impl Default for NormalizeConfig {
     }
 }
 
+impl NormalizeConfig {
+    /// Entities shorter than the snap tolerance would collapse to a point in the
+    /// topology anyway, so drop them here.
+    pub fn with_tolerances(mut self, tolerances: &Tolerances) -> Self {
+        self.min_entity_length = tolerances.snap;
+        self
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct NormalizeStats {
     pub removed_degenerate_entities: usize,
@@ -26,6 +36,14 @@ pub struct NormalizeStats {
 pub fn normalize_in_place(drawing: &mut Drawing2D, cfg: &NormalizeConfig) -> NormalizeStats {
     let mut stats = NormalizeStats::default();
 
+    // Drop first, so `inferred_kinds` only counts entities that are kept.
+    if cfg.drop_degenerate_entities {
+        let min_len2 = cfg.min_entity_length * cfg.min_entity_length;
+        let before = drawing.entities.len();
+        drawing.entities.retain(|e| !is_degenerate(&e.primitive, min_len2));
+        stats.removed_degenerate_entities = before.saturating_sub(drawing.entities.len());
+    }
+
     if cfg.infer_kinds_from_style {
         for ent in &mut drawing.entities {
             if ent.kind == EntityKind::Unknown {
@@ -37,13 +55,6 @@ pub fn normalize_in_place(drawing: &mut Drawing2D, cfg: &NormalizeConfig) -> Nor
         }
     }
 
-    if cfg.drop_degenerate_entities {
-        let min_len2 = cfg.min_entity_length * cfg.min_entity_length;
-        let before = drawing.entities.len();
-        drawing.entities.retain(|e| !is_degenerate(&e.primitive, min_len2));
-        stats.removed_degenerate_entities = before.saturating_sub(drawing.entities.len());
-    }
-
     stats
 }
 
@@ -64,6 +75,9 @@ fn infer_kind_from_style(style: &Style) -> EntityKind {
     if s.contains("hidden") || s.contains("hid") {
         return EntityKind::Hidden;
     }
+    if s.contains("phantom") {
+        return EntityKind::Phantom;
+    }
     if s.contains("object") || s.contains("cont") {
         return EntityKind::Object;
     }
@@ -81,6 +95,12 @@ fn is_degenerate(p: &Primitive2D, min_len2: f64) -> bool {
         Primitive2D::Circle(c) => c.radius * c.radius <= min_len2,
         Primitive2D::Arc(a) => a.radius * a.radius <= min_len2,
         Primitive2D::Polyline(pl) => pl.vertices.len() < 2,
+        Primitive2D::Point(_) => false,
+        Primitive2D::Ellipse(e) => {
+            let m = e.major_axis;
+            m.x * m.x + m.y * m.y <= min_len2
+        }
+        Primitive2D::Nurbs(n) => !n.is_valid() || n.bbox().diag().powi(2) <= min_len2,
         Primitive2D::CubicBezier(b) => {
             let mut max_d2 = 0.0f64;
             for (p0, p1) in [
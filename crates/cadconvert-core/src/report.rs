@@ -1,14 +1,20 @@
 use crate::geom::BBox2;
+use crate::tolerance::Tolerances;
 use crate::view::ViewAssignmentReport;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Warning {
     pub code: String,
     pub message: String,
+    /// Entities or dimensions the warning is about, if any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entity_ids: Vec<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ViewClusterReport {
     pub id: usize,
     pub entity_count: usize,
@@ -16,7 +22,7 @@ pub struct ViewClusterReport {
     pub entity_id_sample: Vec<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StatsReport {
     pub entities_total: usize,
     pub entities_normalized: usize,
@@ -24,13 +30,17 @@ pub struct StatsReport {
     pub inferred_kinds: usize,
     pub dims_total: usize,
     pub texts_total: usize,
+    /// Source entities the importer left out, by source type.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub skipped_entities: BTreeMap<String, usize>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AnalysisReport {
     pub input_format: String,
     pub stats: StatsReport,
     pub extents: Option<BBox2>,
+    pub tolerances: Tolerances,
     pub view_clusters: Vec<ViewClusterReport>,
     pub view_assignment: Option<ViewAssignmentReport>,
     pub warnings: Vec<Warning>,
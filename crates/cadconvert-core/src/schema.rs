@@ -0,0 +1,273 @@
+//! Versioned JSON for `report.json` and `drawing.json`.
+//!
+//! Both are written as [`Document`]s: the serde shape of [`AnalysisReport`] or
+//! [`Drawing2D`] with a `schema_version` field alongside. Bump
+//! [`SCHEMA_VERSION`] whenever either shape changes, and teach `migrate` to
+//! bring the previous version forward.
+//!
+//! Drawings can also be streamed as JSON Lines: a header record first, then
+//! one record per entity, dimension or text, in any order.
+//!
+//! ```text
+//! {"record":"header","schema_version":8,"units":"Millimeters"}
+//! {"record":"entity","id":1,"kind":"Object","primitive":{"Line":{...}},"style":{...}}
+//! {"record":"dim","id":2,"raw_type":null,"text":"10","measurement":10.0,"style":{...}}
+//! {"record":"text","id":3,"text":"NOTE","at":{...},"height":null,"style":{...}}
+//! ```
+//!
+//! The line format starts at version 1. Versions 2 to 8 only added optional
+//! fields and enum variants, so neither format needs migrating to them.
+
+use crate::model::{DimensionEntity, Drawing2D, Entity2D, ImportLog, TextEntity, Units};
+use crate::report::AnalysisReport;
+use crate::tolerance::Tolerances;
+use schemars::{JsonSchema, Schema};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, Write};
+use thiserror::Error;
+
+/// Version of the JSON written by this build.
+///
+/// Version 0 is everything written before the field existed.
+pub const SCHEMA_VERSION: u32 = 8;
+
+/// A report or drawing as stored on disk.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Document<T> {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub body: T,
+}
+
+#[derive(Debug, Error)]
+pub enum SchemaError {
+    #[error("not valid JSON: {0}")]
+    Json(#[source] serde_json::Error),
+    #[error("schema_version must be a non-negative integer")]
+    BadVersion,
+    #[error(
+        "written with schema version {found}, but this build reads up to {SCHEMA_VERSION}; \
+         upgrade cadconvert"
+    )]
+    Newer { found: u32 },
+    #[error("does not match schema version {version} (after migration): {source}")]
+    Invalid {
+        version: u32,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("read failed: {0}")]
+    Io(#[source] std::io::Error),
+    #[error("first record must be the header with schema_version and units")]
+    MissingHeader,
+    #[error("line {line}: {error}")]
+    Line {
+        line: usize,
+        error: Box<SchemaError>,
+    },
+}
+
+/// Which document a JSON file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Report,
+    Drawing,
+}
+
+/// Serializes `body` as a current-version [`Document`].
+pub fn to_json_pretty<T: Serialize>(body: &T) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&Document {
+        schema_version: SCHEMA_VERSION,
+        body,
+    })
+}
+
+/// Reads a `report.json`, migrating older versions.
+pub fn report_from_json(json: &str) -> Result<AnalysisReport, SchemaError> {
+    from_json(json, DocumentKind::Report)
+}
+
+/// Reads a `drawing.json`, migrating older versions.
+pub fn drawing_from_json(json: &str) -> Result<Drawing2D, SchemaError> {
+    from_json(json, DocumentKind::Drawing)
+}
+
+fn from_json<T: DeserializeOwned>(json: &str, kind: DocumentKind) -> Result<T, SchemaError> {
+    let value: Value = serde_json::from_str(json).map_err(SchemaError::Json)?;
+    from_value(value, kind)
+}
+
+/// Deserializes a document of `kind` from `value`, migrating older versions.
+pub fn from_value<T: DeserializeOwned>(
+    mut value: Value,
+    kind: DocumentKind,
+) -> Result<T, SchemaError> {
+    let found = version_of(&value)?.unwrap_or(0);
+    for version in found..SCHEMA_VERSION {
+        migrate(&mut value, kind, version);
+    }
+    if let Value::Object(map) = &mut value {
+        map.remove("schema_version");
+    }
+    serde_json::from_value(value).map_err(|source| SchemaError::Invalid {
+        version: found,
+        source,
+    })
+}
+
+/// The `schema_version` of `value`, if it has one this build can read.
+fn version_of(value: &Value) -> Result<Option<u32>, SchemaError> {
+    let Some(version) = value.get("schema_version") else {
+        return Ok(None);
+    };
+    let found = version
+        .as_u64()
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or(SchemaError::BadVersion)?;
+    if found > SCHEMA_VERSION {
+        return Err(SchemaError::Newer { found });
+    }
+    Ok(Some(found))
+}
+
+/// Upgrades `value` from `version` to `version + 1` in place.
+fn migrate(value: &mut Value, kind: DocumentKind, version: u32) {
+    let Value::Object(map) = value else {
+        return;
+    };
+    // Reports from before tolerances were derived per drawing used the fixed
+    // defaults. Warning entity ids and dimension positions default to empty.
+    if version == 0 && kind == DocumentKind::Report && !map.contains_key("tolerances") {
+        map.insert(
+            "tolerances".to_string(),
+            serde_json::to_value(Tolerances::default()).unwrap_or(Value::Null),
+        );
+    }
+    // Version 2 added `Style::lineweight`, which defaults to none, and the
+    // phantom entity kind. Version 3 added text rotation and alignment, which
+    // default to unrotated and left. Version 4 added point primitives, the
+    // drawing's import log and skipped entity counts, which default to empty.
+    // Version 5 added ellipse and NURBS primitives. Version 6 added the
+    // import log's layout and paper-space entities, which default to none.
+    // Version 7 added the source of entities, dimensions and texts, which
+    // defaults to unknown. Version 8 added dimension definition points, which
+    // default to none.
+}
+
+/// JSON Schema of the current `report.json` or `drawing.json`.
+pub fn json_schema(kind: DocumentKind) -> Schema {
+    let (mut schema, title) = match kind {
+        DocumentKind::Report => (
+            schemars::schema_for!(Document<AnalysisReport>),
+            "cadconvert analysis report",
+        ),
+        DocumentKind::Drawing => (
+            schemars::schema_for!(Document<Drawing2D>),
+            "cadconvert drawing",
+        ),
+    };
+    schema.insert(
+        "title".to_string(),
+        format!("{title} (schema version {SCHEMA_VERSION})").into(),
+    );
+    schema
+}
+
+/// One line of a `.jsonl` drawing.
+#[derive(Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum Record {
+    /// `schema_version` is checked by [`version_of`] before this is parsed.
+    Header {
+        units: Units,
+        #[serde(default)]
+        import_log: ImportLog,
+    },
+    Entity(Entity2D),
+    Dim(DimensionEntity),
+    Text(TextEntity),
+}
+
+#[derive(Serialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum RecordRef<'a> {
+    Header {
+        schema_version: u32,
+        units: Units,
+        #[serde(skip_serializing_if = "ImportLog::is_empty")]
+        import_log: &'a ImportLog,
+    },
+    Entity(&'a Entity2D),
+    Dim(&'a DimensionEntity),
+    Text(&'a TextEntity),
+}
+
+/// Reads a drawing streamed as JSON Lines. Blank lines are skipped.
+pub fn drawing_from_jsonl(reader: impl BufRead) -> Result<Drawing2D, SchemaError> {
+    let mut drawing: Option<Drawing2D> = None;
+    for (index, line) in reader.lines().enumerate() {
+        let at_line = |error| SchemaError::Line {
+            line: index + 1,
+            error: Box::new(error),
+        };
+        let line = line.map_err(|e| at_line(SchemaError::Io(e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(drawing) = drawing.as_mut() else {
+            drawing = Some(read_header(&line).map_err(at_line)?);
+            continue;
+        };
+        let record = serde_json::from_str(&line).map_err(|source| {
+            at_line(SchemaError::Invalid {
+                version: SCHEMA_VERSION,
+                source,
+            })
+        })?;
+        match record {
+            Record::Header { .. } => return Err(at_line(SchemaError::MissingHeader)),
+            Record::Entity(e) => drawing.entities.push(e),
+            Record::Dim(d) => drawing.dims.push(d),
+            Record::Text(t) => drawing.texts.push(t),
+        }
+    }
+    drawing.ok_or(SchemaError::MissingHeader)
+}
+
+fn read_header(line: &str) -> Result<Drawing2D, SchemaError> {
+    let value: Value = serde_json::from_str(line).map_err(SchemaError::Json)?;
+    let Some(version) = version_of(&value)? else {
+        return Err(SchemaError::MissingHeader);
+    };
+    match serde_json::from_value(value) {
+        Ok(Record::Header { units, import_log }) => Ok(Drawing2D {
+            units,
+            entities: Vec::new(),
+            dims: Vec::new(),
+            texts: Vec::new(),
+            import_log,
+        }),
+        Ok(_) => Err(SchemaError::MissingHeader),
+        Err(source) => Err(SchemaError::Invalid { version, source }),
+    }
+}
+
+/// Writes `drawing` as current-version JSON Lines.
+pub fn drawing_to_jsonl(drawing: &Drawing2D, mut writer: impl Write) -> serde_json::Result<()> {
+    let header = RecordRef::Header {
+        schema_version: SCHEMA_VERSION,
+        units: drawing.units,
+        import_log: &drawing.import_log,
+    };
+    let records = std::iter::once(header)
+        .chain(drawing.entities.iter().map(RecordRef::Entity))
+        .chain(drawing.dims.iter().map(RecordRef::Dim))
+        .chain(drawing.texts.iter().map(RecordRef::Text));
+    for record in records {
+        serde_json::to_writer(&mut writer, &record)?;
+        writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+    }
+    Ok(())
+}
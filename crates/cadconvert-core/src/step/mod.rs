@@ -0,0 +1,303 @@
+mod reader;
+mod writer;
+
+pub use reader::{Edge, Face, Instance, Record, StepFile, StepHeader, Value};
+pub use writer::{Entity, Param, ProductIds, Schema, StepError, StepId, StepOptions, StepWriter};
+
+use crate::geom::{bulge_arc, BBox2, Vec2};
+use crate::model::{
+    Arc2D, Bezier2D, Drawing2D, Ellipse2D, EntityKind, Nurbs2D, Polyline2D, Primitive2D,
+};
+use crate::report::AnalysisReport;
+use crate::tolerance::Tolerances;
+use crate::view::ViewRole;
+use std::f64::consts::TAU;
+
+/// A view's region on the sheet, used to fold it into 3D.
+#[derive(Debug, Clone, Copy)]
+pub struct PlacedView {
+    pub role: ViewRole,
+    pub bbox: BBox2,
+}
+
+/// The assigned views of an analysis, or nothing if no assignment was made.
+pub fn placed_views(report: &AnalysisReport) -> Vec<PlacedView> {
+    let Some(assignment) = &report.view_assignment else {
+        return Vec::new();
+    };
+    assignment
+        .roles
+        .iter()
+        .filter_map(|r| {
+            let cluster = report.view_clusters.iter().find(|c| c.id == r.cluster_id)?;
+            Some(PlacedView {
+                role: r.role,
+                bbox: cluster.bbox,
+            })
+        })
+        .collect()
+}
+
+/// Exports the drawing's geometry as a STEP wireframe with exact curves: lines
+/// and straight polyline runs as `POLYLINE`, circles as `CIRCLE`, ellipses as
+/// `ELLIPSE`, arcs, elliptical arcs and bulges as `TRIMMED_CURVE`, Béziers and
+/// splines as `B_SPLINE_CURVE_WITH_KNOTS`, rational where weighted.
+///
+/// Each of `views` becomes a `MAPPED_ITEM` that places the view's geometry on its
+/// own `AXIS2_PLACEMENT_3D`, folded like an unfolded glass box: front on XZ, top
+/// on XY (at the front view's height) and right on YZ (at the front view's
+/// width). Geometry outside every view stays on the XY plane in sheet coordinates.
+///
+/// The snap tolerance is written as the context's distance uncertainty.
+pub fn wireframe_step(
+    drawing: &Drawing2D,
+    name: &str,
+    views: &[PlacedView],
+    tolerances: &Tolerances,
+    options: &StepOptions,
+) -> Result<String, StepError> {
+    let name = if name.trim().is_empty() {
+        "cadconvert"
+    } else {
+        name.trim()
+    };
+
+    let mut writer = StepWriter::new(options);
+    let product = writer.product(name);
+    let context = writer.representation_context(drawing.units, tolerances.snap);
+
+    // Geometry, grouped by the view that contains each entity.
+    let mut view_curves = vec![Vec::new(); views.len()];
+    let mut sheet_curves = Vec::new();
+    for ent in &drawing.entities {
+        match ent.kind {
+            EntityKind::Dimension | EntityKind::Text | EntityKind::Hatch => continue,
+            _ => {}
+        }
+        let center = ent.bbox().center();
+        let curves = match views.iter().position(|v| v.bbox.contains(center)) {
+            Some(i) => &mut view_curves[i],
+            None => &mut sheet_curves,
+        };
+        write_primitive(&mut writer, &ent.primitive, curves);
+    }
+
+    // Representation
+    let front = views.iter().find(|v| v.role == ViewRole::Front);
+    let (width, height) = front.map_or((0.0, 0.0), |v| (v.bbox.width(), v.bbox.height()));
+    let mut items = Vec::new();
+    for (view, curves) in views.iter().zip(&view_curves) {
+        if curves.is_empty() {
+            continue;
+        }
+        let label = match view.role {
+            ViewRole::Front => "front view",
+            ViewRole::Top => "top view",
+            ViewRole::Right => "right view",
+        };
+        let origin = writer.axis2_placement_3d([view.bbox.min.x, view.bbox.min.y, 0.0], None, None);
+        let curve_set = writer.geometric_curve_set(label, curves);
+        let rep =
+            writer.representation("SHAPE_REPRESENTATION", label, &[origin, curve_set], context);
+        let target = match view.role {
+            ViewRole::Front => writer.axis2_placement_3d(
+                [0.0, 0.0, 0.0],
+                Some([0.0, -1.0, 0.0]),
+                Some([1.0, 0.0, 0.0]),
+            ),
+            ViewRole::Top => writer.axis2_placement_3d(
+                [0.0, 0.0, height],
+                Some([0.0, 0.0, 1.0]),
+                Some([1.0, 0.0, 0.0]),
+            ),
+            ViewRole::Right => writer.axis2_placement_3d(
+                [width, 0.0, 0.0],
+                Some([1.0, 0.0, 0.0]),
+                Some([0.0, 1.0, 0.0]),
+            ),
+        };
+        items.push(writer.mapped_item(label, origin, rep, target));
+    }
+    if !sheet_curves.is_empty() || items.is_empty() {
+        items.push(writer.geometric_curve_set("", &sheet_curves));
+    }
+    writer.shape_representation(
+        &product,
+        "SHAPE_REPRESENTATION",
+        "wireframe",
+        &items,
+        context,
+    );
+
+    writer.finish(&format!("{name}.step"), "cadconvert wireframe")
+}
+
+fn write_primitive(writer: &mut StepWriter, prim: &Primitive2D, curves: &mut Vec<StepId>) {
+    match prim {
+        Primitive2D::Line(l) => {
+            if !approx_eq(l.a, l.b) {
+                curves.push(polyline(writer, &[l.a, l.b]));
+            }
+        }
+        Primitive2D::Circle(c) => {
+            if c.radius.is_finite() && c.radius > 0.0 {
+                let position = writer.axis2_placement_3d([c.center.x, c.center.y, 0.0], None, None);
+                curves.push(writer.circle(position, c.radius));
+            }
+        }
+        Primitive2D::Arc(a) => {
+            if let Some(id) = write_arc(writer, a) {
+                curves.push(id);
+            }
+        }
+        Primitive2D::Polyline(pl) => write_polyline(writer, pl, curves),
+        Primitive2D::CubicBezier(b) => curves.push(write_bezier(writer, b)),
+        Primitive2D::Ellipse(e) => {
+            if let Some(id) = write_ellipse(writer, e) {
+                curves.push(id);
+            }
+        }
+        Primitive2D::Nurbs(n) => {
+            if let Some(id) = write_nurbs(writer, n) {
+                curves.push(id);
+            }
+        }
+        // A wireframe is curves only.
+        Primitive2D::Point(_) => {}
+    }
+}
+
+fn polyline(writer: &mut StepWriter, points: &[Vec2]) -> StepId {
+    let ids: Vec<StepId> = points
+        .iter()
+        .map(|p| writer.cartesian_point([p.x, p.y, 0.0]))
+        .collect();
+    writer.polyline(&ids)
+}
+
+fn write_arc(writer: &mut StepWriter, a: &Arc2D) -> Option<StepId> {
+    if !a.radius.is_finite() || a.radius <= 0.0 {
+        return None;
+    }
+    let (start, sweep) = (a.start_angle_deg.to_radians(), a.sweep_deg().to_radians());
+    Some(trimmed_arc(writer, a.center, a.radius, start, sweep))
+}
+
+/// Arc of the circle (`center`, `radius`) from angle `start` through the signed
+/// `sweep` (radians; negative is clockwise).
+fn trimmed_arc(
+    writer: &mut StepWriter,
+    center: Vec2,
+    radius: f64,
+    start: f64,
+    sweep: f64,
+) -> StepId {
+    let position = writer.axis2_placement_3d([center.x, center.y, 0.0], None, None);
+    let circle = writer.circle(position, radius);
+    let end = start + sweep;
+    let at = |angle: f64| {
+        let p = center + Vec2::from_angle(angle) * radius;
+        [p.x, p.y, 0.0]
+    };
+    let p0 = writer.cartesian_point(at(start));
+    let p1 = writer.cartesian_point(at(end));
+    // Parameters are angles from the placement's X axis, in [0, 2π) for the start.
+    let t0 = start.rem_euclid(TAU);
+    let t1 = t0 + sweep;
+    writer.trimmed_curve(circle, (p0, t0), (p1, t1), sweep >= 0.0)
+}
+
+/// Straight runs become one `POLYLINE` each; bulged segments become arcs.
+fn write_polyline(writer: &mut StepWriter, pl: &Polyline2D, curves: &mut Vec<StepId>) {
+    let n = pl.vertices.len();
+    let segment_count = if pl.closed { n } else { n.saturating_sub(1) };
+    let mut run: Vec<Vec2> = Vec::new();
+    for i in 0..segment_count {
+        let v0 = &pl.vertices[i];
+        let p1 = pl.vertices[(i + 1) % n].pos;
+        if approx_eq(v0.pos, p1) {
+            continue;
+        }
+        match bulge_arc(v0.pos, p1, v0.bulge) {
+            Some((center, radius, start, sweep)) => {
+                if run.len() >= 2 {
+                    curves.push(polyline(writer, &run));
+                }
+                run.clear();
+                curves.push(trimmed_arc(writer, center, radius, start, sweep));
+            }
+            None => {
+                if run.is_empty() {
+                    run.push(v0.pos);
+                }
+                run.push(p1);
+            }
+        }
+    }
+    if run.len() >= 2 {
+        curves.push(polyline(writer, &run));
+    }
+}
+
+/// A cubic Bézier is a degree-3 B-spline with a single span.
+fn write_bezier(writer: &mut StepWriter, b: &Bezier2D) -> StepId {
+    let points: Vec<StepId> = [b.p0, b.p1, b.p2, b.p3]
+        .iter()
+        .map(|p| writer.cartesian_point([p.x, p.y, 0.0]))
+        .collect();
+    writer.b_spline_curve_with_knots(3, &points, &[4, 4], &[0.0, 1.0], "PIECEWISE_BEZIER_KNOTS")
+}
+
+/// An `ELLIPSE` placed along the major axis, trimmed unless it is whole.
+fn write_ellipse(writer: &mut StepWriter, e: &Ellipse2D) -> Option<StepId> {
+    let major = e.major_axis.length();
+    let minor = major * e.ratio;
+    if !major.is_finite() || !minor.is_finite() || minor <= 0.0 {
+        return None;
+    }
+    let x_axis = [e.major_axis.x / major, e.major_axis.y / major, 0.0];
+    let position = writer.axis2_placement_3d([e.center.x, e.center.y, 0.0], None, Some(x_axis));
+    let ellipse = writer.ellipse(position, major, minor);
+    if e.is_closed() {
+        return Some(ellipse);
+    }
+    let (start, end) = (e.start_point(), e.end_point());
+    let p0 = writer.cartesian_point([start.x, start.y, 0.0]);
+    let p1 = writer.cartesian_point([end.x, end.y, 0.0]);
+    let t0 = e.start_param_deg.to_radians().rem_euclid(TAU);
+    let t1 = t0 + e.sweep_deg().to_radians();
+    Some(writer.trimmed_curve(ellipse, (p0, t0), (p1, t1), true))
+}
+
+/// Repeated knots become multiplicities; all weights one is non-rational.
+fn write_nurbs(writer: &mut StepWriter, n: &Nurbs2D) -> Option<StepId> {
+    if !n.is_valid() {
+        return None;
+    }
+    let points: Vec<StepId> = n
+        .control_points
+        .iter()
+        .map(|p| writer.cartesian_point([p.x, p.y, 0.0]))
+        .collect();
+    let mut knots: Vec<f64> = Vec::new();
+    let mut multiplicities: Vec<i64> = Vec::new();
+    for &k in &n.knots {
+        match multiplicities.last_mut() {
+            Some(m) if knots.last() == Some(&k) => *m += 1,
+            _ => {
+                knots.push(k);
+                multiplicities.push(1);
+            }
+        }
+    }
+    let degree = n.degree as i64;
+    Some(if n.weights.iter().all(|w| *w == 1.0) {
+        writer.b_spline_curve_with_knots(degree, &points, &multiplicities, &knots, "UNSPECIFIED")
+    } else {
+        writer.rational_b_spline_curve(degree, &points, &multiplicities, &knots, &n.weights)
+    })
+}
+
+fn approx_eq(a: Vec2, b: Vec2) -> bool {
+    (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+}
@@ -0,0 +1,762 @@
+//! Minimal ISO 10303-21 (STEP physical file) reader.
+//!
+//! Parses the header and data sections into an entity graph, checks that every
+//! reference resolves, and extracts points, edges and faces. Only the subset of
+//! the format that STEP exporters commonly write is supported: no binary values,
+//! no anchor or reference sections, no external mappings.
+
+use super::writer::{Schema, StepError, StepId};
+use std::collections::BTreeMap;
+
+/// A parsed attribute value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Ref(StepId),
+    Str(String),
+    Real(f64),
+    Int(i64),
+    /// Enumeration or boolean, without the dots: `T` for `.T.`.
+    Enum(String),
+    List(Vec<Value>),
+    /// A typed value such as `LENGTH_MEASURE(1.)`.
+    Typed(String, Box<Value>),
+    /// `$`
+    Unset,
+    /// `*`
+    Derived,
+}
+
+impl Value {
+    pub fn as_ref(&self) -> Option<StepId> {
+        match self {
+            Value::Ref(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// Reals and integers, looking through typed values.
+    pub fn as_real(&self) -> Option<f64> {
+        match self {
+            Value::Real(v) => Some(*v),
+            Value::Int(v) => Some(*v as f64),
+            Value::Typed(_, v) => v.as_real(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Enum(e) if e == "T" => Some(true),
+            Value::Enum(e) if e == "F" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn visit_refs(&self, f: &mut impl FnMut(StepId)) {
+        match self {
+            Value::Ref(id) => f(*id),
+            Value::List(items) => items.iter().for_each(|v| v.visit_refs(f)),
+            Value::Typed(_, v) => v.visit_refs(f),
+            _ => {}
+        }
+    }
+}
+
+/// `NAME(params)`, one entity type's attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub name: String,
+    pub params: Vec<Value>,
+}
+
+/// An entity instance: one record, or several for a complex entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub records: Vec<Record>,
+}
+
+impl Instance {
+    /// The record of type `name` (case-insensitive).
+    pub fn record(&self, name: &str) -> Option<&Record> {
+        self.records
+            .iter()
+            .find(|r| r.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn is_complex(&self) -> bool {
+        self.records.len() > 1
+    }
+}
+
+/// The `FILE_DESCRIPTION`, `FILE_NAME` and `FILE_SCHEMA` header fields.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepHeader {
+    pub description: Vec<String>,
+    pub file_name: String,
+    pub time_stamp: String,
+    pub author: Vec<String>,
+    pub organization: Vec<String>,
+    pub preprocessor_version: String,
+    pub originating_system: String,
+    pub schemas: Vec<String>,
+}
+
+/// A topological edge (`EDGE_CURVE`) by its end points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub id: StepId,
+    pub start: [f64; 3],
+    pub end: [f64; 3],
+}
+
+/// A face by the points of its bounds, outer bound first when one is marked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Face {
+    pub id: StepId,
+    pub bounds: Vec<Vec<[f64; 3]>>,
+}
+
+/// A parsed STEP file whose references all resolve.
+#[derive(Debug, Clone, Default)]
+pub struct StepFile {
+    pub header: StepHeader,
+    instances: BTreeMap<StepId, Instance>,
+}
+
+impl StepFile {
+    pub fn parse(text: &str) -> Result<Self, StepError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let (header, instances) = parser.file()?;
+
+        for (&from, instance) in &instances {
+            let mut dangling = None;
+            for record in &instance.records {
+                for value in &record.params {
+                    value.visit_refs(&mut |to| {
+                        if dangling.is_none() && !instances.contains_key(&to) {
+                            dangling = Some(to);
+                        }
+                    });
+                }
+            }
+            if let Some(to) = dangling {
+                return Err(StepError::DanglingReference { from, to });
+            }
+        }
+
+        Ok(Self {
+            header: parse_header(&header),
+            instances,
+        })
+    }
+
+    /// The application protocol named first in `FILE_SCHEMA`, if it is one we write.
+    pub fn schema(&self) -> Option<Schema> {
+        self.header
+            .schemas
+            .first()
+            .and_then(|s| Schema::from_file_schema(s))
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn get(&self, id: StepId) -> Option<&Instance> {
+        self.instances.get(&id)
+    }
+
+    /// All instances, in id order.
+    pub fn instances(&self) -> impl Iterator<Item = (StepId, &Instance)> {
+        self.instances.iter().map(|(&id, inst)| (id, inst))
+    }
+
+    /// Records of type `name` (case-insensitive), including parts of complex entities.
+    pub fn records<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (StepId, &'a Record)> {
+        self.instances()
+            .filter_map(move |(id, inst)| inst.record(name).map(|r| (id, r)))
+    }
+
+    /// Every `CARTESIAN_POINT`, with 2D points on z = 0.
+    pub fn points(&self) -> Result<Vec<(StepId, [f64; 3])>, StepError> {
+        self.records("CARTESIAN_POINT")
+            .map(|(id, _)| Ok((id, self.point(id)?)))
+            .collect()
+    }
+
+    /// Every `VERTEX_POINT` with its location.
+    pub fn vertices(&self) -> Result<Vec<(StepId, [f64; 3])>, StepError> {
+        self.records("VERTEX_POINT")
+            .map(|(id, _)| Ok((id, self.vertex(id)?)))
+            .collect()
+    }
+
+    /// Every `EDGE_CURVE`, by the locations of its start and end vertices.
+    pub fn edges(&self) -> Result<Vec<Edge>, StepError> {
+        self.records("EDGE_CURVE")
+            .map(|(id, record)| {
+                Ok(Edge {
+                    id,
+                    start: self.vertex(ref_param(id, record, 1)?)?,
+                    end: self.vertex(ref_param(id, record, 2)?)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Every `ADVANCED_FACE`, `FACE_SURFACE` and plain `FACE`, bounded by edge,
+    /// poly or vertex loops.
+    pub fn faces(&self) -> Result<Vec<Face>, StepError> {
+        let mut faces = Vec::new();
+        for (id, inst) in self.instances() {
+            let Some(record) = ["ADVANCED_FACE", "FACE_SURFACE", "FACE"]
+                .iter()
+                .find_map(|name| inst.record(name))
+            else {
+                continue;
+            };
+            let mut bounds = Vec::new();
+            for bound in list_param(id, record, 1)? {
+                let bound = bound
+                    .as_ref()
+                    .ok_or_else(|| malformed(id, "face bound is not a reference"))?;
+                let outer = self
+                    .get(bound)
+                    .is_some_and(|i| i.record("FACE_OUTER_BOUND").is_some());
+                let points = self.bound_points(bound)?;
+                if outer {
+                    bounds.insert(0, points);
+                } else {
+                    bounds.push(points);
+                }
+            }
+            faces.push(Face { id, bounds });
+        }
+        Ok(faces)
+    }
+
+    fn bound_points(&self, id: StepId) -> Result<Vec<[f64; 3]>, StepError> {
+        let inst = self.instance(id)?;
+        let record = inst
+            .record("FACE_BOUND")
+            .or_else(|| inst.record("FACE_OUTER_BOUND"))
+            .ok_or_else(|| malformed(id, "expected FACE_BOUND"))?;
+        let mut points = self.loop_points(ref_param(id, record, 1)?)?;
+        if record.params.get(2).and_then(Value::as_bool) == Some(false) {
+            points.reverse();
+        }
+        Ok(points)
+    }
+
+    fn loop_points(&self, id: StepId) -> Result<Vec<[f64; 3]>, StepError> {
+        let inst = self.instance(id)?;
+        if let Some(record) = inst.record("POLY_LOOP") {
+            return list_param(id, record, 1)?
+                .iter()
+                .map(|p| self.point(p.as_ref().ok_or_else(|| malformed(id, "bad point"))?))
+                .collect();
+        }
+        if let Some(record) = inst.record("VERTEX_LOOP") {
+            return Ok(vec![self.vertex(ref_param(id, record, 1)?)?]);
+        }
+        let record = inst
+            .record("EDGE_LOOP")
+            .ok_or_else(|| malformed(id, "expected EDGE_LOOP, POLY_LOOP or VERTEX_LOOP"))?;
+        let mut points = Vec::new();
+        for oriented in list_param(id, record, 1)? {
+            let oriented = oriented
+                .as_ref()
+                .ok_or_else(|| malformed(id, "loop edge is not a reference"))?;
+            let record = self.expect(oriented, "ORIENTED_EDGE")?;
+            let edge_id = ref_param(oriented, record, 3)?;
+            let edge = self.expect(edge_id, "EDGE_CURVE")?;
+            let forward = record.params.get(4).and_then(Value::as_bool) != Some(false);
+            let start = ref_param(edge_id, edge, if forward { 1 } else { 2 })?;
+            points.push(self.vertex(start)?);
+        }
+        Ok(points)
+    }
+
+    fn vertex(&self, id: StepId) -> Result<[f64; 3], StepError> {
+        let record = self.expect(id, "VERTEX_POINT")?;
+        self.point(ref_param(id, record, 1)?)
+    }
+
+    fn point(&self, id: StepId) -> Result<[f64; 3], StepError> {
+        let record = self.expect(id, "CARTESIAN_POINT")?;
+        let coords = list_param(id, record, 1)?
+            .iter()
+            .map(Value::as_real)
+            .collect::<Option<Vec<f64>>>()
+            .filter(|c| (1..=3).contains(&c.len()))
+            .ok_or_else(|| malformed(id, "expected 1 to 3 coordinates"))?;
+        let mut p = [0.0; 3];
+        p[..coords.len()].copy_from_slice(&coords);
+        Ok(p)
+    }
+
+    fn instance(&self, id: StepId) -> Result<&Instance, StepError> {
+        self.get(id).ok_or(StepError::Undefined(id))
+    }
+
+    fn expect(&self, id: StepId, name: &str) -> Result<&Record, StepError> {
+        self.instance(id)?
+            .record(name)
+            .ok_or_else(|| malformed(id, &format!("expected {name}")))
+    }
+}
+
+fn malformed(id: StepId, message: &str) -> StepError {
+    StepError::Malformed {
+        id,
+        message: message.to_string(),
+    }
+}
+
+fn ref_param(id: StepId, record: &Record, index: usize) -> Result<StepId, StepError> {
+    record
+        .params
+        .get(index)
+        .and_then(Value::as_ref)
+        .ok_or_else(|| {
+            malformed(
+                id,
+                &format!("{} attribute {index} is not a reference", record.name),
+            )
+        })
+}
+
+fn list_param(id: StepId, record: &Record, index: usize) -> Result<&[Value], StepError> {
+    record
+        .params
+        .get(index)
+        .and_then(Value::as_list)
+        .ok_or_else(|| {
+            malformed(
+                id,
+                &format!("{} attribute {index} is not a list", record.name),
+            )
+        })
+}
+
+fn parse_header(records: &[Record]) -> StepHeader {
+    let strings = |v: Option<&Value>| -> Vec<String> {
+        v.and_then(Value::as_list)
+            .map(|l| {
+                l.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let string = |v: Option<&Value>| v.and_then(Value::as_str).unwrap_or_default().to_string();
+
+    let mut header = StepHeader::default();
+    for record in records {
+        let p = &record.params;
+        match record.name.as_str() {
+            "FILE_DESCRIPTION" => header.description = strings(p.first()),
+            "FILE_NAME" => {
+                header.file_name = string(p.first());
+                header.time_stamp = string(p.get(1));
+                header.author = strings(p.get(2));
+                header.organization = strings(p.get(3));
+                header.preprocessor_version = string(p.get(4));
+                header.originating_system = string(p.get(5));
+            }
+            "FILE_SCHEMA" => header.schemas = strings(p.first()),
+            _ => {}
+        }
+    }
+    header
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id(u32),
+    Keyword(String),
+    Str(String),
+    Enum(String),
+    Int(i64),
+    Real(f64),
+    Unset,
+    Derived,
+    Open,
+    Close,
+    Comma,
+    Equals,
+    Semi,
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> StepError {
+    StepError::Syntax {
+        line,
+        message: message.into(),
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, StepError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        let start = line;
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            prev = c;
+                        }
+                        None => return Err(syntax(start, "unterminated comment")),
+                    }
+                }
+                continue;
+            }
+            '#' => {
+                let mut digits = String::new();
+                while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(d);
+                }
+                let n = digits
+                    .parse()
+                    .map_err(|_| syntax(line, "expected an instance number after '#'"))?;
+                Token::Id(n)
+            }
+            '\'' => Token::Str(string(&mut chars, &mut line)?),
+            '.' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('.') => break,
+                        Some(c) if c.is_ascii_alphanumeric() || c == '_' => name.push(c),
+                        _ => return Err(syntax(line, "malformed enumeration")),
+                    }
+                }
+                Token::Enum(name)
+            }
+            '"' => return Err(syntax(line, "binary values are not supported")),
+            c if c.is_ascii_digit() || c == '+' || c == '-' => {
+                let mut number = String::from(c);
+                let mut real = false;
+                while let Some(d) = chars.next_if(|d| {
+                    d.is_ascii_digit()
+                        || matches!(d, '.' | 'E' | 'e')
+                        || (matches!(d, '+' | '-')
+                            && number.ends_with(|e: char| e.eq_ignore_ascii_case(&'e')))
+                }) {
+                    real |= !d.is_ascii_digit() && d != '+' && d != '-';
+                    number.push(d);
+                }
+                if real {
+                    let v = number
+                        .parse()
+                        .map_err(|_| syntax(line, format!("malformed real '{number}'")))?;
+                    Token::Real(v)
+                } else {
+                    let v = number
+                        .parse()
+                        .map_err(|_| syntax(line, format!("malformed integer '{number}'")))?;
+                    Token::Int(v)
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' || c == '!' => {
+                let mut name = String::from(c);
+                while let Some(d) =
+                    chars.next_if(|d| d.is_ascii_alphanumeric() || *d == '_' || *d == '-')
+                {
+                    name.push(d);
+                }
+                Token::Keyword(name.to_ascii_uppercase())
+            }
+            '$' => Token::Unset,
+            '*' => Token::Derived,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '=' => Token::Equals,
+            ';' => Token::Semi,
+            c => return Err(syntax(line, format!("unexpected character '{c}'"))),
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+/// The rest of a string after its opening quote, with escapes decoded.
+fn string(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    line: &mut usize,
+) -> Result<String, StepError> {
+    let start = *line;
+    let mut out = String::new();
+    loop {
+        match chars.next() {
+            Some('\'') if chars.next_if_eq(&'\'').is_some() => out.push('\''),
+            Some('\'') => return Ok(out),
+            // Line breaks inside a string are not part of its value.
+            Some('\n') => *line += 1,
+            Some('\r') => {}
+            Some('\\') => match chars.next() {
+                Some('\\') => out.push('\\'),
+                Some('X') => match chars.next() {
+                    Some('\\') => {
+                        let code = hex(chars, 2, *line)?;
+                        out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    Some(width @ ('2' | '4')) => {
+                        let width = if width == '2' { 4 } else { 8 };
+                        if chars.next() != Some('\\') {
+                            return Err(syntax(*line, "malformed \\X escape"));
+                        }
+                        while chars.next_if_eq(&'\\').is_none() {
+                            let code = hex(chars, width, *line)?;
+                            out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                        }
+                        if chars.next() != Some('X')
+                            || chars.next() != Some('0')
+                            || chars.next() != Some('\\')
+                        {
+                            return Err(syntax(*line, "unterminated \\X escape"));
+                        }
+                    }
+                    _ => return Err(syntax(*line, "malformed \\X escape")),
+                },
+                Some('S') if chars.next_if_eq(&'\\').is_some() => {
+                    let c = chars
+                        .next()
+                        .ok_or_else(|| syntax(*line, "malformed \\S escape"))?;
+                    out.push(
+                        char::from_u32(c as u32 + 0x80).unwrap_or(char::REPLACEMENT_CHARACTER),
+                    );
+                }
+                // Code page switch (`\PA\`); \S\ is then read as ISO 8859-1 regardless.
+                Some('P') => {
+                    chars.next();
+                    if chars.next() != Some('\\') {
+                        return Err(syntax(*line, "malformed \\P escape"));
+                    }
+                }
+                _ => return Err(syntax(*line, "unknown string escape")),
+            },
+            Some(c) => out.push(c),
+            None => return Err(syntax(start, "unterminated string")),
+        }
+    }
+}
+
+fn hex(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    digits: usize,
+    line: usize,
+) -> Result<u32, StepError> {
+    let mut code = 0;
+    for _ in 0..digits {
+        let d = chars
+            .next()
+            .and_then(|c| c.to_digit(16))
+            .ok_or_else(|| syntax(line, "malformed hex escape"))?;
+        code = code * 16 + d;
+    }
+    Ok(code)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Result<Token, StepError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|(t, _)| t.clone())
+            .ok_or_else(|| syntax(self.line(), "unexpected end of file"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), StepError> {
+        let line = self.line();
+        if self.next()? == expected {
+            Ok(())
+        } else {
+            Err(syntax(line, format!("expected {what}")))
+        }
+    }
+
+    fn keyword(&mut self) -> Result<String, StepError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Keyword(k) => Ok(k),
+            _ => Err(syntax(line, "expected a keyword")),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), StepError> {
+        let line = self.line();
+        if self.keyword()? == keyword {
+            Ok(())
+        } else {
+            Err(syntax(line, format!("expected {keyword}")))
+        }
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Keyword(k)) if k == keyword)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn file(&mut self) -> Result<(Vec<Record>, BTreeMap<StepId, Instance>), StepError> {
+        self.expect_keyword("ISO-10303-21")?;
+        self.expect(Token::Semi, "';'")?;
+
+        self.expect_keyword("HEADER")?;
+        self.expect(Token::Semi, "';'")?;
+        let mut header = Vec::new();
+        while !self.at_keyword("ENDSEC") {
+            header.push(self.record()?);
+            self.expect(Token::Semi, "';'")?;
+        }
+        self.next()?;
+        self.expect(Token::Semi, "';'")?;
+
+        let mut instances = BTreeMap::new();
+        loop {
+            let line = self.line();
+            match self.keyword()?.as_str() {
+                "DATA" => {
+                    if self.peek() == Some(&Token::Open) {
+                        self.list()?;
+                    }
+                    self.expect(Token::Semi, "';'")?;
+                    while !self.at_keyword("ENDSEC") {
+                        let (id, instance) = self.instance()?;
+                        if instances.insert(id, instance).is_some() {
+                            return Err(StepError::DuplicateId(id));
+                        }
+                    }
+                    self.next()?;
+                    self.expect(Token::Semi, "';'")?;
+                }
+                "END-ISO-10303-21" => {
+                    self.expect(Token::Semi, "';'")?;
+                    return Ok((header, instances));
+                }
+                other => return Err(syntax(line, format!("unexpected section {other}"))),
+            }
+        }
+    }
+
+    fn instance(&mut self) -> Result<(StepId, Instance), StepError> {
+        let line = self.line();
+        let Token::Id(n) = self.next()? else {
+            return Err(syntax(line, "expected an instance name"));
+        };
+        self.expect(Token::Equals, "'='")?;
+        let mut records = Vec::new();
+        if self.peek() == Some(&Token::Open) {
+            self.next()?;
+            while self.peek() != Some(&Token::Close) {
+                records.push(self.record()?);
+            }
+            self.next()?;
+        } else {
+            records.push(self.record()?);
+        }
+        self.expect(Token::Semi, "';'")?;
+        Ok((StepId(n), Instance { records }))
+    }
+
+    fn record(&mut self) -> Result<Record, StepError> {
+        let name = self.keyword()?;
+        let params = self.list()?;
+        Ok(Record { name, params })
+    }
+
+    fn list(&mut self) -> Result<Vec<Value>, StepError> {
+        self.expect(Token::Open, "'('")?;
+        let mut values = Vec::new();
+        if self.peek() == Some(&Token::Close) {
+            self.next()?;
+            return Ok(values);
+        }
+        loop {
+            values.push(self.value()?);
+            let line = self.line();
+            match self.next()? {
+                Token::Comma => {}
+                Token::Close => return Ok(values),
+                _ => return Err(syntax(line, "expected ',' or ')'")),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, StepError> {
+        let line = self.line();
+        Ok(match self.next()? {
+            Token::Id(n) => Value::Ref(StepId(n)),
+            Token::Str(s) => Value::Str(s),
+            Token::Enum(e) => Value::Enum(e),
+            Token::Int(v) => Value::Int(v),
+            Token::Real(v) => Value::Real(v),
+            Token::Unset => Value::Unset,
+            Token::Derived => Value::Derived,
+            Token::Open => {
+                self.pos -= 1;
+                Value::List(self.list()?)
+            }
+            Token::Keyword(name) => {
+                self.expect(Token::Open, "'('")?;
+                let value = self.value()?;
+                self.expect(Token::Close, "')'")?;
+                Value::Typed(name, Box::new(value))
+            }
+            _ => return Err(syntax(line, "expected a value")),
+        })
+    }
+}
@@ -0,0 +1,1090 @@
+//! Typed ISO 10303-21 (STEP physical file) writer.
+//!
+//! Entities are built from [`Param`]s instead of formatted strings, ids are handed
+//! out by [`StepWriter`], and [`StepWriter::finish`] refuses to write a file with a
+//! reference to an entity that was never defined or has the wrong type, or with a
+//! real that is not finite.
+
+use crate::model::Units;
+use std::fmt::{self, Write as _};
+use thiserror::Error;
+
+/// Instance name (`#n`) of an entity in a [`StepWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StepId(pub(super) u32);
+
+impl StepId {
+    pub fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for StepId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum StepError {
+    #[error("{from} refers to {to}, which is not defined")]
+    DanglingReference { from: StepId, to: StepId },
+    #[error("{0} was reserved but never defined")]
+    Undefined(StepId),
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+    #[error("{0} is defined twice")]
+    DuplicateId(StepId),
+    #[error("{id}: {message}")]
+    Malformed { id: StepId, message: String },
+    #[error("{0} was not reserved by this writer")]
+    NotReserved(StepId),
+    #[error("{from} refers to {to}, a {found}, where {expected} is expected")]
+    WrongReferenceType {
+        from: StepId,
+        to: StepId,
+        expected: String,
+        found: String,
+    },
+    #[error("{0} has a real that is NaN or infinite")]
+    NonFiniteReal(StepId),
+}
+
+const POINT: &[&str] = &["CARTESIAN_POINT"];
+const DIRECTION: &[&str] = &["DIRECTION"];
+const PLACEMENT: &[&str] = &["AXIS2_PLACEMENT_3D"];
+const VERTEX: &[&str] = &["VERTEX_POINT"];
+const CURVE: &[&str] = &[
+    "LINE",
+    "CIRCLE",
+    "ELLIPSE",
+    "POLYLINE",
+    "TRIMMED_CURVE",
+    "B_SPLINE_CURVE",
+    "B_SPLINE_CURVE_WITH_KNOTS",
+];
+
+/// Types a reference may point to, by entity (or partial record) type and
+/// attribute index. Attributes not listed here are only checked for existence.
+const REFERENCE_TYPES: &[(&str, usize, &[&str])] = &[
+    ("VERTEX_POINT", 1, POINT),
+    ("VECTOR", 1, DIRECTION),
+    ("LINE", 1, POINT),
+    ("LINE", 2, &["VECTOR"]),
+    ("POLYLINE", 1, POINT),
+    ("AXIS2_PLACEMENT_3D", 1, POINT),
+    ("AXIS2_PLACEMENT_3D", 2, DIRECTION),
+    ("AXIS2_PLACEMENT_3D", 3, DIRECTION),
+    ("CIRCLE", 1, PLACEMENT),
+    ("ELLIPSE", 1, PLACEMENT),
+    ("TRIMMED_CURVE", 1, CURVE),
+    ("TRIMMED_CURVE", 2, POINT),
+    ("TRIMMED_CURVE", 3, POINT),
+    ("B_SPLINE_CURVE_WITH_KNOTS", 2, POINT),
+    ("B_SPLINE_CURVE", 1, POINT),
+    ("EDGE_CURVE", 1, VERTEX),
+    ("EDGE_CURVE", 2, VERTEX),
+    ("EDGE_CURVE", 3, CURVE),
+    ("MAPPED_ITEM", 1, &["REPRESENTATION_MAP"]),
+    ("MAPPED_ITEM", 2, PLACEMENT),
+    ("REPRESENTATION_MAP", 0, PLACEMENT),
+    (
+        "SHAPE_DEFINITION_REPRESENTATION",
+        0,
+        &["PRODUCT_DEFINITION_SHAPE"],
+    ),
+    ("PRODUCT_DEFINITION_SHAPE", 2, &["PRODUCT_DEFINITION"]),
+    (
+        "PRODUCT_DEFINITION",
+        2,
+        &["PRODUCT_DEFINITION_FORMATION_WITH_SPECIFIED_SOURCE"],
+    ),
+    (
+        "PRODUCT_DEFINITION",
+        3,
+        &["PRODUCT_DEFINITION_CONTEXT", "DESIGN_CONTEXT"],
+    ),
+    (
+        "PRODUCT_DEFINITION_FORMATION_WITH_SPECIFIED_SOURCE",
+        2,
+        &["PRODUCT"],
+    ),
+    ("PRODUCT", 3, &["PRODUCT_CONTEXT", "MECHANICAL_CONTEXT"]),
+    ("CC_DESIGN_APPROVAL", 0, &["APPROVAL"]),
+    (
+        "CC_DESIGN_PERSON_AND_ORGANIZATION_ASSIGNMENT",
+        0,
+        &["PERSON_AND_ORGANIZATION"],
+    ),
+    ("CC_DESIGN_DATE_AND_TIME_ASSIGNMENT", 0, &["DATE_AND_TIME"]),
+    (
+        "CC_DESIGN_SECURITY_CLASSIFICATION",
+        0,
+        &["SECURITY_CLASSIFICATION"],
+    ),
+    ("UNCERTAINTY_MEASURE_WITH_UNIT", 1, &["NAMED_UNIT"]),
+];
+
+/// Application protocol the file claims to conform to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Schema {
+    /// AP203, configuration controlled 3D design.
+    Ap203,
+    /// AP214, automotive design.
+    #[default]
+    Ap214,
+    /// AP242, managed model-based 3D engineering.
+    Ap242,
+}
+
+impl Schema {
+    fn file_schema(self) -> &'static str {
+        match self {
+            Schema::Ap203 => "CONFIG_CONTROL_DESIGN",
+            Schema::Ap214 => "AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }",
+            Schema::Ap242 => {
+                "AP242_MANAGED_MODEL_BASED_3D_ENGINEERING_MIM_LF { 1 0 10303 442 1 1 4 }"
+            }
+        }
+    }
+
+    /// The schema named by a `FILE_SCHEMA` entry, ignoring its object identifier.
+    pub(super) fn from_file_schema(name: &str) -> Option<Self> {
+        let name = name.split(['{', ' ']).next()?.to_ascii_uppercase();
+        match name.as_str() {
+            "CONFIG_CONTROL_DESIGN" => Some(Schema::Ap203),
+            "AUTOMOTIVE_DESIGN" => Some(Schema::Ap214),
+            n if n.starts_with("AP242_MANAGED_MODEL_BASED_3D_ENGINEERING") => Some(Schema::Ap242),
+            _ => None,
+        }
+    }
+
+    /// `(APPLICATION_CONTEXT application, protocol name, protocol year)`.
+    fn application(self) -> (&'static str, &'static str, i64) {
+        match self {
+            Schema::Ap203 => (
+                "configuration controlled 3d designs of mechanical parts and assemblies",
+                "config_control_design",
+                1994,
+            ),
+            Schema::Ap214 => (
+                "core data for automotive mechanical design processes",
+                "automotive_design",
+                2000,
+            ),
+            Schema::Ap242 => (
+                "managed model based 3d engineering",
+                "ap242_managed_model_based_3d_engineering",
+                2014,
+            ),
+        }
+    }
+}
+
+/// Schema and `FILE_NAME` header fields.
+///
+/// Output depends only on the entities and these options, so identical input gives
+/// byte-identical files.
+#[derive(Debug, Clone, Default)]
+pub struct StepOptions {
+    pub schema: Schema,
+    /// ISO 8601 time stamp for `FILE_NAME`; `None` writes the Unix epoch.
+    pub timestamp: Option<String>,
+    pub author: Option<String>,
+    pub organization: Option<String>,
+}
+
+/// An attribute value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Ref(StepId),
+    Str(String),
+    Real(f64),
+    Int(i64),
+    /// Enumeration value, written as `.NAME.`.
+    Enum(&'static str),
+    List(Vec<Param>),
+    /// Typed value such as `LENGTH_MEASURE(1.E-6)`.
+    Typed(&'static str, Box<Param>),
+    /// `$`
+    Unset,
+    /// `*`
+    Derived,
+}
+
+impl Param {
+    pub fn bool(v: bool) -> Self {
+        Param::Enum(if v { "T" } else { "F" })
+    }
+
+    pub fn refs(ids: &[StepId]) -> Self {
+        Param::List(ids.iter().copied().map(Param::Ref).collect())
+    }
+
+    pub fn reals(values: &[f64]) -> Self {
+        Param::List(values.iter().copied().map(Param::Real).collect())
+    }
+
+    fn for_each_ref(&self, f: &mut impl FnMut(StepId)) {
+        match self {
+            Param::Ref(id) => f(*id),
+            Param::List(items) => items.iter().for_each(|p| p.for_each_ref(f)),
+            Param::Typed(_, inner) => inner.for_each_ref(f),
+            _ => {}
+        }
+    }
+
+    fn any_real(&self, f: &impl Fn(f64) -> bool) -> bool {
+        match self {
+            Param::Real(v) => f(*v),
+            Param::List(items) => items.iter().any(|p| p.any_real(f)),
+            Param::Typed(_, inner) => inner.any_real(f),
+            _ => false,
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        match self {
+            Param::Ref(id) => {
+                let _ = write!(out, "{id}");
+            }
+            Param::Str(s) => write_string(out, s),
+            Param::Real(v) => write_real(out, *v),
+            Param::Int(v) => {
+                let _ = write!(out, "{v}");
+            }
+            Param::Enum(name) => {
+                let _ = write!(out, ".{name}.");
+            }
+            Param::List(items) => {
+                out.push('(');
+                write_params(out, items);
+                out.push(')');
+            }
+            Param::Typed(name, inner) => {
+                out.push_str(name);
+                out.push('(');
+                inner.write(out);
+                out.push(')');
+            }
+            Param::Unset => out.push('$'),
+            Param::Derived => out.push('*'),
+        }
+    }
+}
+
+impl From<StepId> for Param {
+    fn from(id: StepId) -> Self {
+        Param::Ref(id)
+    }
+}
+
+impl From<&str> for Param {
+    fn from(s: &str) -> Self {
+        Param::Str(s.to_string())
+    }
+}
+
+impl From<f64> for Param {
+    fn from(v: f64) -> Self {
+        Param::Real(v)
+    }
+}
+
+/// A simple entity instance, or a complex one made of several partial records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    records: Vec<(&'static str, Vec<Param>)>,
+    complex: bool,
+}
+
+impl Entity {
+    pub fn new(name: &'static str, params: Vec<Param>) -> Self {
+        Self {
+            records: vec![(name, params)],
+            complex: false,
+        }
+    }
+
+    /// Complex instance; records are written in alphabetical order as Part 21 requires.
+    pub fn complex(mut records: Vec<(&'static str, Vec<Param>)>) -> Self {
+        records.sort_by_key(|(name, _)| *name);
+        Self {
+            records,
+            complex: true,
+        }
+    }
+
+    fn has_type(&self, types: &[&str]) -> bool {
+        self.records.iter().any(|(name, _)| types.contains(name))
+    }
+
+    fn type_name(&self) -> String {
+        let names: Vec<_> = self.records.iter().map(|(name, _)| *name).collect();
+        names.join("+")
+    }
+
+    fn write(&self, out: &mut String) {
+        if self.complex {
+            out.push('(');
+        }
+        for (name, params) in &self.records {
+            out.push_str(name);
+            out.push('(');
+            write_params(out, params);
+            out.push(')');
+        }
+        if self.complex {
+            out.push(')');
+        }
+    }
+}
+
+/// Ids of the product structure created by [`StepWriter::product`].
+#[derive(Debug, Clone, Copy)]
+pub struct ProductIds {
+    pub product: StepId,
+    pub definition: StepId,
+    pub shape: StepId,
+}
+
+/// Builds the DATA section of a STEP file.
+pub struct StepWriter {
+    options: StepOptions,
+    entities: Vec<Option<Entity>>,
+    application_context: Option<StepId>,
+}
+
+impl StepWriter {
+    pub fn new(options: &StepOptions) -> Self {
+        Self {
+            options: options.clone(),
+            entities: Vec::new(),
+            application_context: None,
+        }
+    }
+
+    pub fn schema(&self) -> Schema {
+        self.options.schema
+    }
+
+    pub fn add(&mut self, entity: Entity) -> StepId {
+        self.entities.push(Some(entity));
+        StepId(self.entities.len() as u32)
+    }
+
+    /// Allocates an id for an entity that is defined later with [`StepWriter::define`],
+    /// for forward references.
+    pub fn reserve(&mut self) -> StepId {
+        self.entities.push(None);
+        StepId(self.entities.len() as u32)
+    }
+
+    pub fn define(&mut self, id: StepId, entity: Entity) -> Result<(), StepError> {
+        let slot = (id.0 as usize)
+            .checked_sub(1)
+            .and_then(|i| self.entities.get_mut(i))
+            .ok_or(StepError::NotReserved(id))?;
+        if slot.is_some() {
+            return Err(StepError::DuplicateId(id));
+        }
+        *slot = Some(entity);
+        Ok(())
+    }
+
+    /// Checks every entity and writes the complete file.
+    ///
+    /// Every reference must point to a defined entity, of a type the attribute
+    /// allows for the attributes this writer knows, and every real must be finite.
+    pub fn finish(self, file_name: &str, description: &str) -> Result<String, StepError> {
+        for (idx, entity) in self.entities.iter().enumerate() {
+            self.check(StepId(idx as u32 + 1), entity.as_ref())?;
+        }
+
+        let opts = &self.options;
+        let mut out = String::new();
+        out.push_str("ISO-10303-21;\nHEADER;\n");
+        let header = [
+            Entity::new(
+                "FILE_DESCRIPTION",
+                vec![Param::List(vec![description.into()]), "2;1".into()],
+            ),
+            Entity::new(
+                "FILE_NAME",
+                vec![
+                    file_name.into(),
+                    opts.timestamp
+                        .as_deref()
+                        .unwrap_or("1970-01-01T00:00:00")
+                        .into(),
+                    Param::List(vec![opts.author.as_deref().unwrap_or("").into()]),
+                    Param::List(vec![opts.organization.as_deref().unwrap_or("").into()]),
+                    concat!("cadconvert ", env!("CARGO_PKG_VERSION")).into(),
+                    "cadconvert".into(),
+                    "".into(),
+                ],
+            ),
+            Entity::new(
+                "FILE_SCHEMA",
+                vec![Param::List(vec![opts.schema.file_schema().into()])],
+            ),
+        ];
+        for entity in &header {
+            entity.write(&mut out);
+            out.push_str(";\n");
+        }
+        out.push_str("ENDSEC;\nDATA;\n");
+        for (idx, entity) in self.entities.iter().enumerate() {
+            let _ = write!(out, "#{}=", idx + 1);
+            if let Some(entity) = entity {
+                entity.write(&mut out);
+            }
+            out.push_str(";\n");
+        }
+        out.push_str("ENDSEC;\nEND-ISO-10303-21;\n");
+        Ok(out)
+    }
+
+    fn check(&self, from: StepId, entity: Option<&Entity>) -> Result<(), StepError> {
+        let Some(entity) = entity else {
+            return Err(StepError::Undefined(from));
+        };
+        let target = |to: StepId| {
+            (to.0 as usize)
+                .checked_sub(1)
+                .and_then(|i| self.entities.get(i))
+                .and_then(Option::as_ref)
+        };
+        for (name, params) in &entity.records {
+            for (attribute, p) in params.iter().enumerate() {
+                if p.any_real(&|v| !v.is_finite()) {
+                    return Err(StepError::NonFiniteReal(from));
+                }
+                let expected = REFERENCE_TYPES
+                    .iter()
+                    .find(|(n, a, _)| n == name && *a == attribute)
+                    .map(|(_, _, types)| *types);
+                let mut error = None;
+                p.for_each_ref(&mut |to| {
+                    if error.is_some() {
+                        return;
+                    }
+                    match (target(to), expected) {
+                        (None, _) => error = Some(StepError::DanglingReference { from, to }),
+                        (Some(found), Some(types)) if !found.has_type(types) => {
+                            error = Some(StepError::WrongReferenceType {
+                                from,
+                                to,
+                                expected: types.join(" or "),
+                                found: found.type_name(),
+                            })
+                        }
+                        _ => {}
+                    }
+                });
+                if let Some(error) = error {
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // --- Product structure --------------------------------------------------
+
+    fn application_context(&mut self) -> StepId {
+        if let Some(id) = self.application_context {
+            return id;
+        }
+        let (application, protocol, year) = self.options.schema.application();
+        let ctx = self.add(Entity::new("APPLICATION_CONTEXT", vec![application.into()]));
+        self.add(Entity::new(
+            "APPLICATION_PROTOCOL_DEFINITION",
+            vec![
+                "international standard".into(),
+                protocol.into(),
+                Param::Int(year),
+                ctx.into(),
+            ],
+        ));
+        self.application_context = Some(ctx);
+        ctx
+    }
+
+    /// A part with one version, one design definition and its shape.
+    ///
+    /// AP203 also gets the configuration management data its schema requires;
+    /// see [`StepWriter::config_control`].
+    pub fn product(&mut self, name: &str) -> ProductIds {
+        let app = self.application_context();
+        let ap203 = self.options.schema == Schema::Ap203;
+        let product_ctx = self.add(Entity::new(
+            if ap203 {
+                "MECHANICAL_CONTEXT"
+            } else {
+                "PRODUCT_CONTEXT"
+            },
+            vec!["".into(), app.into(), "mechanical".into()],
+        ));
+        let definition_ctx = self.add(Entity::new(
+            if ap203 {
+                "DESIGN_CONTEXT"
+            } else {
+                "PRODUCT_DEFINITION_CONTEXT"
+            },
+            vec!["part definition".into(), app.into(), "design".into()],
+        ));
+        let product = self.add(Entity::new(
+            "PRODUCT",
+            vec![
+                name.into(),
+                name.into(),
+                "".into(),
+                Param::refs(&[product_ctx]),
+            ],
+        ));
+        self.add(Entity::new(
+            "PRODUCT_RELATED_PRODUCT_CATEGORY",
+            vec!["part".into(), Param::Unset, Param::refs(&[product])],
+        ));
+        let formation = self.add(Entity::new(
+            "PRODUCT_DEFINITION_FORMATION_WITH_SPECIFIED_SOURCE",
+            vec!["".into(), "".into(), product.into(), Param::Enum("MADE")],
+        ));
+        let definition = self.add(Entity::new(
+            "PRODUCT_DEFINITION",
+            vec![
+                "design".into(),
+                "".into(),
+                formation.into(),
+                definition_ctx.into(),
+            ],
+        ));
+        let shape = self.add(Entity::new(
+            "PRODUCT_DEFINITION_SHAPE",
+            vec!["".into(), "".into(), definition.into()],
+        ));
+        if ap203 {
+            self.config_control(product, formation, definition);
+        }
+        ProductIds {
+            product,
+            definition,
+            shape,
+        }
+    }
+
+    /// AP203 approval, security classification, people and dates.
+    ///
+    /// The header's author and organization own, create and supply the design and
+    /// its classification; it is unclassified and not yet approved, and dated
+    /// with the header's time stamp.
+    fn config_control(&mut self, product: StepId, formation: StepId, definition: StepId) {
+        let author = self.options.author.clone().unwrap_or_default();
+        let organization = self.options.organization.clone().unwrap_or_default();
+        let person = self.add(Entity::new(
+            "PERSON",
+            vec![
+                author.as_str().into(),
+                author.as_str().into(),
+                Param::Unset,
+                Param::Unset,
+                Param::Unset,
+                Param::Unset,
+            ],
+        ));
+        let organization = self.add(Entity::new(
+            "ORGANIZATION",
+            vec![Param::Unset, organization.as_str().into(), "".into()],
+        ));
+        let person_org = self.add(Entity::new(
+            "PERSON_AND_ORGANIZATION",
+            vec![person.into(), organization.into()],
+        ));
+        let date_time = self.date_and_time();
+
+        let level = self.add(Entity::new(
+            "SECURITY_CLASSIFICATION_LEVEL",
+            vec!["unclassified".into()],
+        ));
+        let classification = self.add(Entity::new(
+            "SECURITY_CLASSIFICATION",
+            vec!["".into(), "".into(), level.into()],
+        ));
+        self.add(Entity::new(
+            "CC_DESIGN_SECURITY_CLASSIFICATION",
+            vec![classification.into(), Param::refs(&[formation])],
+        ));
+
+        let status = self.add(Entity::new(
+            "APPROVAL_STATUS",
+            vec!["not_yet_approved".into()],
+        ));
+        let approval = self.add(Entity::new("APPROVAL", vec![status.into(), "".into()]));
+        self.add(Entity::new(
+            "CC_DESIGN_APPROVAL",
+            vec![
+                approval.into(),
+                Param::refs(&[formation, definition, classification]),
+            ],
+        ));
+        let approver = self.add(Entity::new("APPROVAL_ROLE", vec!["approver".into()]));
+        self.add(Entity::new(
+            "APPROVAL_PERSON_ORGANIZATION",
+            vec![person_org.into(), approval.into(), approver.into()],
+        ));
+        self.add(Entity::new(
+            "APPROVAL_DATE_TIME",
+            vec![date_time.into(), approval.into()],
+        ));
+
+        for (role, items) in [
+            ("creator", vec![formation, definition]),
+            ("design_owner", vec![product]),
+            ("design_supplier", vec![formation]),
+            ("classification_officer", vec![classification]),
+        ] {
+            let role = self.add(Entity::new(
+                "PERSON_AND_ORGANIZATION_ROLE",
+                vec![role.into()],
+            ));
+            self.add(Entity::new(
+                "CC_DESIGN_PERSON_AND_ORGANIZATION_ASSIGNMENT",
+                vec![person_org.into(), role.into(), Param::refs(&items)],
+            ));
+        }
+        for (role, item) in [
+            ("creation_date", definition),
+            ("classification_date", classification),
+        ] {
+            let role = self.add(Entity::new("DATE_TIME_ROLE", vec![role.into()]));
+            self.add(Entity::new(
+                "CC_DESIGN_DATE_AND_TIME_ASSIGNMENT",
+                vec![date_time.into(), role.into(), Param::refs(&[item])],
+            ));
+        }
+    }
+
+    /// The header's time stamp as a `DATE_AND_TIME`, in UTC.
+    fn date_and_time(&mut self) -> StepId {
+        let [year, month, day, hour, minute, second] = self
+            .options
+            .timestamp
+            .as_deref()
+            .and_then(parse_timestamp)
+            .unwrap_or([1970, 1, 1, 0, 0, 0]);
+        let date = self.add(Entity::new(
+            "CALENDAR_DATE",
+            vec![Param::Int(year), Param::Int(day), Param::Int(month)],
+        ));
+        let offset = self.add(Entity::new(
+            "COORDINATED_UNIVERSAL_TIME_OFFSET",
+            vec![Param::Int(0), Param::Unset, Param::Enum("AHEAD")],
+        ));
+        let time = self.add(Entity::new(
+            "LOCAL_TIME",
+            vec![
+                Param::Int(hour),
+                Param::Int(minute),
+                Param::Real(second as f64),
+                offset.into(),
+            ],
+        ));
+        self.add(Entity::new("DATE_AND_TIME", vec![date.into(), time.into()]))
+    }
+
+    /// 3D geometric context with length, plane-angle and solid-angle units and a
+    /// distance uncertainty (in `units`).
+    pub fn representation_context(&mut self, units: Units, uncertainty: f64) -> StepId {
+        let length = self.length_unit(units);
+        let plane_angle = self.add(Entity::complex(vec![
+            ("NAMED_UNIT", vec![Param::Derived]),
+            ("PLANE_ANGLE_UNIT", vec![]),
+            ("SI_UNIT", vec![Param::Unset, Param::Enum("RADIAN")]),
+        ]));
+        let solid_angle = self.add(Entity::complex(vec![
+            ("NAMED_UNIT", vec![Param::Derived]),
+            ("SOLID_ANGLE_UNIT", vec![]),
+            ("SI_UNIT", vec![Param::Unset, Param::Enum("STERADIAN")]),
+        ]));
+        let uncertainty = self.add(Entity::new(
+            "UNCERTAINTY_MEASURE_WITH_UNIT",
+            vec![
+                Param::Typed("LENGTH_MEASURE", Box::new(Param::Real(uncertainty))),
+                length.into(),
+                "distance_accuracy_value".into(),
+                "".into(),
+            ],
+        ));
+        self.add(Entity::complex(vec![
+            ("GEOMETRIC_REPRESENTATION_CONTEXT", vec![Param::Int(3)]),
+            (
+                "GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT",
+                vec![Param::refs(&[uncertainty])],
+            ),
+            (
+                "GLOBAL_UNIT_ASSIGNED_CONTEXT",
+                vec![Param::refs(&[length, plane_angle, solid_angle])],
+            ),
+            ("REPRESENTATION_CONTEXT", vec!["".into(), "".into()]),
+        ]))
+    }
+
+    fn length_unit(&mut self, units: Units) -> StepId {
+        let si = |prefix: Option<&'static str>| {
+            Entity::complex(vec![
+                ("LENGTH_UNIT", vec![]),
+                ("NAMED_UNIT", vec![Param::Derived]),
+                (
+                    "SI_UNIT",
+                    vec![
+                        prefix.map_or(Param::Unset, Param::Enum),
+                        Param::Enum("METRE"),
+                    ],
+                ),
+            ])
+        };
+        match units {
+            // Unknown units are written as millimetres, the common CAD default.
+            Units::Millimeters | Units::Unknown => self.add(si(Some("MILLI"))),
+            Units::Centimeters => self.add(si(Some("CENTI"))),
+            Units::Meters => self.add(si(None)),
+            Units::Inches => {
+                let mm = self.add(si(Some("MILLI")));
+                let factor = self.add(Entity::new(
+                    "LENGTH_MEASURE_WITH_UNIT",
+                    vec![
+                        Param::Typed("LENGTH_MEASURE", Box::new(Param::Real(25.4))),
+                        mm.into(),
+                    ],
+                ));
+                let exponents = self.add(Entity::new(
+                    "DIMENSIONAL_EXPONENTS",
+                    vec![
+                        1.0.into(),
+                        0.0.into(),
+                        0.0.into(),
+                        0.0.into(),
+                        0.0.into(),
+                        0.0.into(),
+                        0.0.into(),
+                    ],
+                ));
+                self.add(Entity::complex(vec![
+                    ("CONVERSION_BASED_UNIT", vec!["INCH".into(), factor.into()]),
+                    ("LENGTH_UNIT", vec![]),
+                    ("NAMED_UNIT", vec![exponents.into()]),
+                ]))
+            }
+        }
+    }
+
+    /// Adds a representation of `kind` (e.g. `SHAPE_REPRESENTATION`) holding `items`
+    /// and attaches it to the product's shape.
+    pub fn shape_representation(
+        &mut self,
+        product: &ProductIds,
+        kind: &'static str,
+        name: &str,
+        items: &[StepId],
+        context: StepId,
+    ) -> StepId {
+        let rep = self.representation(kind, name, items, context);
+        self.add(Entity::new(
+            "SHAPE_DEFINITION_REPRESENTATION",
+            vec![product.shape.into(), rep.into()],
+        ));
+        rep
+    }
+
+    // --- Geometry and topology ----------------------------------------------
+
+    pub fn cartesian_point(&mut self, p: [f64; 3]) -> StepId {
+        self.add(Entity::new(
+            "CARTESIAN_POINT",
+            vec!["".into(), Param::reals(&p)],
+        ))
+    }
+
+    /// `d` need not be normalised; STEP readers normalise directions themselves.
+    pub fn direction(&mut self, d: [f64; 3]) -> StepId {
+        self.add(Entity::new("DIRECTION", vec!["".into(), Param::reals(&d)]))
+    }
+
+    pub fn vector(&mut self, direction: StepId, magnitude: f64) -> StepId {
+        self.add(Entity::new(
+            "VECTOR",
+            vec!["".into(), direction.into(), magnitude.into()],
+        ))
+    }
+
+    pub fn line(&mut self, point: StepId, vector: StepId) -> StepId {
+        self.add(Entity::new(
+            "LINE",
+            vec!["".into(), point.into(), vector.into()],
+        ))
+    }
+
+    pub fn polyline(&mut self, points: &[StepId]) -> StepId {
+        self.add(Entity::new(
+            "POLYLINE",
+            vec!["".into(), Param::refs(points)],
+        ))
+    }
+
+    pub fn vertex_point(&mut self, point: StepId) -> StepId {
+        self.add(Entity::new("VERTEX_POINT", vec!["".into(), point.into()]))
+    }
+
+    pub fn edge_curve(
+        &mut self,
+        start: StepId,
+        end: StepId,
+        curve: StepId,
+        same_sense: bool,
+    ) -> StepId {
+        self.add(Entity::new(
+            "EDGE_CURVE",
+            vec![
+                "".into(),
+                start.into(),
+                end.into(),
+                curve.into(),
+                Param::bool(same_sense),
+            ],
+        ))
+    }
+
+    pub fn geometric_curve_set(&mut self, name: &str, curves: &[StepId]) -> StepId {
+        self.add(Entity::new(
+            "GEOMETRIC_CURVE_SET",
+            vec![name.into(), Param::refs(curves)],
+        ))
+    }
+
+    /// Coordinate system at `origin`; `axis` is local Z and `ref_direction` local X.
+    /// `None` leaves them at the global defaults.
+    pub fn axis2_placement_3d(
+        &mut self,
+        origin: [f64; 3],
+        axis: Option<[f64; 3]>,
+        ref_direction: Option<[f64; 3]>,
+    ) -> StepId {
+        let location = self.cartesian_point(origin);
+        let axis = axis.map_or(Param::Unset, |d| self.direction(d).into());
+        let ref_direction = ref_direction.map_or(Param::Unset, |d| self.direction(d).into());
+        self.add(Entity::new(
+            "AXIS2_PLACEMENT_3D",
+            vec!["".into(), location.into(), axis, ref_direction],
+        ))
+    }
+
+    /// Circle in the XY plane of `position`; its parameter is the angle in radians
+    /// from the placement's X axis.
+    pub fn circle(&mut self, position: StepId, radius: f64) -> StepId {
+        self.add(Entity::new(
+            "CIRCLE",
+            vec!["".into(), position.into(), radius.into()],
+        ))
+    }
+
+    /// Ellipse in the XY plane of `position` with its first semi-axis along
+    /// the placement's X axis; its parameter is the eccentric angle in radians.
+    pub fn ellipse(&mut self, position: StepId, semi_axis_1: f64, semi_axis_2: f64) -> StepId {
+        self.add(Entity::new(
+            "ELLIPSE",
+            vec![
+                "".into(),
+                position.into(),
+                semi_axis_1.into(),
+                semi_axis_2.into(),
+            ],
+        ))
+    }
+
+    /// Portion of `basis` between two trims, each given both as a point and a
+    /// parameter value (the point is the master representation).
+    pub fn trimmed_curve(
+        &mut self,
+        basis: StepId,
+        start: (StepId, f64),
+        end: (StepId, f64),
+        same_sense: bool,
+    ) -> StepId {
+        let trim = |(point, parameter): (StepId, f64)| {
+            Param::List(vec![
+                point.into(),
+                Param::Typed("PARAMETER_VALUE", Box::new(Param::Real(parameter))),
+            ])
+        };
+        self.add(Entity::new(
+            "TRIMMED_CURVE",
+            vec![
+                "".into(),
+                basis.into(),
+                trim(start),
+                trim(end),
+                Param::bool(same_sense),
+                Param::Enum("CARTESIAN"),
+            ],
+        ))
+    }
+
+    /// Non-rational B-spline; `multiplicities` and `knots` are parallel lists.
+    pub fn b_spline_curve_with_knots(
+        &mut self,
+        degree: i64,
+        control_points: &[StepId],
+        multiplicities: &[i64],
+        knots: &[f64],
+        knot_type: &'static str,
+    ) -> StepId {
+        self.add(Entity::new(
+            "B_SPLINE_CURVE_WITH_KNOTS",
+            vec![
+                "".into(),
+                Param::Int(degree),
+                Param::refs(control_points),
+                Param::Enum("UNSPECIFIED"),
+                Param::bool(false),
+                Param::bool(false),
+                Param::List(multiplicities.iter().copied().map(Param::Int).collect()),
+                Param::reals(knots),
+                Param::Enum(knot_type),
+            ],
+        ))
+    }
+
+    /// Rational B-spline, as the complex instance Part 42 requires; `weights`
+    /// are one per control point.
+    pub fn rational_b_spline_curve(
+        &mut self,
+        degree: i64,
+        control_points: &[StepId],
+        multiplicities: &[i64],
+        knots: &[f64],
+        weights: &[f64],
+    ) -> StepId {
+        self.add(Entity::complex(vec![
+            ("BOUNDED_CURVE", vec![]),
+            (
+                "B_SPLINE_CURVE",
+                vec![
+                    Param::Int(degree),
+                    Param::refs(control_points),
+                    Param::Enum("UNSPECIFIED"),
+                    Param::bool(false),
+                    Param::bool(false),
+                ],
+            ),
+            (
+                "B_SPLINE_CURVE_WITH_KNOTS",
+                vec![
+                    Param::List(multiplicities.iter().copied().map(Param::Int).collect()),
+                    Param::reals(knots),
+                    Param::Enum("UNSPECIFIED"),
+                ],
+            ),
+            ("CURVE", vec![]),
+            ("GEOMETRIC_REPRESENTATION_ITEM", vec![]),
+            ("RATIONAL_B_SPLINE_CURVE", vec![Param::reals(weights)]),
+            ("REPRESENTATION_ITEM", vec!["".into()]),
+        ]))
+    }
+
+    /// Places the items of `representation` (given relative to `origin`) at
+    /// `target`, via a `REPRESENTATION_MAP`.
+    pub fn mapped_item(
+        &mut self,
+        name: &str,
+        origin: StepId,
+        representation: StepId,
+        target: StepId,
+    ) -> StepId {
+        let map = self.add(Entity::new(
+            "REPRESENTATION_MAP",
+            vec![origin.into(), representation.into()],
+        ));
+        self.add(Entity::new(
+            "MAPPED_ITEM",
+            vec![name.into(), map.into(), target.into()],
+        ))
+    }
+
+    /// Representation not attached to a product shape, e.g. the source of a
+    /// [`StepWriter::mapped_item`].
+    pub fn representation(
+        &mut self,
+        kind: &'static str,
+        name: &str,
+        items: &[StepId],
+        context: StepId,
+    ) -> StepId {
+        self.add(Entity::new(
+            kind,
+            vec![name.into(), Param::refs(items), context.into()],
+        ))
+    }
+}
+
+/// Year, month, day, hour, minute and second of an ISO 8601 `YYYY-MM-DDThh:mm:ss`
+/// time stamp; any fraction or zone after the seconds is ignored.
+fn parse_timestamp(s: &str) -> Option<[i64; 6]> {
+    let field = |range: std::ops::Range<usize>| s.get(range)?.parse::<i64>().ok();
+    let separators = [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')];
+    if separators
+        .iter()
+        .any(|&(i, c)| s.as_bytes().get(i) != Some(&c))
+    {
+        return None;
+    }
+    Some([
+        field(0..4)?,
+        field(5..7)?,
+        field(8..10)?,
+        field(11..13)?,
+        field(14..16)?,
+        field(17..19)?,
+    ])
+}
+
+fn write_params(out: &mut String, params: &[Param]) {
+    for (i, p) in params.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        p.write(out);
+    }
+}
+
+/// Shortest representation that reads back to the same value, always with a
+/// decimal point as Part 21 requires. [`StepWriter::finish`] has already
+/// rejected non-finite values.
+fn write_real(out: &mut String, v: f64) {
+    let v = if v == 0.0 { 0.0 } else { v };
+    let s = format!("{v}");
+    out.push_str(&s);
+    if !s.contains('.') {
+        out.push('.');
+    }
+}
+
+/// Quotes and backslashes are doubled; characters outside printable ASCII use the
+/// `\X2\` / `\X4\` hex encodings.
+fn write_string(out: &mut String, s: &str) {
+    out.push('\'');
+    for c in s.chars() {
+        match c {
+            '\'' => out.push_str("''"),
+            '\\' => out.push_str("\\\\"),
+            ' '..='~' => out.push(c),
+            c if (c as u32) <= 0xFFFF => {
+                let _ = write!(out, "\\X2\\{:04X}\\X0\\", c as u32);
+            }
+            c => {
+                let _ = write!(out, "\\X4\\{:08X}\\X0\\", c as u32);
+            }
+        }
+    }
+    out.push('\'');
+}
@@ -0,0 +1,89 @@
+use crate::dimension::nominal_value;
+use crate::model::Drawing2D;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Snap tolerance as a fraction of the drawing diagonal.
+const SNAP_FACTOR: f64 = 1e-6;
+/// View-match tolerance as a fraction of the drawing diagonal.
+const VIEW_MATCH_FACTOR: f64 = 1e-3;
+/// Below this (in millimetres) distances are float noise, whatever the drawing size.
+const MIN_SNAP_MM: f64 = 1e-6;
+/// The view-match tolerance never drops below this many snap tolerances.
+const MIN_VIEW_MATCH_SNAPS: f64 = 10.0;
+
+/// Geometric tolerances, in drawing units.
+///
+/// Use [`Tolerances::for_drawing`] to scale them to a drawing; `Default` is a fixed
+/// set suited to millimetre drawings of a few hundred units.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Tolerances {
+    /// Points closer than this are the same point; shorter entities are degenerate.
+    pub snap: f64,
+    /// Largest disagreement allowed between two views' projections of one 3D point.
+    pub view_match: f64,
+}
+
+impl Default for Tolerances {
+    fn default() -> Self {
+        Self {
+            snap: 1e-4,
+            view_match: 1.0,
+        }
+    }
+}
+
+impl Tolerances {
+    /// Derives tolerances from the drawing's extents, units and dimension precision.
+    ///
+    /// Both tolerances scale with the extents diagonal. The snap tolerance has a
+    /// floor of [`MIN_SNAP_MM`] converted to drawing units (millimetres when units
+    /// are unknown). The view-match tolerance is capped by the finest precision in
+    /// the dimension texts: a drawing dimensioned to 0.01 is not matched at 0.1.
+    pub fn for_drawing(drawing: &Drawing2D) -> Self {
+        let Some(extents) = drawing.extents().filter(|b| b.diag() > 0.0) else {
+            return Self::default();
+        };
+        let diag = extents.diag();
+        let mm_per_unit = drawing.units.millimeters_per_unit().unwrap_or(1.0);
+
+        let snap = (diag * SNAP_FACTOR).max(MIN_SNAP_MM / mm_per_unit);
+        let mut view_match = diag * VIEW_MATCH_FACTOR;
+        if let Some(precision) = dimension_precision(drawing) {
+            view_match = view_match.min(precision);
+        }
+        Self {
+            snap,
+            view_match: view_match.max(snap * MIN_VIEW_MATCH_SNAPS),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.snap.is_finite()
+            && self.snap > 0.0
+            && self.view_match.is_finite()
+            && self.view_match > 0.0
+    }
+}
+
+/// Smallest decimal step of any dimension's nominal value, in drawing units.
+///
+/// Only the nominal value counts (see [`nominal_value`]); tolerances, thread
+/// pitches and format codes in the text do not. A step written for a view drawn
+/// at 1:2 is half as fine on the sheet: when a dimension has a measurement, the
+/// step is scaled by the measured length over the written one. Texts without a
+/// number (such as DXF's `<>` placeholder) are ignored.
+pub fn dimension_precision(drawing: &Drawing2D) -> Option<f64> {
+    drawing
+        .dims
+        .iter()
+        .filter_map(|dim| {
+            let (nominal, half_step) = nominal_value(dim.text.as_deref()?)?;
+            let scale = match dim.measurement {
+                Some(m) if m.is_finite() && m != 0.0 && nominal != 0.0 => m.abs() / nominal,
+                _ => 1.0,
+            };
+            Some(2.0 * half_step * scale)
+        })
+        .min_by(f64::total_cmp)
+}
@@ -0,0 +1,120 @@
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{Ellipse2D, Nurbs2D, Primitive2D};
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+fn close(a: Vec2, b: Vec2) -> bool {
+    (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+}
+
+fn quarter_circle(weights: Vec<f64>) -> Nurbs2D {
+    Nurbs2D {
+        degree: 2,
+        control_points: vec![
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0),
+        ],
+        weights,
+        knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+    }
+}
+
+#[test]
+fn ellipses_from_transformed_axes() {
+    // A circle of radius 2 sheared along x: the image of its axes is not
+    // orthogonal, but the ellipse through them is the same curve.
+    let (u, v) = (Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0));
+    let e = Ellipse2D::from_conjugate(Vec2::new(1.0, 1.0), u, v, 0.0, 90.0).unwrap();
+    assert!(e.ratio > 0.0 && e.ratio < 1.0, "{e:?}");
+    assert!(close(e.start_point(), Vec2::new(3.0, 1.0)), "{e:?}");
+    assert!(close(e.end_point(), Vec2::new(3.0, 3.0)), "{e:?}");
+    let mid = e.point_at(e.start_param_deg + e.sweep_deg() / 2.0);
+    let t = 45f64.to_radians();
+    let expected = Vec2::new(
+        1.0 + u.x * t.cos() + v.x * t.sin(),
+        1.0 + u.y * t.cos() + v.y * t.sin(),
+    );
+    assert!(
+        (mid.x - expected.x).hypot(mid.y - expected.y) < 0.1,
+        "{mid:?}"
+    );
+
+    // Mirrored axes and a negative sweep give the same arc, counter-clockwise.
+    let flipped = Ellipse2D::from_conjugate(Vec2::new(0.0, 0.0), v, u, 90.0, -90.0).unwrap();
+    let again = Ellipse2D::from_conjugate(Vec2::new(0.0, 0.0), u, v, 0.0, 90.0).unwrap();
+    assert!(close(flipped.start_point(), again.start_point()));
+    assert!(close(flipped.end_point(), again.end_point()));
+    assert!(Ellipse2D::from_conjugate(Vec2::new(0.0, 0.0), u, u, 0.0, 90.0).is_none());
+
+    // A whole ellipse 4 by 2 and its upper half.
+    let whole = Ellipse2D {
+        center: Vec2::new(0.0, 0.0),
+        major_axis: Vec2::new(4.0, 0.0),
+        ratio: 0.5,
+        start_param_deg: 0.0,
+        end_param_deg: 360.0,
+    };
+    assert!(whole.is_closed());
+    let bbox = Primitive2D::Ellipse(whole.clone()).bbox();
+    assert!(close(bbox.min, Vec2::new(-4.0, -2.0)) && close(bbox.max, Vec2::new(4.0, 2.0)));
+    // Ramanujan's approximation is exact to ~1e-5 here.
+    let h: f64 = (2.0f64 - 1.0).powi(2) / (2.0f64 + 1.0).powi(2);
+    let ramanujan = PI * 3.0 * (1.0 + 3.0 * h / (10.0 + (4.0 - 3.0 * h).sqrt())) * 2.0;
+    assert!(
+        (whole.length() - ramanujan).abs() < 1e-3,
+        "{}",
+        whole.length()
+    );
+    let upper = Ellipse2D {
+        end_param_deg: 180.0,
+        ..whole.clone()
+    };
+    assert!((upper.length() * 2.0 - whole.length()).abs() < 1e-9);
+    assert!((upper.bbox().min.y).abs() < 1e-9);
+    let points = upper.tessellate(0.01);
+    assert!(close(points[0], Vec2::new(4.0, 0.0)));
+    assert!(close(points[points.len() - 1], Vec2::new(-4.0, 0.0)));
+    assert!(points
+        .iter()
+        .all(|p| ((p.x / 4.0).powi(2) + (p.y / 2.0).powi(2) - 1.0).abs() < 1e-9));
+    assert!(points.len() > 10, "{}", points.len());
+}
+
+#[test]
+fn nurbs_evaluate_exactly() {
+    let circle = quarter_circle(vec![1.0, FRAC_1_SQRT_2, 1.0]);
+    assert!(circle.is_valid());
+    for u in [0.0, 0.2, 0.5, 0.9, 1.0] {
+        let p = circle.point_at(u).unwrap();
+        assert!((p.x.hypot(p.y) - 10.0).abs() < 1e-9, "{u}: {p:?}");
+    }
+    assert!(
+        (circle.length() - 5.0 * PI).abs() < 1e-5,
+        "{}",
+        circle.length()
+    );
+    let bbox = circle.bbox();
+    assert!(close(bbox.min, Vec2::new(0.0, 0.0)) && close(bbox.max, Vec2::new(10.0, 10.0)));
+    let points = circle.tessellate(0.01);
+    assert!(close(points[0], Vec2::new(10.0, 0.0)));
+    assert!(close(points[points.len() - 1], Vec2::new(0.0, 10.0)));
+    for w in points.windows(2) {
+        let mid = Vec2::new((w[0].x + w[1].x) / 2.0, (w[0].y + w[1].y) / 2.0);
+        assert!(10.0 - mid.x.hypot(mid.y) < 0.01);
+    }
+
+    // Without weights it is the quadratic Bézier on the same points, which
+    // stays inside the control polygon's hull.
+    let parabola = quarter_circle(Vec::new());
+    assert!(close(parabola.point_at(0.5).unwrap(), Vec2::new(7.5, 7.5)));
+    assert!(parabola.bbox().max.x <= 10.0);
+
+    // Knots that do not fit fall back to the control polygon.
+    let broken = Nurbs2D {
+        knots: vec![0.0, 1.0],
+        ..parabola
+    };
+    assert!(!broken.is_valid());
+    assert_eq!(None, broken.point_at(0.5));
+    assert_eq!(broken.control_points, broken.tessellate(0.01));
+}
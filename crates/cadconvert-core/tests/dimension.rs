@@ -0,0 +1,70 @@
+use cadconvert_core::dimension::{check_dimension, nominal_value, text_numbers, DimensionStatus};
+use cadconvert_core::model::{DimensionEntity, Provenance, Style};
+
+fn dim(text: Option<&str>, measurement: Option<f64>) -> DimensionEntity {
+    DimensionEntity {
+        id: 1,
+        raw_type: None,
+        text: text.map(str::to_string),
+        measurement,
+        at: None,
+        definition: None,
+        style: Style::default(),
+        source: Provenance::default(),
+    }
+}
+
+#[test]
+fn reads_numbers_and_their_precision() {
+    assert_eq!(vec![(12.5, 0.05)], text_numbers("R12.5"));
+    assert_eq!(vec![(2.0, 0.5), (6.0, 0.5)], text_numbers("2X %%c6"));
+    assert_eq!(vec![(10.0, 0.5)], text_numbers("10."));
+    assert!(text_numbers("<>").is_empty());
+}
+
+#[test]
+fn nominal_value_skips_counts_tolerances_and_format_codes() {
+    assert_eq!(Some((6.0, 0.5)), nominal_value("2X %%c6"));
+    assert_eq!(Some((12.5, 0.05)), nominal_value("12.5 %%p0.02"));
+    assert_eq!(Some((10.0, 0.5)), nominal_value("M10x1.5"));
+    assert_eq!(Some((40.0, 0.5)), nominal_value("\\A1;{\\H2.5x;\\C1;40}"));
+    assert_eq!(Some((25.0, 0.5)), nominal_value("25\\S+0.005^-0.002;"));
+    assert_eq!(None, nominal_value("<> %%p0.01"));
+}
+
+#[test]
+fn checks_text_against_measurement() {
+    assert_eq!(DimensionStatus::Ok, check_dimension(&dim(None, Some(10.0))));
+    assert_eq!(
+        DimensionStatus::Ok,
+        check_dimension(&dim(Some("<> TYP"), Some(10.0)))
+    );
+    assert_eq!(
+        DimensionStatus::Ok,
+        check_dimension(&dim(Some("10.0"), Some(10.04)))
+    );
+    // One matching number is enough.
+    assert_eq!(
+        DimensionStatus::Ok,
+        check_dimension(&dim(Some("2X 6"), Some(6.0)))
+    );
+    // Text alone still gives a value.
+    assert_eq!(DimensionStatus::Ok, check_dimension(&dim(Some("12"), None)));
+
+    assert_eq!(
+        DimensionStatus::Conflict { measured: 10.0 },
+        check_dimension(&dim(Some("10.5"), Some(10.0)))
+    );
+    assert_eq!(
+        DimensionStatus::Unresolved,
+        check_dimension(&dim(Some("<>"), Some(0.0)))
+    );
+    assert_eq!(
+        DimensionStatus::Unresolved,
+        check_dimension(&dim(Some("A"), None))
+    );
+    assert_eq!(
+        DimensionStatus::Unresolved,
+        check_dimension(&dim(None, None))
+    );
+}
@@ -0,0 +1,296 @@
+use cadconvert_core::geom::{bulge_arc, BBox2, Vec2};
+use cadconvert_core::model::{
+    Arc2D, Bezier2D, Circle2D, LineSeg2D, Polyline2D, PolylineVertex2D, Primitive2D,
+};
+use std::f64::consts::PI;
+
+fn close(a: Vec2, b: Vec2) -> bool {
+    a.distance(b) < 1e-9
+}
+
+fn line(ax: f64, ay: f64, bx: f64, by: f64) -> Primitive2D {
+    Primitive2D::Line(LineSeg2D {
+        a: Vec2::new(ax, ay),
+        b: Vec2::new(bx, by),
+    })
+}
+
+fn arc(x: f64, y: f64, radius: f64, start: f64, end: f64) -> Primitive2D {
+    Primitive2D::Arc(Arc2D {
+        center: Vec2::new(x, y),
+        radius,
+        start_angle_deg: start,
+        end_angle_deg: end,
+    })
+}
+
+/// A square 10 across whose top edge bulges up into a half circle.
+fn domed_square() -> Primitive2D {
+    let vertex = |x, y, bulge| PolylineVertex2D {
+        pos: Vec2::new(x, y),
+        bulge,
+    };
+    Primitive2D::Polyline(Polyline2D {
+        vertices: vec![
+            vertex(0.0, 0.0, 0.0),
+            vertex(10.0, 0.0, 0.0),
+            vertex(10.0, 10.0, 1.0),
+            vertex(0.0, 10.0, 0.0),
+        ],
+        closed: true,
+    })
+}
+
+fn s_curve() -> Bezier2D {
+    Bezier2D {
+        p0: Vec2::new(0.0, 0.0),
+        p1: Vec2::new(0.0, 10.0),
+        p2: Vec2::new(10.0, -10.0),
+        p3: Vec2::new(10.0, 0.0),
+    }
+}
+
+fn sorted(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points
+}
+
+#[test]
+fn vector_arithmetic() {
+    let (a, b) = (Vec2::new(3.0, 4.0), Vec2::new(1.0, -2.0));
+    assert_eq!(Vec2::new(4.0, 2.0), a + b);
+    assert_eq!(Vec2::new(2.0, 6.0), a - b);
+    assert_eq!(Vec2::new(6.0, 8.0), a * 2.0);
+    assert_eq!(Vec2::new(-1.5, -2.0), -a / 2.0);
+    assert_eq!(-5.0, a.dot(b));
+    assert_eq!(-10.0, a.cross(b));
+    assert_eq!(5.0, a.length());
+    assert_eq!(Vec2::new(-4.0, 3.0), a.perp());
+    assert!(close(Vec2::new(0.6, 0.8), a.normalized()));
+    assert_eq!(Vec2::ZERO, Vec2::ZERO.normalized());
+    assert!(close(Vec2::new(2.0, 1.0), a.lerp(b, 0.5)));
+    assert!(close(Vec2::new(0.0, 1.0), Vec2::from_angle(PI / 2.0)));
+
+    let bbox = BBox2::from_points([a, b]);
+    assert!(bbox.contains(Vec2::new(2.0, 0.0)) && bbox.contains(a));
+    assert!(!bbox.contains(Vec2::new(0.0, 0.0)));
+}
+
+#[test]
+fn bounding_boxes_are_exact() {
+    // A quarter arc in the first quadrant, and one across the top.
+    let quarter = arc(0.0, 0.0, 2.0, 0.0, 90.0).bbox();
+    assert!(close(quarter.min, Vec2::new(0.0, 0.0)) && close(quarter.max, Vec2::new(2.0, 2.0)));
+    let top = arc(0.0, 0.0, 2.0, 45.0, 135.0).bbox();
+    assert!((top.max.y - 2.0).abs() < 1e-9 && (top.min.y - 2f64.sqrt()).abs() < 1e-9);
+    let wrapped = arc(0.0, 0.0, 1.0, 270.0, 90.0).bbox();
+    assert!(close(wrapped.min, Vec2::new(0.0, -1.0)) && close(wrapped.max, Vec2::new(1.0, 1.0)));
+
+    let dome = domed_square().bbox();
+    assert!(close(dome.min, Vec2::new(0.0, 0.0)) && close(dome.max, Vec2::new(10.0, 15.0)));
+
+    // The S curve turns back at a height of 10·√3/6, well inside its
+    // control points.
+    let s = Primitive2D::CubicBezier(s_curve()).bbox();
+    assert!((s.max.y - 10.0 * 3f64.sqrt() / 6.0).abs() < 1e-9, "{s:?}");
+    assert!((s.min.y + s.max.y).abs() < 1e-9);
+    assert_eq!((0.0, 10.0), (s.min.x, s.max.x));
+}
+
+#[test]
+fn lengths_and_tessellation() {
+    assert_eq!(5.0, line(0.0, 0.0, 3.0, 4.0).length());
+    assert!((arc(0.0, 0.0, 2.0, 0.0, 90.0).length() - PI).abs() < 1e-12);
+    assert!((domed_square().length() - (30.0 + 5.0 * PI)).abs() < 1e-9);
+    let straight = Bezier2D {
+        p0: Vec2::new(0.0, 0.0),
+        p1: Vec2::new(1.0, 0.0),
+        p2: Vec2::new(2.0, 0.0),
+        p3: Vec2::new(3.0, 0.0),
+    };
+    assert!((Primitive2D::CubicBezier(straight).length() - 3.0).abs() < 1e-9);
+
+    // The dome's points stay within tolerance of it and close the outline.
+    let points = domed_square().tessellate(0.01);
+    assert!(close(points[0], *points.last().unwrap()));
+    assert!(points.len() > 10, "{}", points.len());
+    let above: Vec<Vec2> = points.iter().copied().filter(|p| p.y > 10.0).collect();
+    assert!(above
+        .iter()
+        .all(|p| (p.distance(Vec2::new(5.0, 10.0)) - 5.0).abs() < 1e-9));
+    for w in points
+        .windows(2)
+        .filter(|w| w[0].y >= 10.0 && w[1].y >= 10.0)
+    {
+        assert!(5.0 - w[0].lerp(w[1], 0.5).distance(Vec2::new(5.0, 10.0)) <= 0.01);
+    }
+
+    let circle = Circle2D {
+        center: Vec2::new(1.0, 1.0),
+        radius: 1.0,
+    };
+    let points = Primitive2D::Circle(circle).tessellate(1e-3);
+    assert!(close(points[0], Vec2::new(2.0, 1.0)) && close(points[0], points[points.len() - 1]));
+    let curve = Primitive2D::CubicBezier(s_curve()).tessellate(1e-3);
+    assert_eq!(
+        (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)),
+        (curve[0], curve[curve.len() - 1])
+    );
+}
+
+#[test]
+fn closest_points() {
+    let p = Vec2::new(5.0, 5.0);
+    assert!(close(
+        Vec2::new(3.0, 0.0),
+        line(0.0, 0.0, 3.0, 0.0).closest_point(p)
+    ));
+    assert!(close(
+        Vec2::new(5.0, 0.0),
+        line(0.0, 0.0, 10.0, 0.0).closest_point(p)
+    ));
+    // Toward the point when the arc passes it, else the nearer end.
+    let quarter = arc(0.0, 0.0, 2.0, 0.0, 90.0);
+    let expected = Vec2::new(2f64.sqrt(), 2f64.sqrt());
+    assert!(close(expected, quarter.closest_point(p)));
+    assert!(close(
+        Vec2::new(2.0, 0.0),
+        quarter.closest_point(Vec2::new(5.0, -1.0))
+    ));
+    assert!(close(
+        Vec2::new(5.0, 15.0),
+        domed_square().closest_point(Vec2::new(5.0, 20.0))
+    ));
+    let near = Primitive2D::CubicBezier(s_curve()).closest_point(Vec2::new(0.0, 10.0));
+    assert!(near.y > 0.0 && near.x < 5.0, "{near:?}");
+}
+
+#[test]
+fn intersections() {
+    let cross = line(0.0, 0.0, 10.0, 10.0).intersections(&line(0.0, 10.0, 10.0, 0.0));
+    assert_eq!(1, cross.len());
+    assert!(close(Vec2::new(5.0, 5.0), cross[0]));
+    assert!(line(0.0, 0.0, 1.0, 0.0)
+        .intersections(&line(2.0, 0.0, 3.0, 1.0))
+        .is_empty());
+    // Overlapping collinear lines meet along a run: its ends.
+    let overlap = sorted(line(0.0, 0.0, 4.0, 0.0).intersections(&line(2.0, 0.0, 6.0, 0.0)));
+    assert_eq!(vec![Vec2::new(2.0, 0.0), Vec2::new(4.0, 0.0)], overlap);
+
+    let circle = Primitive2D::Circle(Circle2D {
+        center: Vec2::new(0.0, 0.0),
+        radius: 5.0,
+    });
+    let through = sorted(line(-10.0, 3.0, 10.0, 3.0).intersections(&circle));
+    assert_eq!(2, through.len());
+    assert!(close(Vec2::new(-4.0, 3.0), through[0]) && close(Vec2::new(4.0, 3.0), through[1]));
+    let tangent = line(-10.0, 5.0, 10.0, 5.0).intersections(&circle);
+    assert_eq!(1, tangent.len());
+    assert!(close(Vec2::new(0.0, 5.0), tangent[0]));
+    // Only the half of the crossing that the arc covers counts.
+    let upper = arc(0.0, 0.0, 5.0, 0.0, 180.0);
+    let hits = line(3.0, -10.0, 3.0, 10.0).intersections(&upper);
+    assert_eq!(1, hits.len());
+    assert!(close(Vec2::new(3.0, 4.0), hits[0]));
+
+    let other = Primitive2D::Circle(Circle2D {
+        center: Vec2::new(8.0, 0.0),
+        radius: 5.0,
+    });
+    let lens = sorted(circle.intersections(&other));
+    assert_eq!(2, lens.len());
+    assert!(close(Vec2::new(4.0, -3.0), lens[0]) && close(Vec2::new(4.0, 3.0), lens[1]));
+    assert_eq!(1, upper.intersections(&other).len());
+
+    // A polyline's bulge is a real arc; the S curve crosses its own chord
+    // in the middle.
+    let dome = domed_square().intersections(&line(5.0, 12.0, 5.0, 20.0));
+    assert!(dome.len() == 1 && close(Vec2::new(5.0, 15.0), dome[0]));
+    let chord = Primitive2D::CubicBezier(s_curve()).intersections(&line(-1.0, 0.0, 11.0, 0.0));
+    let chord = sorted(chord);
+    assert_eq!(3, chord.len(), "{chord:?}");
+    assert!(chord[1].distance(Vec2::new(5.0, 0.0)) < 1e-4);
+}
+
+#[test]
+fn bulge_arcs() {
+    let (center, radius, start, sweep) =
+        bulge_arc(Vec2::new(10.0, 10.0), Vec2::new(0.0, 10.0), 1.0).unwrap();
+    assert!(close(Vec2::new(5.0, 10.0), center));
+    assert!((radius - 5.0).abs() < 1e-12 && start.abs() < 1e-12 && (sweep - PI).abs() < 1e-12);
+    assert_eq!(None, bulge_arc(Vec2::ZERO, Vec2::new(1.0, 0.0), 0.0));
+}
+
+#[test]
+fn clipping_to_a_rectangle() {
+    let rect = BBox2::new(Vec2::ZERO, Vec2::new(10.0, 10.0));
+    assert_eq!(
+        vec![line(0.0, 5.0, 10.0, 5.0)],
+        line(-5.0, 5.0, 15.0, 5.0).clip(&rect)
+    );
+    assert!(line(20.0, 20.0, 30.0, 30.0).clip(&rect).is_empty());
+    assert_eq!(vec![domed_square()], domed_square().clip(&rect.expand(5.0)));
+
+    // Half of a circle on the right edge; the left one is joined across 0°.
+    let circle = |x, y, radius| {
+        Primitive2D::Circle(Circle2D {
+            center: Vec2::new(x, y),
+            radius,
+        })
+    };
+    let sweep = |p: &Primitive2D| match p {
+        Primitive2D::Arc(a) => (a.start_point(), a.sweep_deg(), a.end_point()),
+        other => panic!("expected an arc, got {other:?}"),
+    };
+    let [right] = &circle(10.0, 5.0, 2.0).clip(&rect)[..] else {
+        panic!("expected one arc");
+    };
+    let (start, degrees, end) = sweep(right);
+    assert!(close(Vec2::new(10.0, 7.0), start) && close(Vec2::new(10.0, 3.0), end));
+    assert!((degrees - 180.0).abs() < 1e-9);
+    let [left] = &circle(0.0, 5.0, 2.0).clip(&rect)[..] else {
+        panic!("expected one arc");
+    };
+    let (start, degrees, end) = sweep(left);
+    assert!(close(Vec2::new(0.0, 3.0), start) && close(Vec2::new(0.0, 7.0), end));
+    assert!((degrees - 180.0).abs() < 1e-9);
+    // Poking out of every edge leaves the four corners.
+    assert_eq!(4, circle(5.0, 5.0, 6.0).clip(&rect).len());
+
+    // The dome's top is cut off and the outline opened there.
+    let cut = domed_square().clip(&BBox2::new(Vec2::ZERO, Vec2::new(10.0, 12.0)));
+    let [Primitive2D::Polyline(open)] = &cut[..] else {
+        panic!("expected one polyline, got {cut:?}");
+    };
+    assert!(!open.closed);
+    let x = 5.0 - 21f64.sqrt();
+    let expected = [
+        (x, 12.0),
+        (0.0, 10.0),
+        (0.0, 0.0),
+        (10.0, 0.0),
+        (10.0, 10.0),
+        (10.0 - x, 12.0),
+    ];
+    assert_eq!(expected.len(), open.vertices.len(), "{open:?}");
+    for (v, (x, y)) in open.vertices.iter().zip(expected) {
+        assert!(close(Vec2::new(x, y), v.pos), "{open:?}");
+    }
+    let quarter = |degrees: f64| (degrees.to_radians() / 4.0).tan();
+    let angle = (2.0f64 / 5.0).asin().to_degrees();
+    assert!((open.vertices[0].bulge - quarter(angle)).abs() < 1e-9);
+    assert!((open.vertices[4].bulge - quarter(angle)).abs() < 1e-9);
+    assert_eq!(0.0, open.vertices[1].bulge);
+
+    // Curves are flattened where they cross an edge.
+    let half = Primitive2D::CubicBezier(s_curve())
+        .clip(&BBox2::new(Vec2::new(-1.0, -10.0), Vec2::new(5.0, 10.0)));
+    let [Primitive2D::Polyline(flat)] = &half[..] else {
+        panic!("expected one polyline, got {half:?}");
+    };
+    assert!(close(Vec2::ZERO, flat.vertices[0].pos));
+    assert!(close(
+        Vec2::new(5.0, 0.0),
+        flat.vertices.last().unwrap().pos
+    ));
+}
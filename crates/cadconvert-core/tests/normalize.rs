@@ -1,5 +1,5 @@
 use cadconvert_core::geom::Vec2;
-use cadconvert_core::model::{Drawing2D, Entity2D, EntityKind, LineSeg2D, Primitive2D, Style, Units};
+use cadconvert_core::model::{Drawing2D, Entity2D, EntityKind, ImportLog, LineSeg2D, Primitive2D, Provenance, Style, Units};
 use cadconvert_core::normalize::{normalize_in_place, NormalizeConfig};
 
 #[test]
@@ -18,7 +18,9 @@ fn drops_degenerate_entities_and_inferrs_kind() {
                     layer: None,
                     linetype: Some("HIDDEN".to_string()),
                     color_index: None,
+                    lineweight: None,
                 },
+                source: Provenance::default(),
             },
             Entity2D {
                 id: 2,
@@ -31,11 +33,14 @@ fn drops_degenerate_entities_and_inferrs_kind() {
                     layer: None,
                     linetype: Some("HIDDEN".to_string()),
                     color_index: None,
+                    lineweight: None,
                 },
+                source: Provenance::default(),
             },
         ],
         dims: Vec::new(),
         texts: Vec::new(),
+        import_log: ImportLog::default(),
     };
 
     let stats = normalize_in_place(&mut drawing, &NormalizeConfig::default());
@@ -0,0 +1,170 @@
+use cadconvert_core::analysis::{AnalysisConfig, Analyzer};
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{
+    DimensionEntity, Drawing2D, Entity2D, EntityKind, ImportLog, LineSeg2D, Primitive2D,
+    Provenance, Style, Units,
+};
+use cadconvert_core::schema::{
+    drawing_from_json, drawing_from_jsonl, drawing_to_jsonl, json_schema, report_from_json,
+    to_json_pretty, DocumentKind, SchemaError, SCHEMA_VERSION,
+};
+use cadconvert_core::tolerance::Tolerances;
+use serde_json::{json, Value};
+
+fn drawing() -> Drawing2D {
+    Drawing2D {
+        units: Units::Millimeters,
+        entities: vec![Entity2D {
+            id: 1,
+            kind: EntityKind::Object,
+            primitive: Primitive2D::Line(LineSeg2D {
+                a: Vec2::new(0.0, 0.0),
+                b: Vec2::new(10.0, 0.0),
+            }),
+            style: Style::default(),
+            source: Provenance::default(),
+        }],
+        dims: vec![DimensionEntity {
+            id: 2,
+            raw_type: None,
+            text: Some("10".to_string()),
+            measurement: Some(10.0),
+            at: Some(Vec2::new(5.0, 2.0)),
+            definition: None,
+            style: Style::default(),
+            source: Provenance::default(),
+        }],
+        texts: Vec::new(),
+        import_log: ImportLog::default(),
+    }
+}
+
+#[test]
+fn documents_round_trip_with_their_version() {
+    let drawing = drawing();
+    let json = to_json_pretty(&drawing).unwrap();
+    let value: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json!(SCHEMA_VERSION), value["schema_version"]);
+    assert_eq!(drawing, drawing_from_json(&json).unwrap());
+
+    let report = Analyzer::new(AnalysisConfig::default()).analyze("dxf", &drawing);
+    let json = to_json_pretty(&report).unwrap();
+    let back = report_from_json(&json).unwrap();
+    assert_eq!(report.stats.entities_total, back.stats.entities_total);
+    // serde_json's default float parsing is not bit-exact.
+    assert!((report.tolerances.snap - back.tolerances.snap).abs() < 1e-12);
+}
+
+#[test]
+fn unversioned_files_are_migrated() {
+    // As written before versioning: no schema_version, tolerances, warning
+    // entity ids or dimension positions.
+    let report = json!({
+        "input_format": "svg",
+        "stats": {
+            "entities_total": 3,
+            "entities_normalized": 3,
+            "removed_degenerate_entities": 0,
+            "inferred_kinds": 0,
+            "dims_total": 0,
+            "texts_total": 0
+        },
+        "extents": null,
+        "view_clusters": [],
+        "view_assignment": null,
+        "warnings": [{ "code": "views_not_detected", "message": "..." }]
+    });
+    let report = report_from_json(&report.to_string()).unwrap();
+    assert_eq!(Tolerances::default(), report.tolerances);
+    assert!(report.warnings[0].entity_ids.is_empty());
+
+    let mut old = serde_json::to_value(drawing()).unwrap();
+    old["dims"][0].as_object_mut().unwrap().remove("at");
+    let drawing = drawing_from_json(&old.to_string()).unwrap();
+    assert_eq!(None, drawing.dims[0].at);
+}
+
+#[test]
+fn newer_and_malformed_files_fail_clearly() {
+    let mut newer = serde_json::to_value(drawing()).unwrap();
+    newer["schema_version"] = json!(SCHEMA_VERSION + 1);
+    let err = drawing_from_json(&newer.to_string()).unwrap_err();
+    assert!(matches!(err, SchemaError::Newer { found } if found == SCHEMA_VERSION + 1));
+    assert!(err.to_string().contains("upgrade cadconvert"));
+
+    let err = drawing_from_json(r#"{"schema_version": "one"}"#).unwrap_err();
+    assert!(matches!(err, SchemaError::BadVersion));
+
+    let err = drawing_from_json(r#"{"schema_version": 1, "units": "Millimeters"}"#).unwrap_err();
+    assert!(matches!(err, SchemaError::Invalid { version: 1, .. }));
+    assert!(err.to_string().contains("entities"), "{err}");
+
+    assert!(matches!(
+        report_from_json("{").unwrap_err(),
+        SchemaError::Json(_)
+    ));
+}
+
+#[test]
+fn schemas_describe_the_versioned_documents() {
+    for (kind, field) in [
+        (DocumentKind::Report, "view_clusters"),
+        (DocumentKind::Drawing, "entities"),
+    ] {
+        let schema = serde_json::to_value(json_schema(kind)).unwrap();
+        let properties = &schema["properties"];
+        assert!(properties["schema_version"].is_object(), "{kind:?}");
+        assert!(properties[field].is_object(), "{kind:?}");
+        assert!(schema["title"]
+            .as_str()
+            .unwrap()
+            .ends_with(&format!("(schema version {SCHEMA_VERSION})")));
+    }
+    let drawing = serde_json::to_value(json_schema(DocumentKind::Drawing)).unwrap();
+    assert!(drawing["$defs"]["Primitive2D"].is_object());
+}
+
+#[test]
+fn drawings_stream_as_json_lines() {
+    let drawing = drawing();
+    let mut out = Vec::new();
+    drawing_to_jsonl(&drawing, &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(3, text.lines().count());
+    let header = format!(r#"{{"record":"header","schema_version":{SCHEMA_VERSION},"#);
+    assert!(text.starts_with(&header), "{text}");
+    assert_eq!(drawing, drawing_from_jsonl(text.as_bytes()).unwrap());
+
+    // Records may come in any order, with blank lines between them.
+    let mut lines: Vec<&str> = text.lines().collect();
+    lines.swap(1, 2);
+    let shuffled = lines.join("\n\n");
+    assert_eq!(drawing, drawing_from_jsonl(shuffled.as_bytes()).unwrap());
+}
+
+#[test]
+fn json_lines_need_a_readable_header() {
+    let entity = r#"{"record":"entity","id":1}"#;
+    let err = drawing_from_jsonl(entity.as_bytes()).unwrap_err();
+    assert!(matches!(
+        err,
+        SchemaError::Line { line: 1, ref error } if matches!(**error, SchemaError::MissingHeader)
+    ));
+    assert!(matches!(
+        drawing_from_jsonl("\n".as_bytes()).unwrap_err(),
+        SchemaError::MissingHeader
+    ));
+
+    let newer = format!(
+        r#"{{"record":"header","schema_version":{},"units":"Millimeters"}}"#,
+        SCHEMA_VERSION + 1
+    );
+    let err = drawing_from_jsonl(newer.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("upgrade cadconvert"), "{err}");
+
+    let header = r#"{"record":"header","schema_version":1,"units":"Millimeters"}"#;
+    let err = drawing_from_jsonl(format!("{header}\n{entity}").as_bytes()).unwrap_err();
+    assert!(err.to_string().starts_with("line 2: "), "{err}");
+    let err = drawing_from_jsonl(format!("{header}\n{header}").as_bytes()).unwrap_err();
+    assert!(matches!(err, SchemaError::Line { line: 2, .. }), "{err}");
+}
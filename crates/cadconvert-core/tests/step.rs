@@ -1,7 +1,14 @@
-use cadconvert_core::geom::Vec2;
+use cadconvert_core::geom::{BBox2, Vec2};
 use cadconvert_core::model::{
-    Circle2D, Drawing2D, Entity2D, EntityKind, LineSeg2D, Primitive2D, Style, Units,
+    Arc2D, Circle2D, Drawing2D, Ellipse2D, Entity2D, EntityKind, ImportLog, LineSeg2D, Nurbs2D,
+    Polyline2D, PolylineVertex2D, Primitive2D, Provenance, Style, Units,
 };
+use cadconvert_core::step::{
+    wireframe_step, Entity, Param, PlacedView, Schema, StepError, StepFile, StepId, StepOptions,
+    StepWriter, Value,
+};
+use cadconvert_core::tolerance::Tolerances;
+use cadconvert_core::view::ViewRole;
 
 #[test]
 fn writes_basic_step_wireframe() {
@@ -19,7 +26,9 @@ fn writes_basic_step_wireframe() {
                     layer: None,
                     linetype: None,
                     color_index: None,
+                    lineweight: None,
                 },
+                source: Provenance::default(),
             },
             Entity2D {
                 id: 2,
@@ -32,17 +41,484 @@ fn writes_basic_step_wireframe() {
                     layer: None,
                     linetype: None,
                     color_index: None,
+                    lineweight: None,
                 },
+                source: Provenance::default(),
             },
         ],
         dims: Vec::new(),
         texts: Vec::new(),
+        import_log: ImportLog::default(),
     };
 
-    let step = cadconvert_core::step::wireframe_step(&drawing, "part");
+    let step = wireframe_step(
+        &drawing,
+        "part",
+        &[],
+        &Tolerances::default(),
+        &StepOptions::default(),
+    )
+    .unwrap();
     assert!(step.contains("ISO-10303-21;"));
-    assert!(step.contains("FILE_SCHEMA(('AUTOMOTIVE_DESIGN_CC2'));"));
+    assert!(step.contains("FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));"));
     assert!(step.contains("GEOMETRIC_CURVE_SET"));
     assert!(step.contains("POLYLINE"));
+    assert!(step.contains("CIRCLE('',"));
     assert!(step.contains("CARTESIAN_POINT"));
+    assert!(!step.contains("MAPPED_ITEM"));
+    assert!(step.contains("UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(0.0001),"));
+}
+
+fn object(id: u64, primitive: Primitive2D) -> Entity2D {
+    Entity2D {
+        id,
+        kind: EntityKind::Object,
+        primitive,
+        style: Style::default(),
+        source: Provenance::default(),
+    }
+}
+
+fn square(x: f64, y: f64) -> Primitive2D {
+    Primitive2D::Polyline(Polyline2D {
+        vertices: [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]
+            .iter()
+            .map(|&(dx, dy)| PolylineVertex2D {
+                pos: Vec2::new(x + dx, y + dy),
+                bulge: 0.0,
+            })
+            .collect(),
+        closed: true,
+    })
+}
+
+#[test]
+fn wireframe_folds_assigned_views_and_keeps_exact_curves() {
+    let drawing = Drawing2D {
+        units: Units::Millimeters,
+        entities: vec![
+            object(1, square(0.0, 0.0)),
+            object(2, square(0.0, 20.0)),
+            object(3, square(20.0, 0.0)),
+            object(
+                4,
+                Primitive2D::Arc(Arc2D {
+                    center: Vec2::new(5.0, 25.0),
+                    radius: 2.0,
+                    start_angle_deg: 0.0,
+                    end_angle_deg: 90.0,
+                }),
+            ),
+            // Outside every view: stays on the sheet.
+            object(
+                5,
+                Primitive2D::Line(LineSeg2D {
+                    a: Vec2::new(100.0, 100.0),
+                    b: Vec2::new(110.0, 100.0),
+                }),
+            ),
+        ],
+        dims: Vec::new(),
+        texts: Vec::new(),
+        import_log: ImportLog::default(),
+    };
+    let view = |role, x, y| PlacedView {
+        role,
+        bbox: BBox2::new(Vec2::new(x, y), Vec2::new(x + 10.0, y + 10.0)),
+    };
+    let views = [
+        view(ViewRole::Front, 0.0, 0.0),
+        view(ViewRole::Top, 0.0, 20.0),
+        view(ViewRole::Right, 20.0, 0.0),
+    ];
+
+    let step = wireframe_step(
+        &drawing,
+        "part",
+        &views,
+        &Tolerances::default(),
+        &StepOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(3, step.matches("MAPPED_ITEM(").count());
+    assert_eq!(3, step.matches("REPRESENTATION_MAP(").count());
+    assert!(step.contains("DIRECTION('',(0.,-1.,0.))"));
+    assert!(step.contains("DIRECTION('',(1.,0.,0.))"));
+    // Top view sits at the front view's height.
+    assert!(step.contains("CARTESIAN_POINT('',(0.,0.,10.))"));
+    // Right view sits at the front view's width.
+    assert!(step.contains("CARTESIAN_POINT('',(10.,0.,0.))"));
+    assert!(step.contains("TRIMMED_CURVE("));
+    assert!(step.contains("PARAMETER_VALUE(1.5707963267948966)"));
+    assert!(step.contains("CARTESIAN_POINT('',(110.,100.,0.))"));
+}
+
+#[test]
+fn wireframe_writes_ellipses_and_splines_exactly() {
+    let ellipse = |start, end| Ellipse2D {
+        center: Vec2::new(5.0, 5.0),
+        major_axis: Vec2::new(0.0, 4.0),
+        ratio: 0.5,
+        start_param_deg: start,
+        end_param_deg: end,
+    };
+    let spline = |weights| Nurbs2D {
+        degree: 2,
+        control_points: vec![
+            Vec2::new(5.0, 0.0),
+            Vec2::new(5.0, 5.0),
+            Vec2::new(0.0, 5.0),
+        ],
+        weights,
+        knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+    };
+    let drawing = Drawing2D {
+        units: Units::Millimeters,
+        entities: vec![
+            object(1, Primitive2D::Ellipse(ellipse(0.0, 360.0))),
+            object(2, Primitive2D::Ellipse(ellipse(0.0, 90.0))),
+            object(3, Primitive2D::Nurbs(spline(Vec::new()))),
+            object(4, Primitive2D::Nurbs(spline(vec![1.0, 0.5, 1.0]))),
+        ],
+        dims: Vec::new(),
+        texts: Vec::new(),
+        import_log: ImportLog::default(),
+    };
+
+    let step = wireframe_step(
+        &drawing,
+        "part",
+        &[],
+        &Tolerances::default(),
+        &StepOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(2, step.matches("ELLIPSE('',").count());
+    assert!(step.contains(",4.,2.);"), "{step}");
+    // Only the arc is trimmed, a quarter turn of the eccentric angle.
+    assert_eq!(1, step.matches("TRIMMED_CURVE(").count());
+    assert!(step.contains("PARAMETER_VALUE(1.5707963267948966)"));
+    assert!(step.contains("CARTESIAN_POINT('',(3.,5.,0.))"));
+    assert!(step.contains("B_SPLINE_CURVE_WITH_KNOTS('',2,"));
+    assert!(step.contains("(3,3),(0.,1.),.UNSPECIFIED.)"));
+    assert!(step.contains("RATIONAL_B_SPLINE_CURVE((1.,0.5,1.))"));
+    StepFile::parse(&step).unwrap();
+}
+
+#[test]
+fn writer_rejects_undefined_references() {
+    let mut writer = StepWriter::new(&StepOptions::default());
+    let reserved = writer.reserve();
+    writer.add(Entity::new(
+        "VERTEX_POINT",
+        vec!["".into(), reserved.into()],
+    ));
+    assert_eq!(
+        Err(StepError::Undefined(reserved)),
+        writer.finish("a.step", "")
+    );
+
+    let mut writer = StepWriter::new(&StepOptions::default());
+    let point = writer.cartesian_point([0.0, 0.0, 0.0]);
+    let vertex = writer.vertex_point(point);
+    let bogus = {
+        let mut other = StepWriter::new(&StepOptions::default());
+        for _ in 0..5 {
+            other.cartesian_point([0.0, 0.0, 0.0]);
+        }
+        other.cartesian_point([1.0, 1.0, 1.0])
+    };
+    writer.add(Entity::new(
+        "EDGE_CURVE",
+        vec!["".into(), vertex.into(), bogus.into()],
+    ));
+    assert!(matches!(
+        writer.finish("a.step", ""),
+        Err(StepError::DanglingReference { to, .. }) if to == bogus
+    ));
+}
+
+#[test]
+fn writer_rejects_references_of_the_wrong_type() {
+    let mut writer = StepWriter::new(&StepOptions::default());
+    let direction = writer.direction([1.0, 0.0, 0.0]);
+    let vertex = writer.vertex_point(direction);
+    assert_eq!(
+        Err(StepError::WrongReferenceType {
+            from: vertex,
+            to: direction,
+            expected: "CARTESIAN_POINT".to_string(),
+            found: "DIRECTION".to_string(),
+        }),
+        writer.finish("a.step", "")
+    );
+
+    let mut writer = StepWriter::new(&StepOptions::default());
+    let (a, b) = (
+        writer.cartesian_point([0.0, 0.0, 0.0]),
+        writer.cartesian_point([1.0, 0.0, 0.0]),
+    );
+    let (va, vb) = (writer.vertex_point(a), writer.vertex_point(b));
+    let edge = writer.edge_curve(va, vb, a, true);
+    assert!(matches!(
+        writer.finish("a.step", ""),
+        Err(StepError::WrongReferenceType { from, to, .. }) if from == edge && to == a
+    ));
+}
+
+#[test]
+fn writer_rejects_non_finite_reals() {
+    let mut writer = StepWriter::new(&StepOptions::default());
+    writer.cartesian_point([0.0, 0.0, 0.0]);
+    let point = writer.cartesian_point([1.0, f64::NAN, 0.0]);
+    assert_eq!(
+        Err(StepError::NonFiniteReal(point)),
+        writer.finish("a.step", "")
+    );
+
+    let mut writer = StepWriter::new(&StepOptions::default());
+    let direction = writer.direction([1.0, 0.0, 0.0]);
+    let vector = writer.vector(direction, f64::INFINITY);
+    assert_eq!(
+        Err(StepError::NonFiniteReal(vector)),
+        writer.finish("a.step", "")
+    );
+}
+
+#[test]
+fn writer_resolves_forward_references() {
+    let mut writer = StepWriter::new(&StepOptions::default());
+    let point = writer.reserve();
+    let vertex = writer.vertex_point(point);
+    writer
+        .define(
+            point,
+            Entity::new(
+                "CARTESIAN_POINT",
+                vec!["".into(), Param::reals(&[1.0, -0.0, 0.25])],
+            ),
+        )
+        .unwrap();
+    assert_eq!(
+        Err(StepError::DuplicateId(point)),
+        writer.define(point, Entity::new("CARTESIAN_POINT", vec![]))
+    );
+    let step = writer.finish("a.step", "").unwrap();
+    assert!(step.contains(&format!("{point}=CARTESIAN_POINT('',(1.,0.,0.25));")));
+    assert!(step.contains(&format!("{vertex}=VERTEX_POINT('',{point});")));
+}
+
+#[test]
+fn writer_emits_schema_units_and_header() {
+    let options = StepOptions {
+        schema: Schema::Ap242,
+        timestamp: Some("2024-01-02T03:04:05".to_string()),
+        author: Some("Zoë O'Neil".to_string()),
+        organization: None,
+    };
+    let mut writer = StepWriter::new(&options);
+    let product = writer.product("bracket");
+    let context = writer.representation_context(Units::Inches, 1e-6);
+    writer.shape_representation(&product, "SHAPE_REPRESENTATION", "", &[], context);
+    let step = writer.finish("bracket.step", "test").unwrap();
+
+    assert!(step.contains("AP242_MANAGED_MODEL_BASED_3D_ENGINEERING_MIM_LF"));
+    assert!(step.contains("'ap242_managed_model_based_3d_engineering',2014,"));
+    assert!(step.contains("'2024-01-02T03:04:05',('Zo\\X2\\00EB\\X0\\ O''Neil'),('')"));
+    assert!(step.contains("(CONVERSION_BASED_UNIT('INCH',"));
+    assert!(step.contains("LENGTH_MEASURE_WITH_UNIT(LENGTH_MEASURE(25.4),"));
+    assert!(
+        step.contains("(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT(")
+    );
+}
+
+#[test]
+fn ap203_carries_configuration_control_data() {
+    let options = StepOptions {
+        schema: Schema::Ap203,
+        timestamp: Some("2024-01-02T03:04:05".to_string()),
+        author: Some("Ada".to_string()),
+        organization: Some("Works".to_string()),
+    };
+    let mut writer = StepWriter::new(&options);
+    let product = writer.product("bracket");
+    let context = writer.representation_context(Units::Millimeters, 1e-6);
+    writer.shape_representation(&product, "SHAPE_REPRESENTATION", "", &[], context);
+    let step = writer.finish("bracket.step", "test").unwrap();
+
+    assert!(step.contains("FILE_SCHEMA(('CONFIG_CONTROL_DESIGN'));"));
+    assert!(step.contains("=MECHANICAL_CONTEXT('',"));
+    assert!(step.contains("=DESIGN_CONTEXT('part definition',"));
+    assert!(step.contains("=PERSON('Ada','Ada',$,$,$,$);"));
+    assert!(step.contains("=ORGANIZATION($,'Works','');"));
+    assert!(step.contains("=CALENDAR_DATE(2024,2,1);"));
+    assert!(step.contains("=LOCAL_TIME(3,4,5.,"));
+    assert!(step.contains("=SECURITY_CLASSIFICATION_LEVEL('unclassified');"));
+    assert!(step.contains("=APPROVAL_STATUS('not_yet_approved');"));
+    for entity in [
+        "CC_DESIGN_APPROVAL(",
+        "CC_DESIGN_SECURITY_CLASSIFICATION(",
+        "APPROVAL_PERSON_ORGANIZATION(",
+        "APPROVAL_DATE_TIME(",
+    ] {
+        assert_eq!(1, step.matches(entity).count(), "{entity}");
+    }
+    assert_eq!(
+        2,
+        step.matches("CC_DESIGN_DATE_AND_TIME_ASSIGNMENT(").count()
+    );
+
+    // Each role is assigned once, the product itself to its design owner.
+    let file = StepFile::parse(&step).unwrap();
+    let roles: Vec<(String, Vec<StepId>)> = file
+        .records("CC_DESIGN_PERSON_AND_ORGANIZATION_ASSIGNMENT")
+        .map(|(_, r)| {
+            let role = file.get(r.params[1].as_ref().unwrap()).unwrap();
+            let name = role.records[0].params[0].as_str().unwrap().to_string();
+            let items = r.params[2].as_list().unwrap();
+            (name, items.iter().filter_map(Value::as_ref).collect())
+        })
+        .collect();
+    let names: Vec<&str> = roles.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(
+        vec![
+            "creator",
+            "design_owner",
+            "design_supplier",
+            "classification_officer"
+        ],
+        names
+    );
+    assert_eq!(vec![product.product], roles[1].1);
+
+    // The other schemas keep the plain AP214-style product structure.
+    let mut writer = StepWriter::new(&StepOptions::default());
+    writer.product("bracket");
+    let step = writer.finish("bracket.step", "test").unwrap();
+    assert!(step.contains("=PRODUCT_CONTEXT('',"));
+    assert!(!step.contains("CC_DESIGN"));
+}
+
+#[test]
+fn reader_round_trips_writer_output() {
+    let options = StepOptions {
+        schema: Schema::Ap242,
+        timestamp: Some("2024-01-02T03:04:05".to_string()),
+        author: Some("Zoë O'Neil \\ 𝔸".to_string()),
+        organization: None,
+    };
+    let mut writer = StepWriter::new(&options);
+    let product = writer.product("bracket");
+    let context = writer.representation_context(Units::Inches, 1e-6);
+    let a = writer.cartesian_point([0.0, 0.0, 0.0]);
+    let b = writer.cartesian_point([1.5, -2.0, 1e-7]);
+    let (va, vb) = (writer.vertex_point(a), writer.vertex_point(b));
+    let direction = writer.direction([1.0, 0.0, 0.0]);
+    let vector = writer.vector(direction, 2.5);
+    let line = writer.line(a, vector);
+    let edge = writer.edge_curve(va, vb, line, true);
+    let set = writer.geometric_curve_set("", &[edge]);
+    writer.shape_representation(&product, "SHAPE_REPRESENTATION", "", &[set], context);
+    let text = writer.finish("bracket.step", "test").unwrap();
+
+    let file = StepFile::parse(&text).unwrap();
+    assert_eq!(Some(Schema::Ap242), file.schema());
+    assert_eq!(vec!["Zoë O'Neil \\ 𝔸".to_string()], file.header.author);
+    assert_eq!("2024-01-02T03:04:05", file.header.time_stamp);
+    assert_eq!(vec!["test".to_string()], file.header.description);
+
+    assert!(file.get(context).unwrap().is_complex());
+    let edges = file.edges().unwrap();
+    assert_eq!(1, edges.len());
+    assert_eq!(edge, edges[0].id);
+    assert_eq!([0.0, 0.0, 0.0], edges[0].start);
+    assert_eq!([1.5, -2.0, 1e-7], edges[0].end);
+    assert_eq!(2, file.vertices().unwrap().len());
+    let magnitude = file.get(vector).unwrap().records[0].params[2].as_real();
+    assert_eq!(Some(2.5), magnitude);
+}
+
+#[test]
+fn reader_extracts_faces() {
+    let text = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION((''),'2;1');
+FILE_NAME('square.step','',(''),(''),'','','');
+FILE_SCHEMA(('CONFIG_CONTROL_DESIGN'));
+ENDSEC;
+DATA;
+/* a unit square, outer bound listed last */
+#1=CARTESIAN_POINT('',(0.,0.));
+#2=CARTESIAN_POINT('',(1.,0.));
+#3=CARTESIAN_POINT('',(1.,1.));
+#4=VERTEX_POINT('',#1);
+#5=VERTEX_POINT('',#2);
+#6=VERTEX_POINT('',#3);
+#7=EDGE_CURVE('',#4,#5,$,.T.);
+#8=EDGE_CURVE('',#5,#6,$,.T.);
+#9=EDGE_CURVE('',#4,#6,$,.T.);
+#10=ORIENTED_EDGE('',*,*,#7,.T.);
+#11=ORIENTED_EDGE('',*,*,#8,.T.);
+#12=ORIENTED_EDGE('',*,*,#9,.F.);
+#13=EDGE_LOOP('',(#10,#11,#12));
+#14=POLY_LOOP('',(#1,#2,#3));
+#15=FACE_BOUND('',#14,.F.);
+#16=FACE_OUTER_BOUND('',#13,.T.);
+#17=ADVANCED_FACE('',(#15,#16),$,.T.);
+ENDSEC;
+END-ISO-10303-21;
+";
+    let file = StepFile::parse(text).unwrap();
+    assert_eq!(Some(Schema::Ap203), file.schema());
+    assert_eq!(3, file.edges().unwrap().len());
+    let faces = file.faces().unwrap();
+    assert_eq!(1, faces.len());
+    assert_eq!(
+        vec![
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+            vec![[1.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 0.0]],
+        ],
+        faces[0].bounds
+    );
+    let loop_record = &file
+        .get(file.records("EDGE_LOOP").next().unwrap().0)
+        .unwrap()
+        .records[0];
+    assert_eq!(Value::Str(String::new()), loop_record.params[0]);
+}
+
+#[test]
+fn reader_rejects_broken_files() {
+    let wrap = |data: &str| {
+        format!("ISO-10303-21;\nHEADER;\nENDSEC;\nDATA;\n{data}ENDSEC;\nEND-ISO-10303-21;\n")
+    };
+    assert!(matches!(
+        StepFile::parse(&wrap("#1=VERTEX_POINT('',#2);\n")),
+        Err(StepError::DanglingReference { .. })
+    ));
+    assert!(matches!(
+        StepFile::parse(&wrap(
+            "#1=CARTESIAN_POINT('',(0.));\n#1=CARTESIAN_POINT('',(1.));\n"
+        )),
+        Err(StepError::DuplicateId(_))
+    ));
+    assert!(matches!(
+        StepFile::parse(&wrap(
+            "#1=CARTESIAN_POINT('',(0.));\n#2=CARTESIAN_POINT('',(0.)\n"
+        )),
+        Err(StepError::Syntax { line: 7, .. })
+    ));
+    assert!(matches!(
+        StepFile::parse(&wrap("#1=CARTESIAN_POINT('unterminated,(0.));\n")),
+        Err(StepError::Syntax { line: 5, .. })
+    ));
+
+    let file = StepFile::parse(&wrap(
+        "#1=VERTEX_POINT('',#2);\n#2=DIRECTION('',(1.,0.,0.));\n",
+    ))
+    .unwrap();
+    assert!(matches!(
+        file.vertices(),
+        Err(StepError::Malformed { id, .. }) if id.get() == 2
+    ));
 }
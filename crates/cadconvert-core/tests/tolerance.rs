@@ -0,0 +1,95 @@
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{
+    DimensionEntity, Drawing2D, Entity2D, EntityKind, ImportLog, LineSeg2D, Primitive2D,
+    Provenance, Style, Units,
+};
+use cadconvert_core::tolerance::{dimension_precision, Tolerances};
+
+fn diagonal_drawing(length: f64, units: Units, dim_texts: &[&str]) -> Drawing2D {
+    Drawing2D {
+        units,
+        entities: vec![Entity2D {
+            id: 1,
+            kind: EntityKind::Object,
+            primitive: Primitive2D::Line(LineSeg2D {
+                a: Vec2::new(0.0, 0.0),
+                b: Vec2::new(length, 0.0),
+            }),
+            style: Style::default(),
+            source: Provenance::default(),
+        }],
+        dims: dim_texts
+            .iter()
+            .enumerate()
+            .map(|(i, t)| DimensionEntity {
+                id: 100 + i as u64,
+                raw_type: None,
+                text: Some(t.to_string()),
+                measurement: None,
+                at: None,
+                definition: None,
+                style: Style::default(),
+                source: Provenance::default(),
+            })
+            .collect(),
+        texts: Vec::new(),
+        import_log: ImportLog::default(),
+    }
+}
+
+#[test]
+fn tolerances_scale_with_extents() {
+    let small = Tolerances::for_drawing(&diagonal_drawing(100.0, Units::Millimeters, &[]));
+    let large = Tolerances::for_drawing(&diagonal_drawing(100_000.0, Units::Millimeters, &[]));
+    assert!((large.view_match / small.view_match - 1000.0).abs() < 1e-9);
+    assert!((large.snap / small.snap - 1000.0).abs() < 1e-9);
+}
+
+#[test]
+fn snap_tolerance_has_a_physical_floor() {
+    // 1 µm long, in metres: the relative snap would be 1e-12 m.
+    let tol = Tolerances::for_drawing(&diagonal_drawing(1e-6, Units::Meters, &[]));
+    assert!((tol.snap - 1e-9).abs() < 1e-15);
+    assert!(tol.view_match >= 10.0 * tol.snap);
+}
+
+#[test]
+fn dimension_precision_caps_view_match() {
+    assert_eq!(
+        None,
+        dimension_precision(&diagonal_drawing(1.0, Units::Unknown, &["<>"]))
+    );
+    let precision = dimension_precision(&diagonal_drawing(1.0, Units::Unknown, &["12", "R3.25"]));
+    assert!((precision.unwrap() - 0.01).abs() < 1e-12);
+
+    let plain = Tolerances::for_drawing(&diagonal_drawing(1000.0, Units::Millimeters, &[]));
+    let dimensioned =
+        Tolerances::for_drawing(&diagonal_drawing(1000.0, Units::Millimeters, &["250.00"]));
+    assert!((plain.view_match - 1.0).abs() < 1e-12);
+    assert!((dimensioned.view_match - 0.01).abs() < 1e-12);
+}
+
+#[test]
+fn dimension_precision_reads_only_the_nominal_value() {
+    let precision =
+        |texts: &[&str]| dimension_precision(&diagonal_drawing(1.0, Units::Unknown, texts));
+    assert_eq!(Some(0.1), precision(&["12.5 %%p0.002"]));
+    assert_eq!(Some(1.0), precision(&["M10x1.5"]));
+    assert_eq!(Some(1.0), precision(&["\\H2.5;{\\C1;40}"]));
+}
+
+#[test]
+fn dimension_precision_follows_view_scale() {
+    // "100.0" written against a 50-unit line: the view is drawn at 1:2.
+    let mut drawing = diagonal_drawing(100.0, Units::Millimeters, &["100.0"]);
+    drawing.dims[0].measurement = Some(50.0);
+    let precision = dimension_precision(&drawing).unwrap();
+    assert!((precision - 0.05).abs() < 1e-12);
+}
+
+#[test]
+fn empty_drawing_falls_back_to_defaults() {
+    let mut drawing = diagonal_drawing(1.0, Units::Millimeters, &[]);
+    drawing.entities.clear();
+    assert_eq!(Tolerances::default(), Tolerances::for_drawing(&drawing));
+}
@@ -1,12 +1,19 @@
 use anyhow::{bail, Context, Result};
+use cadconvert_algo::{
+    ProgressHandle, ReconstructionConfig, ReconstructionError, Stage, StepModel,
+};
 use cadconvert_core::analysis::{AnalysisConfig, Analyzer};
 use cadconvert_core::geom::{BBox2, Vec2 as CadVec2};
 use cadconvert_core::model::{Drawing2D, Primitive2D};
 use cadconvert_core::normalize::{normalize_in_place, NormalizeConfig};
 use cadconvert_core::report::AnalysisReport;
+use cadconvert_core::step::{Schema, StepOptions};
+use cadconvert_core::tolerance::Tolerances;
 use cadconvert_core::view::{ProjectionScheme, ViewRole};
 use eframe::egui;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 
 fn main() -> eframe::Result {
     let native_options = eframe::NativeOptions::default();
@@ -27,11 +34,18 @@ struct CadConvertApp {
 
     view_gap_factor: f64,
     min_cluster_entities: usize,
+    /// Derived from the loaded drawing; `tolerances` starts as a copy the user can edit.
+    auto_tolerances: Tolerances,
+    tolerances: Tolerances,
+    step_schema: Schema,
 
     output_dir: Option<PathBuf>,
     out_report_path: Option<PathBuf>,
     out_drawing_path: Option<PathBuf>,
     out_step_path: Option<PathBuf>,
+    out_solid_path: Option<PathBuf>,
+
+    reconstruction: Option<ReconstructionJob>,
 
     zoom: f32,
     pan: egui::Vec2,
@@ -49,10 +63,15 @@ impl CadConvertApp {
             report: None,
             view_gap_factor: 0.02,
             min_cluster_entities: 10,
+            auto_tolerances: Tolerances::default(),
+            tolerances: Tolerances::default(),
+            step_schema: Schema::default(),
             output_dir: None,
             out_report_path: None,
             out_drawing_path: None,
             out_step_path: None,
+            out_solid_path: None,
+            reconstruction: None,
             zoom: 1.0,
             pan: egui::Vec2::ZERO,
             status: "Open a DXF/SVG to begin.".to_string(),
@@ -61,7 +80,7 @@ impl CadConvertApp {
 
     fn pick_input(&mut self) {
         let file = rfd::FileDialog::new()
-            .add_filter("CAD drawings", &["dxf", "svg"])
+            .add_filter("CAD drawings", &["dxf", "svg", "json", "jsonl"])
             .pick_file();
         if let Some(path) = file {
             self.load_input(&path);
@@ -78,7 +97,10 @@ impl CadConvertApp {
     fn load_input(&mut self, path: &Path) {
         match self.import_any(path) {
             Ok((format, mut drawing)) => {
-                let _ = normalize_in_place(&mut drawing, &NormalizeConfig::default());
+                self.auto_tolerances = Tolerances::for_drawing(&drawing);
+                self.tolerances = self.auto_tolerances;
+                let normalize_cfg = NormalizeConfig::default().with_tolerances(&self.tolerances);
+                let _ = normalize_in_place(&mut drawing, &normalize_cfg);
                 self.drawing_extents = compute_preview_extents(&drawing);
                 self.drawing = Some(drawing);
                 self.input_path = Some(path.to_path_buf());
@@ -87,6 +109,7 @@ impl CadConvertApp {
                 self.out_report_path = None;
                 self.out_drawing_path = None;
                 self.out_step_path = None;
+                self.out_solid_path = None;
                 self.zoom = 1.0;
                 self.pan = egui::Vec2::ZERO;
 
@@ -118,6 +141,17 @@ impl CadConvertApp {
         match ext.as_str() {
             "dxf" => Ok(("dxf", cadconvert_import_dxf::import_dxf(path)?)),
             "svg" => Ok(("svg", cadconvert_import_svg::import_svg(path)?)),
+            "json" => {
+                let json = std::fs::read_to_string(path)
+                    .with_context(|| format!("read drawing: {path:?}"))?;
+                Ok(("json", cadconvert_core::schema::drawing_from_json(&json)?))
+            }
+            "jsonl" => {
+                let file =
+                    std::fs::File::open(path).with_context(|| format!("open drawing: {path:?}"))?;
+                let reader = std::io::BufReader::new(file);
+                Ok(("json", cadconvert_core::schema::drawing_from_jsonl(reader)?))
+            }
             "dwg" => bail!("DWG import not implemented yet."),
             _ => bail!("Unsupported input extension: .{ext}"),
         }
@@ -154,7 +188,8 @@ impl CadConvertApp {
         let cfg = AnalysisConfig {
             view_gap_factor: self.view_gap_factor,
             min_cluster_entities: self.min_cluster_entities,
-            normalize: NormalizeConfig::default(),
+            normalize: NormalizeConfig::default().with_tolerances(&self.tolerances),
+            tolerances: Some(self.tolerances),
         };
         let analyzer = Analyzer::new(cfg.clone());
         let report = analyzer.analyze(&format, &drawing);
@@ -176,12 +211,25 @@ impl CadConvertApp {
             return;
         }
 
+        let views = cadconvert_core::step::placed_views(&report);
         self.report = Some(report);
         self.out_report_path = Some(report_path.clone());
         self.out_drawing_path = Some(drawing_path.clone());
         self.out_step_path = Some(step_path.clone());
-        let step_data = cadconvert_core::step::wireframe_step(&normalized, stem);
-        if let Err(e) = std::fs::write(&step_path, &step_data) {
+        let step_options = StepOptions {
+            schema: self.step_schema,
+            ..StepOptions::default()
+        };
+        let written = cadconvert_core::step::wireframe_step(
+            &normalized,
+            stem,
+            &views,
+            &self.tolerances,
+            &step_options,
+        )
+        .map_err(anyhow::Error::from)
+        .and_then(|step_data| Ok(std::fs::write(&step_path, step_data)?));
+        if let Err(e) = written {
             self.status = format!(
                 "Wrote report: {} (drawing: {}, failed to write STEP: {e})",
                 report_path.display(),
@@ -198,6 +246,97 @@ impl CadConvertApp {
         );
     }
 
+    fn start_reconstruction(&mut self, ctx: &egui::Context) {
+        if self.reconstruction.is_some() {
+            return;
+        }
+        let Some(drawing) = self.drawing.clone() else {
+            self.status = "No drawing loaded.".to_string();
+            return;
+        };
+        let stem = self
+            .input_path
+            .as_deref()
+            .and_then(|p| p.file_stem())
+            .and_then(|s| s.to_str())
+            .unwrap_or("drawing")
+            .to_string();
+        let out_dir = self
+            .output_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("out"));
+        let output = out_dir.join(format!("{stem}.solid.step"));
+
+        let latest = Arc::new(Mutex::new((Stage::ViewSeparation, 0.0f32)));
+        let progress = {
+            let latest = Arc::clone(&latest);
+            let ctx = ctx.clone();
+            ProgressHandle::with_callback(move |stage, fraction| {
+                if let Ok(mut l) = latest.lock() {
+                    *l = (stage, fraction);
+                }
+                ctx.request_repaint();
+            })
+        };
+
+        let worker_progress = progress.clone();
+        let cfg = ReconstructionConfig {
+            tolerances: self.tolerances,
+            step: StepOptions {
+                schema: self.step_schema,
+                ..StepOptions::default()
+            },
+        };
+        let handle = std::thread::spawn(move || {
+            cadconvert_algo::reconstruct_solid(&drawing, &cfg, &worker_progress)
+        });
+
+        self.status = "Reconstructing…".to_string();
+        self.reconstruction = Some(ReconstructionJob {
+            progress,
+            latest,
+            handle: Some(handle),
+            output,
+        });
+    }
+
+    fn poll_reconstruction(&mut self) {
+        let Some(job) = &mut self.reconstruction else {
+            return;
+        };
+        if !job.handle.as_ref().is_some_and(|h| h.is_finished()) {
+            return;
+        }
+        let Some(handle) = job.handle.take() else {
+            return;
+        };
+        let output = job.output.clone();
+        self.reconstruction = None;
+
+        let Ok(result) = handle.join() else {
+            self.status = "Reconstruction failed: worker thread panicked.".to_string();
+            return;
+        };
+        match result {
+            Ok(model) => {
+                if let Some(parent) = output.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                match model.save_to_file(&output) {
+                    Ok(()) => {
+                        self.status = format!("Wrote solid STEP: {}", output.display());
+                        self.out_solid_path = Some(output);
+                    }
+                    Err(e) => self.status = format!("Failed to write solid STEP: {e}"),
+                }
+            }
+            Err(ReconstructionError::Cancelled { .. }) => {
+                self.status = "Reconstruction cancelled.".to_string();
+            }
+            Err(e) => self.status = format!("Reconstruction failed: {e}"),
+        }
+    }
+
     fn handle_file_drop(&mut self, ctx: &egui::Context) {
         let dropped = ctx.input(|i| i.raw.dropped_files.clone());
         let Some(file) = dropped.into_iter().find(|f| f.path.is_some()) else {
@@ -212,6 +351,7 @@ impl CadConvertApp {
 impl eframe::App for CadConvertApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.handle_file_drop(ctx);
+        self.poll_reconstruction();
 
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -241,6 +381,70 @@ impl eframe::App for CadConvertApp {
                 {
                     self.run_analyze();
                 }
+                let can_reconstruct = self.drawing.is_some() && self.reconstruction.is_none();
+                if ui
+                    .add_enabled(can_reconstruct, egui::Button::new("Reconstruct → STEP"))
+                    .clicked()
+                {
+                    self.start_reconstruction(ctx);
+                }
+                if let Some(job) = &self.reconstruction {
+                    let (stage, fraction) = job
+                        .latest
+                        .lock()
+                        .map(|l| *l)
+                        .unwrap_or((Stage::ViewSeparation, 0.0));
+                    ui.add(
+                        egui::ProgressBar::new(fraction)
+                            .desired_width(160.0)
+                            .text(stage.label()),
+                    );
+                    let cancelling = job.progress.is_cancelled();
+                    if ui
+                        .add_enabled(!cancelling, egui::Button::new("Cancel"))
+                        .clicked()
+                    {
+                        job.progress.cancel();
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Tolerances:");
+                ui.add(
+                    egui::DragValue::new(&mut self.tolerances.snap)
+                        .speed(self.auto_tolerances.snap * 0.1)
+                        .range(f64::MIN_POSITIVE..=f64::INFINITY)
+                        .prefix("snap="),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.tolerances.view_match)
+                        .speed(self.auto_tolerances.view_match * 0.1)
+                        .range(f64::MIN_POSITIVE..=f64::INFINITY)
+                        .prefix("match="),
+                );
+                if ui
+                    .add_enabled(
+                        self.tolerances != self.auto_tolerances,
+                        egui::Button::new("Auto"),
+                    )
+                    .on_hover_text("Reset to the values derived from the drawing")
+                    .clicked()
+                {
+                    self.tolerances = self.auto_tolerances;
+                }
+                ui.separator();
+                egui::ComboBox::from_label("STEP schema")
+                    .selected_text(schema_label(self.step_schema))
+                    .show_ui(ui, |ui| {
+                        for schema in [Schema::Ap203, Schema::Ap214, Schema::Ap242] {
+                            ui.selectable_value(
+                                &mut self.step_schema,
+                                schema,
+                                schema_label(schema),
+                            );
+                        }
+                    });
             });
 
             if let Some(p) = &self.input_path {
@@ -289,6 +493,14 @@ impl eframe::App for CadConvertApp {
     }
 }
 
+fn schema_label(schema: Schema) -> &'static str {
+    match schema {
+        Schema::Ap203 => "AP203",
+        Schema::Ap214 => "AP214",
+        Schema::Ap242 => "AP242",
+    }
+}
+
 fn draw_preview(ui: &mut egui::Ui, app: &mut CadConvertApp) {
     let Some(drawing) = &app.drawing else {
         ui.label("No input loaded.");
@@ -323,11 +535,13 @@ fn draw_preview(ui: &mut egui::Ui, app: &mut CadConvertApp) {
     let stroke_obj = egui::Stroke::new(1.0, egui::Color32::BLACK);
     let stroke_hidden = egui::Stroke::new(1.0, egui::Color32::from_gray(140));
     let stroke_center = egui::Stroke::new(1.0, egui::Color32::from_rgb(0, 120, 200));
+    let stroke_phantom = egui::Stroke::new(1.0, egui::Color32::from_rgb(150, 90, 170));
 
     for ent in &drawing.entities {
         let stroke = match ent.kind {
             cadconvert_core::model::EntityKind::Hidden => stroke_hidden,
             cadconvert_core::model::EntityKind::Center => stroke_center,
+            cadconvert_core::model::EntityKind::Phantom => stroke_phantom,
             _ => stroke_obj,
         };
         draw_primitive(&painter, &transform, &ent.primitive, stroke);
@@ -385,6 +599,10 @@ fn draw_output(ui: &mut egui::Ui, app: &mut CadConvertApp) {
         ui.label(format!("Dimensions: {}", report.stats.dims_total));
         ui.label(format!("Texts: {}", report.stats.texts_total));
         ui.label(format!("View clusters: {}", report.view_clusters.len()));
+        ui.label(format!(
+            "Tolerances: snap {:.3e}, view match {:.3e}",
+            report.tolerances.snap, report.tolerances.view_match
+        ));
 
         if let Some(va) = &report.view_assignment {
             let scheme = match va.scheme {
@@ -412,6 +630,15 @@ fn draw_output(ui: &mut egui::Ui, app: &mut CadConvertApp) {
                 ui.monospace(p.display().to_string());
             });
         }
+        if let Some(p) = &app.out_solid_path {
+            ui.horizontal(|ui| {
+                ui.label("Solid (STEP):");
+                ui.monospace(p.display().to_string());
+                if ui.button("Open").clicked() {
+                    let _ = open::that(p);
+                }
+            });
+        }
         if let Some(p) = &app.out_step_path {
             ui.horizontal(|ui| {
                 ui.label("CAD (STEP):");
@@ -439,8 +666,15 @@ fn draw_output(ui: &mut egui::Ui, app: &mut CadConvertApp) {
     }
 }
 
+struct ReconstructionJob {
+    progress: ProgressHandle,
+    latest: Arc<Mutex<(Stage, f32)>>,
+    handle: Option<JoinHandle<Result<StepModel, ReconstructionError>>>,
+    output: PathBuf,
+}
+
 fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> Result<()> {
-    let json = serde_json::to_string_pretty(value).context("serialize json")?;
+    let json = cadconvert_core::schema::to_json_pretty(value).context("serialize json")?;
     std::fs::write(path, json).with_context(|| format!("write {}", path.display()))?;
     Ok(())
 }
@@ -597,97 +831,17 @@ fn draw_primitive(
         Primitive2D::Line(l) => {
             painter.line_segment([tx.point(l.a), tx.point(l.b)], stroke);
         }
-        Primitive2D::Circle(c) => {
-            let pts = circle_points(c.center, c.radius, 64)
-                .into_iter()
-                .map(|p| tx.point(p))
-                .collect::<Vec<_>>();
-            painter.add(egui::Shape::line(pts, stroke));
+        Primitive2D::Point(p) => {
+            painter.circle_filled(tx.point(*p), stroke.width * 1.5, stroke.color);
         }
-        Primitive2D::Arc(a) => {
-            let pts = arc_points(a.center, a.radius, a.start_angle_deg, a.end_angle_deg, 48)
+        _ => {
+            // Half a pixel off the curve at most.
+            let pts = prim
+                .tessellate(0.5 / tx.scale as f64)
                 .into_iter()
                 .map(|p| tx.point(p))
                 .collect::<Vec<_>>();
             painter.add(egui::Shape::line(pts, stroke));
         }
-        Primitive2D::Polyline(pl) => {
-            if pl.vertices.len() < 2 {
-                return;
-            }
-            for w in pl.vertices.windows(2) {
-                painter.line_segment([tx.point(w[0].pos), tx.point(w[1].pos)], stroke);
-            }
-            if pl.closed {
-                let a = pl.vertices.last().unwrap().pos;
-                let b = pl.vertices.first().unwrap().pos;
-                painter.line_segment([tx.point(a), tx.point(b)], stroke);
-            }
-        }
-        Primitive2D::CubicBezier(b) => {
-            let pts = bezier_points(b.clone(), 32)
-                .into_iter()
-                .map(|p| tx.point(p))
-                .collect::<Vec<_>>();
-            painter.add(egui::Shape::line(pts, stroke));
-        }
-    }
-}
-
-fn circle_points(center: CadVec2, radius: f64, segments: usize) -> Vec<CadVec2> {
-    let mut pts = Vec::with_capacity(segments + 1);
-    for i in 0..=segments {
-        let t = i as f64 / segments as f64;
-        let a = t * std::f64::consts::TAU;
-        pts.push(CadVec2::new(
-            center.x + radius * a.cos(),
-            center.y + radius * a.sin(),
-        ));
     }
-    pts
-}
-
-fn arc_points(
-    center: CadVec2,
-    radius: f64,
-    start_deg: f64,
-    end_deg: f64,
-    segments: usize,
-) -> Vec<CadVec2> {
-    let a0 = start_deg.to_radians();
-    let mut a1 = end_deg.to_radians();
-    if a1 < a0 {
-        a1 += std::f64::consts::TAU;
-    }
-    let mut pts = Vec::with_capacity(segments + 1);
-    for i in 0..=segments {
-        let t = i as f64 / segments as f64;
-        let a = a0 + (a1 - a0) * t;
-        pts.push(CadVec2::new(
-            center.x + radius * a.cos(),
-            center.y + radius * a.sin(),
-        ));
-    }
-    pts
-}
-
-fn bezier_points(b: cadconvert_core::model::Bezier2D, segments: usize) -> Vec<CadVec2> {
-    let mut pts = Vec::with_capacity(segments + 1);
-    for i in 0..=segments {
-        let t = i as f64 / segments as f64;
-        pts.push(bezier_eval(b.clone(), t));
-    }
-    pts
-}
-
-fn bezier_eval(b: cadconvert_core::model::Bezier2D, t: f64) -> CadVec2 {
-    let u = 1.0 - t;
-    let tt = t * t;
-    let uu = u * u;
-    let uuu = uu * u;
-    let ttt = tt * t;
-
-    let x = uuu * b.p0.x + 3.0 * uu * t * b.p1.x + 3.0 * u * tt * b.p2.x + ttt * b.p3.x;
-    let y = uuu * b.p0.y + 3.0 * uu * t * b.p1.y + 3.0 * u * tt * b.p2.y + ttt * b.p3.y;
-    CadVec2::new(x, y)
 }
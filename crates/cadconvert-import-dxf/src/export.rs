@@ -0,0 +1,426 @@
+use anyhow::{Context, Result};
+use cadconvert_core::dimension::{check_dimension, DimensionStatus};
+use cadconvert_core::geom::{BBox2, Vec2};
+use cadconvert_core::model::{
+    DimensionEntity, Drawing2D, Primitive2D, Style, TextAlign, TextEntity, Units,
+};
+use cadconvert_core::report::AnalysisReport;
+use cadconvert_core::view::{ProjectionScheme, ViewRole};
+use dxf::entities::{Entity, EntityType};
+use dxf::enums::{AcadVersion, AttachmentPoint, DimensionType, HorizontalTextJustification};
+use dxf::tables::{Layer, LineType};
+use dxf::{Color, LwPolylineVertex, Point};
+use std::path::Path;
+
+/// Overlay layer with a box and label per view cluster.
+pub const CLUSTERS_LAYER: &str = "CADCONVERT_CLUSTERS";
+/// Overlay layer naming the view assigned to each cluster.
+pub const VIEWS_LAYER: &str = "CADCONVERT_VIEWS";
+/// Overlay layer marking dimensions with neither a measurement nor a number in
+/// their text.
+pub const UNRESOLVED_DIMS_LAYER: &str = "CADCONVERT_UNRESOLVED_DIMS";
+/// Overlay layer marking dimensions whose text disagrees with their measurement.
+pub const CONFLICTS_LAYER: &str = "CADCONVERT_CONFLICTS";
+
+/// Writes `drawing` to `path` as an R2000 DXF. See [`to_dxf`].
+pub fn export_dxf(
+    drawing: &Drawing2D,
+    overlay: Option<&AnalysisReport>,
+    path: &Path,
+) -> Result<()> {
+    to_dxf(drawing, overlay)
+        .save_file(path)
+        .with_context(|| format!("save DXF: {path:?}"))
+}
+
+/// Builds a DXF drawing from `drawing`, keeping layers, linetypes, colors,
+/// dimensions and texts.
+///
+/// Béziers become degree 3 splines. Dimensions carry their text, measurement
+/// and definition points but no block of their own, so a reader has to
+/// regenerate their graphics. With `overlay`, the analysis is drawn on the
+/// `CADCONVERT_*` layers on top.
+pub fn to_dxf(drawing: &Drawing2D, overlay: Option<&AnalysisReport>) -> dxf::Drawing {
+    let mut out = dxf::Drawing::new();
+    out.header.version = AcadVersion::R2000;
+    out.header.default_drawing_units = match drawing.units {
+        Units::Unknown => dxf::enums::Units::Unitless,
+        Units::Inches => dxf::enums::Units::Inches,
+        Units::Millimeters => dxf::enums::Units::Millimeters,
+        Units::Centimeters => dxf::enums::Units::Centimeters,
+        Units::Meters => dxf::enums::Units::Meters,
+    };
+    add_line_types(&mut out, drawing);
+
+    for e in &drawing.entities {
+        let specific = match &e.primitive {
+            Primitive2D::Line(l) => {
+                EntityType::Line(dxf::entities::Line::new(point(l.a), point(l.b)))
+            }
+            Primitive2D::Circle(c) => {
+                EntityType::Circle(dxf::entities::Circle::new(point(c.center), c.radius))
+            }
+            Primitive2D::Point(p) => {
+                EntityType::ModelPoint(dxf::entities::ModelPoint::new(point(*p)))
+            }
+            Primitive2D::Arc(a) => EntityType::Arc(dxf::entities::Arc::new(
+                point(a.center),
+                a.radius,
+                a.start_angle_deg,
+                a.end_angle_deg,
+            )),
+            Primitive2D::Polyline(p) => {
+                let mut poly = dxf::entities::LwPolyline {
+                    vertices: p
+                        .vertices
+                        .iter()
+                        .map(|v| LwPolylineVertex {
+                            x: v.pos.x,
+                            y: v.pos.y,
+                            bulge: v.bulge,
+                            ..LwPolylineVertex::default()
+                        })
+                        .collect(),
+                    ..dxf::entities::LwPolyline::default()
+                };
+                poly.set_is_closed(p.closed);
+                EntityType::LwPolyline(poly)
+            }
+            Primitive2D::CubicBezier(b) => {
+                let mut spline = dxf::entities::Spline {
+                    degree_of_curve: 3,
+                    knot_values: vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+                    control_points: [b.p0, b.p1, b.p2, b.p3].map(point).to_vec(),
+                    ..dxf::entities::Spline::default()
+                };
+                spline.set_is_planar(true);
+                EntityType::Spline(spline)
+            }
+            Primitive2D::Ellipse(el) => EntityType::Ellipse(dxf::entities::Ellipse {
+                center: point(el.center),
+                major_axis: dxf::Vector::new(el.major_axis.x, el.major_axis.y, 0.0),
+                minor_axis_ratio: el.ratio,
+                start_parameter: el.start_param_deg.to_radians(),
+                end_parameter: (el.start_param_deg + el.sweep_deg()).to_radians(),
+                ..dxf::entities::Ellipse::default()
+            }),
+            Primitive2D::Nurbs(n) => {
+                let mut spline = dxf::entities::Spline {
+                    degree_of_curve: n.degree as i32,
+                    knot_values: n.knots.clone(),
+                    weight_values: n.weights.clone(),
+                    control_points: n.control_points.iter().copied().map(point).collect(),
+                    ..dxf::entities::Spline::default()
+                };
+                spline.set_is_planar(true);
+                spline.set_is_rational(!n.weights.is_empty());
+                EntityType::Spline(spline)
+            }
+        };
+        add(&mut out, specific, &e.style);
+    }
+    for t in &drawing.texts {
+        add(&mut out, text(t), &t.style);
+    }
+    for d in &drawing.dims {
+        add(&mut out, dimension(d), &d.style);
+    }
+
+    if let Some(report) = overlay {
+        add_overlay(&mut out, drawing, report);
+    }
+    out
+}
+
+fn point(p: Vec2) -> Point {
+    Point::new(p.x, p.y, 0.0)
+}
+
+fn add(out: &mut dxf::Drawing, specific: EntityType, style: &Style) {
+    let mut entity = Entity::new(specific);
+    entity.common.layer = style
+        .layer
+        .clone()
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| "0".to_string());
+    entity.common.line_type_name = style
+        .linetype
+        .clone()
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| "BYLAYER".to_string());
+    entity.common.color = match style.color_index {
+        Some(0) => Color::by_block(),
+        Some(i @ 1..=255) => Color::from_index(i as u8),
+        _ => Color::by_layer(),
+    };
+    out.add_entity(entity);
+}
+
+/// Dash patterns for the hidden, center and phantom linetypes the drawing uses, so they
+/// do not come out continuous. Others are left for the DXF crate to declare.
+fn add_line_types(out: &mut dxf::Drawing, drawing: &Drawing2D) {
+    let mut names: Vec<&str> = drawing
+        .entities
+        .iter()
+        .filter_map(|e| e.style.linetype.as_deref())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    for name in names {
+        let lower = name.to_ascii_lowercase();
+        let (description, pattern): (&str, &[f64]) =
+            if lower.contains("center") || lower.contains("centre") {
+                ("Center ____ _ ____ _ ____", &[1.25, -0.25, 0.25, -0.25])
+            } else if lower.contains("hidden") || lower.contains("hid") {
+                ("Hidden __ __ __ __", &[0.25, -0.125])
+            } else if lower.contains("phantom") {
+                (
+                    "Phantom ______  __  __  ______",
+                    &[1.25, -0.25, 0.25, -0.25, 0.25, -0.25],
+                )
+            } else {
+                continue;
+            };
+        out.add_line_type(LineType {
+            name: name.to_string(),
+            description: description.to_string(),
+            alignment_code: 'A' as i32,
+            element_count: pattern.len() as i32,
+            total_pattern_length: pattern.iter().map(|l| l.abs()).sum(),
+            dash_dot_space_lengths: pattern.to_vec(),
+            complex_line_type_element_types: vec![0; pattern.len()],
+            ..LineType::default()
+        });
+    }
+}
+
+fn text(t: &TextEntity) -> EntityType {
+    let height = t
+        .height
+        .filter(|h| h.is_finite() && *h > 0.0)
+        .unwrap_or(1.0);
+    if t.text.contains('\n') {
+        // MTEXT keeps its angle in radians, and readers prefer the direction.
+        let angle = t.rotation_deg.to_radians();
+        EntityType::MText(dxf::entities::MText {
+            insertion_point: point(t.at),
+            initial_text_height: height,
+            text: t.text.replace("\r\n", "\n").replace('\n', "\\P"),
+            x_axis_direction: dxf::Vector::new(angle.cos(), angle.sin(), 0.0),
+            rotation_angle: angle,
+            attachment_point: match t.align {
+                TextAlign::Left => AttachmentPoint::TopLeft,
+                TextAlign::Center => AttachmentPoint::TopCenter,
+                TextAlign::Right => AttachmentPoint::TopRight,
+            },
+            ..Default::default()
+        })
+    } else {
+        // Justified text is placed by its second point; the first is only a
+        // hint that readers recompute.
+        EntityType::Text(dxf::entities::Text {
+            location: point(t.at),
+            second_alignment_point: point(t.at),
+            text_height: height,
+            value: t.text.clone(),
+            rotation: t.rotation_deg,
+            horizontal_text_justification: match t.align {
+                TextAlign::Left => HorizontalTextJustification::Left,
+                TextAlign::Center => HorizontalTextJustification::Center,
+                TextAlign::Right => HorizontalTextJustification::Right,
+            },
+            ..Default::default()
+        })
+    }
+}
+
+fn dimension(d: &DimensionEntity) -> EntityType {
+    let kind = match d.raw_type.map(|t| t & 0x0f) {
+        Some(1) => DimensionType::Aligned,
+        Some(2) => DimensionType::Angular,
+        Some(3) => DimensionType::Diameter,
+        Some(4) => DimensionType::Radius,
+        Some(5) => DimensionType::AngularThreePoint,
+        Some(6) => DimensionType::Ordinate,
+        _ => DimensionType::RotatedHorizontalOrVertical,
+    };
+    let at = d.at.map(point).unwrap_or_default();
+    // Definition points as read, in group code order; without them every point
+    // sits on the text, which is all the dimension has to go on.
+    let def = d.definition.as_ref();
+    let pt = |i: usize| {
+        def.and_then(|def| def.points.get(i))
+            .copied()
+            .map(point)
+            .unwrap_or_else(|| at.clone())
+    };
+    let base = dxf::entities::DimensionBase {
+        definition_point_1: pt(0),
+        text_mid_point: at.clone(),
+        is_at_user_defined_location: d.at.is_some(),
+        dimension_type: kind,
+        actual_measurement: d.measurement.unwrap_or(0.0),
+        text: d.text.clone().unwrap_or_default(),
+        ..Default::default()
+    };
+    match kind {
+        DimensionType::Diameter => {
+            EntityType::DiameterDimension(dxf::entities::DiameterDimension {
+                dimension_base: base,
+                definition_point_2: pt(1),
+                ..Default::default()
+            })
+        }
+        DimensionType::Radius => EntityType::RadialDimension(dxf::entities::RadialDimension {
+            dimension_base: base,
+            definition_point_2: pt(1),
+            ..Default::default()
+        }),
+        DimensionType::Angular | DimensionType::AngularThreePoint => {
+            EntityType::AngularThreePointDimension(dxf::entities::AngularThreePointDimension {
+                dimension_base: base,
+                definition_point_2: pt(1),
+                definition_point_3: pt(2),
+                definition_point_4: pt(3),
+                definition_point_5: pt(4),
+            })
+        }
+        DimensionType::Ordinate => {
+            EntityType::OrdinateDimension(dxf::entities::OrdinateDimension {
+                dimension_base: base,
+                definition_point_2: pt(1),
+                definition_point_3: pt(2),
+            })
+        }
+        _ => EntityType::RotatedDimension(dxf::entities::RotatedDimension {
+            dimension_base: base,
+            definition_point_2: pt(1),
+            definition_point_3: pt(2),
+            rotation_angle: def.map_or(0.0, |def| def.rotation_deg),
+            ..Default::default()
+        }),
+    }
+}
+
+fn add_overlay(out: &mut dxf::Drawing, drawing: &Drawing2D, report: &AnalysisReport) {
+    for (name, color) in [
+        (CLUSTERS_LAYER, 3),
+        (VIEWS_LAYER, 5),
+        (UNRESOLVED_DIMS_LAYER, 1),
+        (CONFLICTS_LAYER, 6),
+    ] {
+        out.add_layer(Layer {
+            name: name.to_string(),
+            color: Color::from_index(color),
+            is_layer_plotted: false,
+            ..Layer::default()
+        });
+    }
+    let extents = report
+        .extents
+        .or_else(|| drawing.extents())
+        .unwrap_or_else(|| BBox2::new(Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0)));
+    let height = (extents.diag() * 0.015).max(1e-3);
+    let on = |layer: &str| Style {
+        layer: Some(layer.to_string()),
+        ..Style::default()
+    };
+    let label = |text: String, at: Vec2| {
+        EntityType::Text(dxf::entities::Text {
+            location: point(at),
+            text_height: height,
+            value: text,
+            ..Default::default()
+        })
+    };
+
+    for cluster in &report.view_clusters {
+        let b = cluster.bbox;
+        let mut frame = dxf::entities::LwPolyline {
+            vertices: [
+                (b.min.x, b.min.y),
+                (b.max.x, b.min.y),
+                (b.max.x, b.max.y),
+                (b.min.x, b.max.y),
+            ]
+            .map(|(x, y)| LwPolylineVertex {
+                x,
+                y,
+                ..LwPolylineVertex::default()
+            })
+            .to_vec(),
+            ..Default::default()
+        };
+        frame.set_is_closed(true);
+        add(out, EntityType::LwPolyline(frame), &on(CLUSTERS_LAYER));
+        add(
+            out,
+            label(
+                format!("cluster {} ({} entities)", cluster.id, cluster.entity_count),
+                Vec2::new(b.min.x, b.max.y + 0.5 * height),
+            ),
+            &on(CLUSTERS_LAYER),
+        );
+    }
+
+    if let Some(assignment) = &report.view_assignment {
+        let scheme = match assignment.scheme {
+            ProjectionScheme::ThirdAngle => "third angle",
+            ProjectionScheme::FirstAngle => "first angle",
+        };
+        for role in &assignment.roles {
+            let Some(cluster) = report
+                .view_clusters
+                .iter()
+                .find(|c| c.id == role.cluster_id)
+            else {
+                continue;
+            };
+            let name = match role.role {
+                ViewRole::Front => "FRONT",
+                ViewRole::Top => "TOP",
+                ViewRole::Right => "RIGHT",
+            };
+            add(
+                out,
+                label(
+                    format!("{name} ({scheme}, confidence {:.2})", assignment.confidence),
+                    Vec2::new(cluster.bbox.min.x, cluster.bbox.min.y - 1.5 * height),
+                ),
+                &on(VIEWS_LAYER),
+            );
+        }
+    }
+
+    // Dimensions without a position are listed under the drawing instead.
+    let mut note = Vec2::new(extents.min.x, extents.min.y - 4.0 * height);
+    for d in &drawing.dims {
+        let text = d.text.as_deref().unwrap_or("");
+        let (layer, message) = match check_dimension(d) {
+            DimensionStatus::Ok => continue,
+            DimensionStatus::Unresolved => {
+                (UNRESOLVED_DIMS_LAYER, format!("dim {}: no value", d.id))
+            }
+            DimensionStatus::Conflict { measured } => (
+                CONFLICTS_LAYER,
+                format!("dim {}: text \"{text}\" but measured {measured}", d.id),
+            ),
+        };
+        let at = match d.at {
+            Some(at) => {
+                add(
+                    out,
+                    EntityType::Circle(dxf::entities::Circle::new(point(at), 2.0 * height)),
+                    &on(layer),
+                );
+                Vec2::new(at.x + 2.5 * height, at.y)
+            }
+            None => {
+                let at = note;
+                note.y -= 1.5 * height;
+                at
+            }
+        };
+        add(out, label(message, at), &on(layer));
+    }
+}
@@ -1,38 +1,114 @@
-use anyhow::{Context, Result};
-use cadconvert_core::geom::Vec2;
+use anyhow::{bail, Context, Result};
+use cadconvert_core::geom::{BBox2, Vec2};
 use cadconvert_core::model::{
-    Arc2D, Circle2D, DimensionEntity, Drawing2D, Entity2D, EntityKind, LineSeg2D, Polyline2D,
-    PolylineVertex2D, Primitive2D, Style, TextEntity, Units,
+    Arc2D, Circle2D, DimensionDefinition, DimensionEntity, Drawing2D, Ellipse2D, Entity2D,
+    EntityKind, ImportLog, LineSeg2D, Nurbs2D, Polyline2D, PolylineVertex2D, Primitive2D,
+    Provenance, Style, TextAlign, TextEntity, Units,
 };
 use dxf::entities::EntityType;
+use dxf::enums::{AttachmentPoint, HorizontalTextJustification};
 use std::collections::HashMap;
 use std::path::Path;
 
+mod export;
+mod raw;
+
+pub use export::{
+    export_dxf, to_dxf, CLUSTERS_LAYER, CONFLICTS_LAYER, UNRESOLVED_DIMS_LAYER, VIEWS_LAYER,
+};
+
+/// Entity types imported, by their DXF names.
+const IMPORTED_TYPES: [&str; 21] = [
+    "LINE",
+    "CIRCLE",
+    "ARC",
+    "LWPOLYLINE",
+    "POLYLINE",
+    "SPLINE",
+    "ELLIPSE",
+    "TEXT",
+    "MTEXT",
+    "DIMENSION",
+    "INSERT",
+    "HATCH",
+    "SOLID",
+    "TRACE",
+    "POINT",
+    "LEADER",
+    "MULTILEADER",
+    "MLEADER",
+    "TOLERANCE",
+    "3DFACE",
+    "VIEWPORT",
+];
+
+/// Parts of other entities, never skipped on their own.
+const PART_TYPES: [&str; 4] = ["VERTEX", "SEQEND", "ATTRIB", "ATTDEF"];
+
+/// Which part of the file to import.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DxfSpace {
+    /// Model space, plus what is drawn on the active layout's sheet as it
+    /// stands, listed in [`ImportLog::paper_space`].
+    #[default]
+    Model,
+    /// The sheet of a paper-space layout by name, or of the active one, in
+    /// sheet coordinates: what is drawn on it, and model space through each
+    /// of its viewports, scaled and clipped to the viewport.
+    Layout(Option<String>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DxfImportOptions {
+    pub space: DxfSpace,
+}
+
 pub fn import_dxf(path: &Path) -> Result<Drawing2D> {
-    let drawing = dxf::Drawing::load_file(path).with_context(|| format!("load DXF: {path:?}"))?;
+    import_dxf_with(path, &DxfImportOptions::default())
+}
 
-    let mut importer = DxfImporter::new(&drawing);
-    importer.import_all();
+pub fn import_dxf_with(path: &Path, options: &DxfImportOptions) -> Result<Drawing2D> {
+    let bytes = std::fs::read(path).with_context(|| format!("read DXF: {path:?}"))?;
+    let mut drawing =
+        dxf::Drawing::load(&mut bytes.as_slice()).with_context(|| format!("load DXF: {path:?}"))?;
+    let text = String::from_utf8_lossy(&bytes);
+    let raw = raw::scan(&text).unwrap_or_default();
+    restore_block_handles(&mut drawing, &raw);
+
+    let mut importer = DxfImporter::new(&drawing, &raw);
+    match &options.space {
+        DxfSpace::Model => importer.import_all(),
+        DxfSpace::Layout(name) => importer.import_layout(name.as_deref())?,
+    }
 
     Ok(Drawing2D {
         units: Units::Unknown,
         entities: importer.entities,
         dims: importer.dims,
         texts: importer.texts,
+        import_log: importer.log,
     })
 }
 
 struct DxfImporter<'a> {
     drawing: &'a dxf::Drawing,
     blocks: HashMap<String, &'a dxf::Block>,
+    /// HATCH and MULTILEADER, which the `dxf` crate does not read.
+    raw: &'a raw::RawEntities<'a>,
     next_id: u64,
     entities: Vec<Entity2D>,
     dims: Vec<DimensionEntity>,
     texts: Vec<TextEntity>,
+    log: ImportLog,
+    /// Where the entity being imported comes from.
+    source: Provenance,
 }
 
+/// Blocks being inserted, outermost first, by name and insert handle.
+type InsertStack = Vec<(String, Option<String>)>;
+
 impl<'a> DxfImporter<'a> {
-    fn new(drawing: &'a dxf::Drawing) -> Self {
+    fn new(drawing: &'a dxf::Drawing, raw: &'a raw::RawEntities<'a>) -> Self {
         let mut blocks = HashMap::new();
         for block in drawing.blocks() {
             blocks.insert(block.name.to_ascii_lowercase(), block);
@@ -40,19 +116,207 @@ impl<'a> DxfImporter<'a> {
         Self {
             drawing,
             blocks,
+            raw,
             next_id: 1,
             entities: Vec::new(),
             dims: Vec::new(),
             texts: Vec::new(),
+            log: ImportLog::default(),
+            source: Provenance::default(),
         }
     }
 
+    /// Model space, then what is drawn on the active layout's sheet, in sheet
+    /// coordinates and listed in the log.
     fn import_all(&mut self) {
-        let tx = Transform2D::identity();
+        self.import_model(&Transform2D::identity());
+        let raw = self.raw;
+        let sheet: Vec<_> = self
+            .drawing
+            .entities()
+            .filter(|e| e.common.is_in_paper_space)
+            .collect();
+        let raw_sheet: Vec<_> = raw.model.iter().filter(|e| e.paper_space).collect();
+        self.import_sheet(&sheet, &raw_sheet);
+        self.count_skipped();
+    }
+
+    /// Model space, placed by `tx`.
+    fn import_model(&mut self, tx: &Transform2D) {
         let mut stack = Vec::new();
         for ent in self.drawing.entities() {
+            if !ent.common.is_in_paper_space {
+                self.import_entity(ent, tx, None, &mut stack, 0);
+            }
+        }
+        let raw = self.raw;
+        for ent in raw.model.iter().filter(|e| !e.paper_space) {
+            self.import_raw(ent, tx, None, &[]);
+        }
+    }
+
+    /// The active layout keeps its sheet in the ENTITIES section, the others
+    /// in their `*Paper_Space` blocks.
+    fn import_layout(&mut self, name: Option<&str>) -> Result<()> {
+        let raw = self.raw;
+        // R12 files have no LAYOUT objects, only the one sheet.
+        let active_name = || {
+            let active = raw
+                .layouts
+                .iter()
+                .find(|l| l.block.as_deref() == Some("*paper_space"));
+            active.map_or("*Paper_Space".to_string(), |l| l.name.clone())
+        };
+        let (name, block) = match name {
+            None => (active_name(), "*paper_space".to_string()),
+            Some(name) => match raw
+                .layouts
+                .iter()
+                .find(|l| l.name.eq_ignore_ascii_case(name))
+            {
+                Some(raw::Layout {
+                    name,
+                    block: Some(block),
+                    ..
+                }) => (name.clone(), block.clone()),
+                _ => {
+                    let names: Vec<&str> = raw.layouts.iter().map(|l| l.name.as_str()).collect();
+                    bail!("no layout {name:?}; the drawing has {}", names.join(", "));
+                }
+            },
+        };
+        if block == "*model_space" {
+            self.import_all();
+            return Ok(());
+        }
+        self.log.layout = Some(name);
+
+        let active = block == "*paper_space";
+        let drawn = self
+            .drawing
+            .entities()
+            .filter(|e| active && e.common.is_in_paper_space);
+        let in_block = self.blocks.get(&block).map(|b| b.entities.iter());
+        let sheet: Vec<&dxf::entities::Entity> =
+            drawn.chain(in_block.into_iter().flatten()).collect();
+        let raw_drawn = raw.model.iter().filter(|e| active && e.paper_space);
+        let raw_sheet: Vec<&raw::RawEntity<'_>> = raw_drawn
+            .chain(raw.blocks.get(&block).into_iter().flatten())
+            .collect();
+
+        self.import_sheet(&sheet, &raw_sheet);
+
+        for ent in raw_sheet.iter().filter(|e| e.type_name == "VIEWPORT") {
+            let Some(viewport) = raw::viewport(&ent.codes) else {
+                continue;
+            };
+            if viewport.status == 0 || viewport.id == 1 {
+                continue;
+            }
+            if viewport.plan && viewport.view_height > 0.0 {
+                self.import_viewport(&viewport);
+            } else {
+                *self.log.skipped.entry("VIEWPORT".to_string()).or_default() += 1;
+            }
+        }
+        self.count_skipped();
+        Ok(())
+    }
+
+    /// Entities drawn on a sheet, listed in the log as paper space.
+    fn import_sheet(
+        &mut self,
+        sheet: &[&dxf::entities::Entity],
+        raw_sheet: &[&raw::RawEntity<'_>],
+    ) {
+        let tx = Transform2D::identity();
+        let start = (self.entities.len(), self.dims.len(), self.texts.len());
+        let mut stack = Vec::new();
+        for ent in sheet {
             self.import_entity(ent, &tx, None, &mut stack, 0);
         }
+        for ent in raw_sheet {
+            self.import_raw(ent, &tx, None, &[]);
+        }
+        let entities = self.entities[start.0..].iter().map(|e| e.id);
+        let dims = self.dims[start.1..].iter().map(|d| d.id);
+        let texts = self.texts[start.2..].iter().map(|t| t.id);
+        self.log.paper_space = entities.chain(dims).chain(texts).collect();
+        self.log.paper_space.sort_unstable();
+    }
+
+    /// Model space as `viewport` shows it on the sheet. Curves are cut at
+    /// the window's edges, texts and dimensions kept if their anchor is in it.
+    fn import_viewport(&mut self, viewport: &raw::Viewport) {
+        let scale = viewport.height / viewport.view_height;
+        let (sin, cos) = viewport.twist_deg.to_radians().sin_cos();
+        let twist = Transform2D {
+            m11: cos * scale,
+            m12: -sin * scale,
+            m21: sin * scale,
+            m22: cos * scale,
+            tx: 0.0,
+            ty: 0.0,
+        };
+        // The target lands on the view center, which lands on the window's.
+        let offset =
+            viewport.center - viewport.view_center * scale - twist.apply_vector(viewport.target);
+        let tx = Transform2D {
+            tx: offset.x,
+            ty: offset.y,
+            ..twist
+        };
+        let half = Vec2::new(viewport.width, viewport.height) * 0.5;
+        let window = BBox2::new(viewport.center - half, viewport.center + half);
+
+        let start = (self.entities.len(), self.dims.len(), self.texts.len());
+        self.import_model(&tx);
+        let seen: Vec<Entity2D> = self.entities.drain(start.0..).collect();
+        let mut hidden = Vec::new();
+        for entity in seen {
+            let mut parts = entity.primitive.clip(&window).into_iter();
+            let Some(first) = parts.next() else {
+                hidden.push(entity.id);
+                continue;
+            };
+            let projected = self.log.projected.contains(&entity.id);
+            self.entities.push(Entity2D {
+                primitive: first,
+                ..entity.clone()
+            });
+            for primitive in parts {
+                let id = self.next_id();
+                if projected {
+                    self.log.projected.push(id);
+                }
+                self.entities.push(Entity2D {
+                    id,
+                    primitive,
+                    ..entity.clone()
+                });
+            }
+        }
+        self.log.projected.retain(|id| !hidden.contains(id));
+        let dims = self.dims.split_off(start.1);
+        let dims = dims
+            .into_iter()
+            .filter(|d| d.at.is_none_or(|at| window.contains(at)));
+        self.dims.extend(dims);
+        let texts = self.texts.split_off(start.2);
+        self.texts
+            .extend(texts.into_iter().filter(|t| window.contains(t.at)));
+    }
+
+    /// Counted once per occurrence in the file, not per insert.
+    fn count_skipped(&mut self) {
+        let raw = self.raw;
+        let all = raw.model.iter().chain(raw.blocks.values().flatten());
+        for ent in all {
+            let name = ent.type_name;
+            if !IMPORTED_TYPES.contains(&name) && !PART_TYPES.contains(&name) {
+                *self.log.skipped.entry(name.to_string()).or_default() += 1;
+            }
+        }
     }
 
     fn import_entity(
@@ -60,24 +324,30 @@ impl<'a> DxfImporter<'a> {
         ent: &dxf::entities::Entity,
         tx: &Transform2D,
         parent_style: Option<&Style>,
-        stack: &mut Vec<String>,
+        stack: &mut InsertStack,
         depth: usize,
     ) {
         let style = self.resolve_style(ent, parent_style);
+        let handle = handle_string(ent.common.handle);
+        self.source = provenance(handle.clone(), stack);
         match &ent.specific {
             EntityType::Insert(insert) => {
-                self.import_insert(insert, &style, tx, stack, depth + 1);
+                self.import_insert(insert, handle, &style, tx, stack, depth + 1);
             }
             EntityType::Line(line) => {
                 let kind = classify_linetype(&style.linetype);
                 let a = tx.apply_point(Vec2::new(line.p1.x, line.p1.y));
                 let b = tx.apply_point(Vec2::new(line.p2.x, line.p2.y));
                 let id = self.next_id();
+                if line.p1.z != line.p2.z {
+                    self.log.projected.push(id);
+                }
                 self.entities.push(Entity2D {
                     id,
                     kind,
                     primitive: Primitive2D::Line(LineSeg2D { a, b }),
                     style,
+                    source: self.source.clone(),
                 });
             }
             EntityType::Circle(circle) => {
@@ -92,25 +362,11 @@ impl<'a> DxfImporter<'a> {
                         kind,
                         primitive: Primitive2D::Circle(Circle2D { center, radius }),
                         style,
+                        source: self.source.clone(),
                     });
                 } else {
-                    let vertices = circle_points(center, circle.radius, 64)
-                        .into_iter()
-                        .map(|p| PolylineVertex2D {
-                            pos: tx.apply_point(p),
-                            bulge: 0.0,
-                        })
-                        .collect();
-                    let id = self.next_id();
-                    self.entities.push(Entity2D {
-                        id,
-                        kind,
-                        primitive: Primitive2D::Polyline(Polyline2D {
-                            vertices,
-                            closed: true,
-                        }),
-                        style,
-                    });
+                    let axes = (Vec2::new(circle.radius, 0.0), Vec2::new(0.0, circle.radius));
+                    self.import_elliptical_arc(center, axes, (0.0, 360.0), kind, style, tx);
                 }
             }
             EntityType::Arc(arc) => {
@@ -131,31 +387,12 @@ impl<'a> DxfImporter<'a> {
                             end_angle_deg: arc.end_angle + rot_deg,
                         }),
                         style,
+                        source: self.source.clone(),
                     });
                 } else {
-                    let vertices = arc_points(
-                        center,
-                        arc.radius,
-                        arc.start_angle,
-                        arc.end_angle,
-                        48,
-                    )
-                    .into_iter()
-                    .map(|p| PolylineVertex2D {
-                        pos: tx.apply_point(p),
-                        bulge: 0.0,
-                    })
-                    .collect();
-                    let id = self.next_id();
-                    self.entities.push(Entity2D {
-                        id,
-                        kind,
-                        primitive: Primitive2D::Polyline(Polyline2D {
-                            vertices,
-                            closed: false,
-                        }),
-                        style,
-                    });
+                    let axes = (Vec2::new(arc.radius, 0.0), Vec2::new(0.0, arc.radius));
+                    let sweep = hatch_edge_sweep(arc.start_angle, arc.end_angle, true);
+                    self.import_elliptical_arc(center, axes, sweep, kind, style, tx);
                 }
             }
             EntityType::LwPolyline(poly) => {
@@ -176,6 +413,7 @@ impl<'a> DxfImporter<'a> {
                     kind,
                     primitive: Primitive2D::Polyline(Polyline2D { vertices, closed }),
                     style,
+                    source: self.source.clone(),
                 });
             }
             EntityType::Polyline(poly) => {
@@ -197,6 +435,7 @@ impl<'a> DxfImporter<'a> {
                         closed: false,
                     }),
                     style,
+                    source: self.source.clone(),
                 });
             }
             EntityType::Spline(spline) => {
@@ -206,16 +445,30 @@ impl<'a> DxfImporter<'a> {
                 self.import_ellipse(ellipse, style, tx);
             }
             EntityType::Text(t) => {
-                let at = tx.apply_point(Vec2::new(t.location.x, t.location.y));
-                let height = Some(scale_text_height(tx, t.text_height));
-                let id = self.next_id();
-                self.texts.push(TextEntity {
-                    id,
-                    text: t.value.clone(),
-                    at,
-                    height,
-                    style,
-                });
+                let text = PlacedText {
+                    value: &t.value,
+                    location: &t.location,
+                    second_point: &t.second_alignment_point,
+                    justification: t.horizontal_text_justification,
+                    height: t.text_height,
+                    rotation: t.rotation,
+                };
+                self.import_text(text, style, tx);
+            }
+            // Shown on every insert without an ATTRIB of its own.
+            EntityType::AttributeDefinition(def)
+                if def.is_constant() && !def.is_invisible() && !stack.is_empty() =>
+            {
+                self.source.attribute_tag = Some(def.text_tag.clone());
+                let text = PlacedText {
+                    value: &def.value,
+                    location: &def.location,
+                    second_point: &def.second_alignment_point,
+                    justification: def.horizontal_text_justification,
+                    height: def.text_height,
+                    rotation: def.rotation,
+                };
+                self.import_text(text, style, tx);
             }
             EntityType::MText(t) => {
                 let at = tx.apply_point(Vec2::new(
@@ -223,84 +476,304 @@ impl<'a> DxfImporter<'a> {
                     t.insertion_point.y,
                 ));
                 let height = Some(scale_text_height(tx, t.initial_text_height));
+                let align = match t.attachment_point {
+                    AttachmentPoint::TopCenter
+                    | AttachmentPoint::MiddleCenter
+                    | AttachmentPoint::BottomCenter => TextAlign::Center,
+                    AttachmentPoint::TopRight
+                    | AttachmentPoint::MiddleRight
+                    | AttachmentPoint::BottomRight => TextAlign::Right,
+                    _ => TextAlign::Left,
+                };
+                // The direction vector, when given, wins over the angle,
+                // which MTEXT alone keeps in radians.
+                let dir = &t.x_axis_direction;
+                let rotation = if dir.x == 1.0 && dir.y == 0.0 {
+                    t.rotation_angle.to_degrees()
+                } else {
+                    dir.y.atan2(dir.x).to_degrees()
+                };
                 let id = self.next_id();
                 self.texts.push(TextEntity {
                     id,
                     text: join_mtext(t),
                     at,
                     height,
+                    rotation_deg: transform_angle(tx, rotation),
+                    align,
                     style,
+                    source: self.source.clone(),
                 });
             }
             EntityType::RotatedDimension(d) => {
-                let id = self.next_id();
-                self.dims.push(DimensionEntity {
-                    id,
-                    raw_type: None,
-                    text: empty_to_none(&d.dimension_base.text),
-                    measurement: Some(d.dimension_base.actual_measurement),
-                    style,
-                });
+                let own = [&d.definition_point_2, &d.definition_point_3];
+                self.import_dimension(&d.dimension_base, &own, d.rotation_angle, style, tx);
             }
             EntityType::RadialDimension(d) => {
-                let id = self.next_id();
-                self.dims.push(DimensionEntity {
-                    id,
-                    raw_type: None,
-                    text: empty_to_none(&d.dimension_base.text),
-                    measurement: Some(d.dimension_base.actual_measurement),
-                    style,
-                });
+                let own = [&d.definition_point_2];
+                self.import_dimension(&d.dimension_base, &own, 0.0, style, tx);
             }
             EntityType::DiameterDimension(d) => {
-                let id = self.next_id();
-                self.dims.push(DimensionEntity {
-                    id,
-                    raw_type: None,
-                    text: empty_to_none(&d.dimension_base.text),
-                    measurement: Some(d.dimension_base.actual_measurement),
-                    style,
-                });
+                let own = [&d.definition_point_2];
+                self.import_dimension(&d.dimension_base, &own, 0.0, style, tx);
             }
             EntityType::AngularThreePointDimension(d) => {
+                let own = [
+                    &d.definition_point_2,
+                    &d.definition_point_3,
+                    &d.definition_point_4,
+                    &d.definition_point_5,
+                ];
+                self.import_dimension(&d.dimension_base, &own, 0.0, style, tx);
+            }
+            EntityType::OrdinateDimension(d) => {
+                let own = [&d.definition_point_2, &d.definition_point_3];
+                self.import_dimension(&d.dimension_base, &own, 0.0, style, tx);
+            }
+            EntityType::Solid(solid) => {
+                let corners = [
+                    &solid.first_corner,
+                    &solid.second_corner,
+                    &solid.fourth_corner,
+                    &solid.third_corner,
+                ];
+                self.import_face(corners, EntityKind::Hatch, style, tx);
+            }
+            EntityType::Trace(trace) => {
+                let corners = [
+                    &trace.first_corner,
+                    &trace.second_corner,
+                    &trace.fourth_corner,
+                    &trace.third_corner,
+                ];
+                self.import_face(corners, EntityKind::Hatch, style, tx);
+            }
+            EntityType::Face3D(face) => {
+                let corners = [
+                    &face.first_corner,
+                    &face.second_corner,
+                    &face.third_corner,
+                    &face.fourth_corner,
+                ];
+                let kind = classify_linetype(&style.linetype);
+                let id = self.import_face(corners, kind, style, tx);
+                if corners.iter().any(|c| c.z != corners[0].z) {
+                    self.log.projected.push(id);
+                }
+            }
+            EntityType::ModelPoint(point) => {
+                let at = tx.apply_point(Vec2::new(point.location.x, point.location.y));
                 let id = self.next_id();
-                self.dims.push(DimensionEntity {
+                self.entities.push(Entity2D {
                     id,
-                    raw_type: None,
-                    text: empty_to_none(&d.dimension_base.text),
-                    measurement: Some(d.dimension_base.actual_measurement),
+                    kind: classify_linetype(&style.linetype),
+                    primitive: Primitive2D::Point(at),
                     style,
+                    source: self.source.clone(),
                 });
             }
-            EntityType::OrdinateDimension(d) => {
+            EntityType::Leader(leader) => {
+                let points = leader.vertices.iter().map(|p| Vec2::new(p.x, p.y));
+                self.import_leader_line(points.collect(), style, tx);
+            }
+            EntityType::Tolerance(tolerance) => {
+                let p = &tolerance.insertion_point;
+                let dir = &tolerance.direction_vector;
+                let text = tolerance
+                    .display_text
+                    .replace("%%v", " | ")
+                    .replace("^J", "\n");
                 let id = self.next_id();
-                self.dims.push(DimensionEntity {
+                self.texts.push(TextEntity {
                     id,
-                    raw_type: None,
-                    text: empty_to_none(&d.dimension_base.text),
-                    measurement: Some(d.dimension_base.actual_measurement),
+                    text,
+                    at: tx.apply_point(Vec2::new(p.x, p.y)),
+                    height: None,
+                    rotation_deg: transform_angle(tx, dir.y.atan2(dir.x).to_degrees()),
+                    align: TextAlign::Left,
                     style,
+                    source: self.source.clone(),
                 });
             }
             _ => {
-                // Keep deterministic: ignore unsupported entities for now, but don't fail import.
+                // Counted as skipped by type from the raw scan.
+            }
+        }
+    }
+
+    fn import_text(&mut self, text: PlacedText<'_>, style: Style, tx: &Transform2D) {
+        // Aligned and fit text run between both points, from the first.
+        let align = match text.justification {
+            HorizontalTextJustification::Center | HorizontalTextJustification::Middle => {
+                TextAlign::Center
+            }
+            HorizontalTextJustification::Right => TextAlign::Right,
+            _ => TextAlign::Left,
+        };
+        let anchor = match align {
+            TextAlign::Left => text.location,
+            _ => text.second_point,
+        };
+        let at = tx.apply_point(Vec2::new(anchor.x, anchor.y));
+        let height = Some(scale_text_height(tx, text.height));
+        let id = self.next_id();
+        self.texts.push(TextEntity {
+            id,
+            text: text.value.to_string(),
+            at,
+            height,
+            rotation_deg: transform_angle(tx, text.rotation),
+            align,
+            style,
+            source: self.source.clone(),
+        });
+    }
+
+    /// HATCH boundaries and MULTILEADERs, from their group codes.
+    fn import_raw(
+        &mut self,
+        ent: &raw::RawEntity<'_>,
+        tx: &Transform2D,
+        parent_style: Option<&Style>,
+        stack: &[(String, Option<String>)],
+    ) {
+        self.source = provenance(ent.code(5).map(str::to_string), stack);
+        let (style, color_by_block) = raw_style(ent);
+        let style = inherit_block_style(style, color_by_block, parent_style);
+        match ent.type_name {
+            "HATCH" => {
+                for path in raw::hatch_paths(&ent.codes) {
+                    self.import_hatch_path(&path, style.clone(), tx);
+                }
             }
+            "MULTILEADER" => self.import_mleader(raw::mleader(&ent.codes), style, tx),
+            _ => {}
+        }
+    }
+
+    /// A closed outline through up to four corners; the last is dropped when
+    /// it repeats the one before, as in triangles.
+    fn import_face(
+        &mut self,
+        corners: [&dxf::Point; 4],
+        kind: EntityKind,
+        style: Style,
+        tx: &Transform2D,
+    ) -> u64 {
+        let mut points: Vec<Vec2> = corners.iter().map(|c| Vec2::new(c.x, c.y)).collect();
+        if points[3] == points[2] {
+            points.pop();
+        }
+        let vertices = points
+            .into_iter()
+            .map(|p| PolylineVertex2D {
+                pos: tx.apply_point(p),
+                bulge: 0.0,
+            })
+            .collect();
+        let id = self.next_id();
+        self.entities.push(Entity2D {
+            id,
+            kind,
+            primitive: Primitive2D::Polyline(Polyline2D {
+                vertices,
+                closed: true,
+            }),
+            style,
+            source: self.source.clone(),
+        });
+        id
+    }
+
+    /// One hatch boundary loop as a closed polyline of kind `Hatch`.
+    fn import_hatch_path(&mut self, path: &raw::BoundaryPath, style: Style, tx: &Transform2D) {
+        let vertices = match path {
+            raw::BoundaryPath::Polyline(vertices) => vertices.clone(),
+            raw::BoundaryPath::Edges(edges) => edges.iter().flat_map(edge_vertices).collect(),
+        };
+        if vertices.len() < 2 {
+            return;
+        }
+        let preserve_bulge = tx.uniform_scale_rotation().is_some();
+        let vertices = vertices
+            .into_iter()
+            .map(|(p, bulge)| PolylineVertex2D {
+                pos: tx.apply_point(p),
+                bulge: if preserve_bulge { bulge } else { 0.0 },
+            })
+            .collect();
+        let id = self.next_id();
+        self.entities.push(Entity2D {
+            id,
+            kind: EntityKind::Hatch,
+            primitive: Primitive2D::Polyline(Polyline2D {
+                vertices,
+                closed: true,
+            }),
+            style,
+            source: self.source.clone(),
+        });
+    }
+
+    fn import_mleader(&mut self, leader: raw::MLeader, style: Style, tx: &Transform2D) {
+        for line in leader.lines {
+            self.import_leader_line(line, style.clone(), tx);
+        }
+        if let (Some(text), Some(at)) = (leader.text, leader.text_at) {
+            let dir = leader.text_direction.unwrap_or(Vec2::new(1.0, 0.0));
+            let id = self.next_id();
+            self.texts.push(TextEntity {
+                id,
+                text,
+                at: tx.apply_point(at),
+                height: leader.text_height.map(|h| scale_text_height(tx, h)),
+                rotation_deg: transform_angle(tx, dir.y.atan2(dir.x).to_degrees()),
+                align: TextAlign::Left,
+                style,
+                source: self.source.clone(),
+            });
+        }
+    }
+
+    /// Leader lines are annotation, drawn but kept out of the views.
+    fn import_leader_line(&mut self, points: Vec<Vec2>, style: Style, tx: &Transform2D) {
+        if points.len() < 2 {
+            return;
         }
+        let vertices = points
+            .into_iter()
+            .map(|p| PolylineVertex2D {
+                pos: tx.apply_point(p),
+                bulge: 0.0,
+            })
+            .collect();
+        let id = self.next_id();
+        self.entities.push(Entity2D {
+            id,
+            kind: EntityKind::Dimension,
+            primitive: Primitive2D::Polyline(Polyline2D {
+                vertices,
+                closed: false,
+            }),
+            style,
+            source: self.source.clone(),
+        });
     }
 
     fn import_insert(
         &mut self,
         insert: &dxf::entities::Insert,
+        handle: Option<String>,
         insert_style: &Style,
         parent_tx: &Transform2D,
-        stack: &mut Vec<String>,
+        stack: &mut InsertStack,
         depth: usize,
     ) {
         if depth > 8 {
             return;
         }
         let name = insert.name.to_ascii_lowercase();
-        if stack.iter().any(|n| n == &name) {
+        if stack.iter().any(|(n, _)| n.eq_ignore_ascii_case(&name)) {
             return;
         }
         let (base, entities) = match self.blocks.get(&name) {
@@ -310,7 +783,7 @@ impl<'a> DxfImporter<'a> {
             ),
             None => return,
         };
-        stack.push(name);
+        stack.push((insert.name.clone(), handle));
         let loc = Vec2::new(insert.location.x, insert.location.y);
         let scale = Vec2::new(insert.x_scale_factor, insert.y_scale_factor);
         let rot = insert.rotation;
@@ -328,45 +801,107 @@ impl<'a> DxfImporter<'a> {
                 for ent in &entities {
                     self.import_entity(ent, &combined, Some(insert_style), stack, depth);
                 }
+                let raw = self.raw;
+                for ent in raw.blocks.get(&name).into_iter().flatten() {
+                    self.import_raw(ent, &combined, Some(insert_style), stack);
+                }
             }
         }
 
+        // Attributes are placed where the insert is, not in the block. The
+        // `dxf` crate drops their layer, color and handle, so those come from
+        // the raw scan, which has them in the same order.
+        let raw = self.raw;
+        let scanned = match stack.last().and_then(|(_, handle)| handle.as_deref()) {
+            Some(handle) => raw.attributes(handle),
+            None => &[],
+        };
+        for (i, attribute) in insert.attributes().enumerate() {
+            if attribute.is_invisible() {
+                continue;
+            }
+            let scanned = scanned.get(i);
+            let style = scanned.map_or_else(
+                || insert_style.clone(),
+                |ent| attribute_style(ent, insert_style),
+            );
+            let handle = scanned.and_then(|ent| ent.handle).map(str::to_string);
+            self.source = Provenance {
+                attribute_tag: Some(attribute.attribute_tag.clone()),
+                ..provenance(handle, stack)
+            };
+            let text = PlacedText {
+                value: &attribute.value,
+                location: &attribute.location,
+                second_point: &attribute.second_alignment_point,
+                justification: attribute.horizontal_text_justification,
+                height: attribute.text_height,
+                rotation: attribute.rotation,
+            };
+            self.import_text(text, style, parent_tx);
+        }
         stack.pop();
     }
 
+    /// A NURBS when the knots fit the control points, otherwise a polyline
+    /// through the fit points or, failing those, the control points.
     fn import_spline(
         &mut self,
         spline: &dxf::entities::Spline,
         style: Style,
         tx: &Transform2D,
     ) {
-        let points = if !spline.fit_points.is_empty() {
-            spline.fit_points.iter().collect::<Vec<_>>()
-        } else {
-            spline.control_points.iter().collect::<Vec<_>>()
-        };
-        if points.len() < 2 {
-            return;
+        let control_points: Vec<Vec2> = spline
+            .control_points
+            .iter()
+            .map(|p| tx.apply_point(Vec2::new(p.x, p.y)))
+            .collect();
+        // Files often list unit weights for non-rational splines too.
+        let mut weights = spline.weight_values.clone();
+        if weights.len() != control_points.len() || weights.iter().all(|w| *w == 1.0) {
+            weights.clear();
         }
-        let vertices = points
-            .into_iter()
-            .map(|p| PolylineVertex2D {
-                pos: tx.apply_point(Vec2::new(p.x, p.y)),
-                bulge: 0.0,
+        let nurbs = Nurbs2D {
+            degree: spline.degree_of_curve.max(0) as usize,
+            control_points,
+            weights,
+            knots: spline.knot_values.clone(),
+        };
+        let primitive = if nurbs.is_valid() {
+            Primitive2D::Nurbs(nurbs)
+        } else {
+            let points = if !spline.fit_points.is_empty() {
+                spline
+                    .fit_points
+                    .iter()
+                    .map(|p| tx.apply_point(Vec2::new(p.x, p.y)))
+                    .collect()
+            } else {
+                nurbs.control_points
+            };
+            if points.len() < 2 {
+                return;
+            }
+            let vertices = points
+                .into_iter()
+                .map(|pos| PolylineVertex2D { pos, bulge: 0.0 })
+                .collect();
+            Primitive2D::Polyline(Polyline2D {
+                vertices,
+                closed: false,
             })
-            .collect();
+        };
         let id = self.next_id();
         self.entities.push(Entity2D {
             id,
             kind: classify_linetype(&style.linetype),
-            primitive: Primitive2D::Polyline(Polyline2D {
-                vertices,
-                closed: false,
-            }),
+            primitive,
             style,
+            source: self.source.clone(),
         });
     }
 
+    /// An ellipse stays one under any insert transform, skewed or not.
     fn import_ellipse(
         &mut self,
         ellipse: &dxf::entities::Ellipse,
@@ -375,40 +910,73 @@ impl<'a> DxfImporter<'a> {
     ) {
         let center = Vec2::new(ellipse.center.x, ellipse.center.y);
         let major = Vec2::new(ellipse.major_axis.x, ellipse.major_axis.y);
-        let major_len = (major.x * major.x + major.y * major.y).sqrt();
-        if !major_len.is_finite() || major_len <= 0.0 {
-            return;
-        }
-        let minor_len = major_len * ellipse.minor_axis_ratio;
-        let minor_dir = norm(Vec2::new(-major.y, major.x));
-        let minor = Vec2::new(minor_dir.x * minor_len, minor_dir.y * minor_len);
-
-        let vertices = ellipse_points(
-            center,
-            major,
-            minor,
-            ellipse.start_parameter,
-            ellipse.end_parameter,
-            64,
-        )
-        .into_iter()
-        .map(|p| PolylineVertex2D {
-            pos: tx.apply_point(p),
-            bulge: 0.0,
-        })
-        .collect::<Vec<_>>();
+        let minor = major.perp() * ellipse.minor_axis_ratio;
+        let sweep = hatch_edge_sweep(
+            ellipse.start_parameter.to_degrees(),
+            ellipse.end_parameter.to_degrees(),
+            true,
+        );
+        let kind = classify_linetype(&style.linetype);
+        self.import_elliptical_arc(center, (major, minor), sweep, kind, style, tx);
+    }
 
-        if vertices.len() < 2 {
+    /// The image under `tx` of the elliptical arc on the conjugate axes
+    /// `(u, v)` over `(start, sweep)` in degrees. Also how circles and arcs
+    /// come out of a transform that does not keep them round.
+    fn import_elliptical_arc(
+        &mut self,
+        center: Vec2,
+        (u, v): (Vec2, Vec2),
+        (start, sweep): (f64, f64),
+        kind: EntityKind,
+        style: Style,
+        tx: &Transform2D,
+    ) {
+        let center = tx.apply_point(center);
+        let (u, v) = (tx.apply_vector(u), tx.apply_vector(v));
+        let Some(ellipse) = Ellipse2D::from_conjugate(center, u, v, start, sweep) else {
             return;
-        }
-
-        let closed = is_full_ellipse(ellipse.start_parameter, ellipse.end_parameter);
+        };
         let id = self.next_id();
         self.entities.push(Entity2D {
             id,
-            kind: classify_linetype(&style.linetype),
-            primitive: Primitive2D::Polyline(Polyline2D { vertices, closed }),
+            kind,
+            primitive: Primitive2D::Ellipse(ellipse),
             style,
+            source: self.source.clone(),
+        });
+    }
+
+    /// `own` are the type's definition points after the base's, in group code
+    /// order; see [`DimensionDefinition`].
+    fn import_dimension(
+        &mut self,
+        base: &dxf::entities::DimensionBase,
+        own: &[&dxf::Point],
+        rotation_deg: f64,
+        style: Style,
+        tx: &Transform2D,
+    ) {
+        let id = self.next_id();
+        let points = std::iter::once(&base.definition_point_1)
+            .chain(own.iter().copied())
+            .map(|p| tx.apply_point(Vec2::new(p.x, p.y)))
+            .collect();
+        self.dims.push(DimensionEntity {
+            id,
+            raw_type: Some(base.dimension_type as i16),
+            text: empty_to_none(&base.text),
+            measurement: Some(base.actual_measurement),
+            at: Some(tx.apply_point(Vec2::new(
+                base.text_mid_point.x,
+                base.text_mid_point.y,
+            ))),
+            definition: Some(DimensionDefinition {
+                points,
+                rotation_deg: transform_angle(tx, rotation_deg),
+            }),
+            style,
+            source: self.source.clone(),
         });
     }
 
@@ -419,26 +987,27 @@ impl<'a> DxfImporter<'a> {
     }
 
     fn resolve_style(&self, ent: &dxf::entities::Entity, parent_style: Option<&Style>) -> Style {
-        let mut style = Style {
+        let style = Style {
             layer: Some(ent.common.layer.clone()),
             linetype: Some(ent.common.line_type_name.clone()),
             color_index: ent.common.color.index().map(|v| v as i16),
+            lineweight: None,
         };
-        if let Some(parent) = parent_style {
-            if is_layer_zero(&style.layer) {
-                style.layer = parent.layer.clone();
-            }
-            if is_byblock_linetype(&style.linetype) {
-                style.linetype = parent.linetype.clone();
-            }
-            if ent.common.color.is_by_block() {
-                style.color_index = parent.color_index;
-            }
-        }
-        style
+        inherit_block_style(style, ent.common.color.is_by_block(), parent_style)
     }
 }
 
+/// One line of text as TEXT, ATTRIB and ATTDEF place it.
+struct PlacedText<'t> {
+    value: &'t str,
+    location: &'t dxf::Point,
+    /// The other end of the baseline, for centred and right-aligned text.
+    second_point: &'t dxf::Point,
+    justification: HorizontalTextJustification,
+    height: f64,
+    rotation: f64,
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Transform2D {
     m11: f64,
@@ -503,6 +1072,13 @@ impl Transform2D {
         )
     }
 
+    fn apply_vector(&self, v: Vec2) -> Vec2 {
+        Vec2::new(
+            self.m11 * v.x + self.m12 * v.y,
+            self.m21 * v.x + self.m22 * v.y,
+        )
+    }
+
     fn uniform_scale_rotation(&self) -> Option<(f64, f64)> {
         const EPS: f64 = 1e-6;
         let sx = (self.m11 * self.m11 + self.m21 * self.m21).sqrt();
@@ -534,128 +1110,231 @@ fn scale_text_height(tx: &Transform2D, height: f64) -> f64 {
     }
 }
 
-fn is_layer_zero(layer: &Option<String>) -> bool {
-    matches!(layer.as_deref(), Some(v) if v.eq_ignore_ascii_case("0"))
+/// Direction `deg` in block coordinates, as an angle after `tx`.
+fn transform_angle(tx: &Transform2D, deg: f64) -> f64 {
+    let (sin, cos) = deg.to_radians().sin_cos();
+    let x = tx.m11 * cos + tx.m12 * sin;
+    let y = tx.m21 * cos + tx.m22 * sin;
+    y.atan2(x).to_degrees()
 }
 
-fn is_byblock_linetype(linetype: &Option<String>) -> bool {
-    matches!(linetype.as_deref(), Some(v) if v.eq_ignore_ascii_case("BYBLOCK"))
+/// Puts back the handles of block entities, which the `dxf` crate renumbers
+/// on load, by matching them in order and by type to the raw scan's. Those it
+/// cannot match are cleared rather than left wrong.
+fn restore_block_handles(drawing: &mut dxf::Drawing, raw: &raw::RawEntities<'_>) {
+    for block in drawing.blocks_mut() {
+        let scanned = raw.blocks.get(&block.name.to_ascii_lowercase());
+        let mut scanned = scanned.into_iter().flatten();
+        for ent in &mut block.entities {
+            let found = dxf_type_name(&ent.specific)
+                .and_then(|name| scanned.find(|e| e.type_name == name))
+                .and_then(|e| u64::from_str_radix(e.handle?, 16).ok());
+            ent.common.handle = dxf::Handle(found.unwrap_or(0));
+        }
+    }
 }
 
-fn classify_linetype(linetype: &Option<String>) -> EntityKind {
-    let lt = match linetype {
-        Some(v) => v.to_ascii_lowercase(),
-        None => return EntityKind::Unknown,
+/// The DXF name of the types imported from the `dxf` crate.
+fn dxf_type_name(specific: &EntityType) -> Option<&'static str> {
+    Some(match specific {
+        EntityType::Line(_) => "LINE",
+        EntityType::Circle(_) => "CIRCLE",
+        EntityType::Arc(_) => "ARC",
+        EntityType::LwPolyline(_) => "LWPOLYLINE",
+        EntityType::Polyline(_) => "POLYLINE",
+        EntityType::Spline(_) => "SPLINE",
+        EntityType::Ellipse(_) => "ELLIPSE",
+        EntityType::Text(_) => "TEXT",
+        EntityType::MText(_) => "MTEXT",
+        EntityType::RotatedDimension(_)
+        | EntityType::RadialDimension(_)
+        | EntityType::DiameterDimension(_)
+        | EntityType::AngularThreePointDimension(_)
+        | EntityType::OrdinateDimension(_) => "DIMENSION",
+        EntityType::Insert(_) => "INSERT",
+        EntityType::Solid(_) => "SOLID",
+        EntityType::Trace(_) => "TRACE",
+        EntityType::Face3D(_) => "3DFACE",
+        EntityType::ModelPoint(_) => "POINT",
+        EntityType::Leader(_) => "LEADER",
+        EntityType::Tolerance(_) => "TOLERANCE",
+        EntityType::AttributeDefinition(_) => "ATTDEF",
+        _ => return None,
+    })
+}
+
+/// The source of an entity with `handle`, reached through the inserts on
+/// `stack`.
+fn provenance(handle: Option<String>, stack: &[(String, Option<String>)]) -> Provenance {
+    Provenance {
+        handle,
+        block_path: stack.iter().map(|(name, _)| name.clone()).collect(),
+        insert_handle: stack.first().and_then(|(_, handle)| handle.clone()),
+        attribute_tag: None,
+    }
+}
+
+fn handle_string(handle: dxf::Handle) -> Option<String> {
+    (!handle.is_empty()).then(|| handle.as_string())
+}
+
+/// The style a scanned entity's group codes give, and whether its color is
+/// BYBLOCK.
+fn raw_style(ent: &raw::RawEntity<'_>) -> (Style, bool) {
+    let color = ent
+        .code(62)
+        .and_then(|v| v.parse::<i16>().ok())
+        .unwrap_or(256);
+    let style = Style {
+        layer: Some(ent.code(8).unwrap_or("0").to_string()),
+        linetype: Some(ent.code(6).unwrap_or("BYLAYER").to_string()),
+        color_index: (1..=255).contains(&color).then_some(color),
+        lineweight: None,
     };
-    if lt.contains("center") || lt.contains("centre") {
-        return EntityKind::Center;
+    (style, color == 0)
+}
+
+/// An ATTRIB's own style, with BYBLOCK linetype and color taken from the
+/// insert. Its layer stays its own, 0 included, as it is not block content.
+fn attribute_style(ent: &raw::RawEntity<'_>, insert_style: &Style) -> Style {
+    let (mut style, color_by_block) = raw_style(ent);
+    if is_byblock_linetype(&style.linetype) {
+        style.linetype = insert_style.linetype.clone();
     }
-    if lt.contains("hidden") || lt.contains("hid") {
-        return EntityKind::Hidden;
+    if color_by_block {
+        style.color_index = insert_style.color_index;
     }
-    EntityKind::Object
+    style
 }
 
-fn circle_points(center: Vec2, radius: f64, segments: usize) -> Vec<Vec2> {
-    if !radius.is_finite() || radius <= 0.0 || segments < 3 {
-        return Vec::new();
-    }
-    let mut pts = Vec::with_capacity(segments + 1);
-    for i in 0..=segments {
-        let t = i as f64 / segments as f64;
-        let a = t * std::f64::consts::TAU;
-        pts.push(Vec2::new(
-            center.x + radius * a.cos(),
-            center.y + radius * a.sin(),
-        ));
+/// Layer 0, BYBLOCK linetype and BYBLOCK color take the inserting entity's.
+fn inherit_block_style(mut style: Style, color_by_block: bool, parent: Option<&Style>) -> Style {
+    if let Some(parent) = parent {
+        if is_layer_zero(&style.layer) {
+            style.layer = parent.layer.clone();
+        }
+        if is_byblock_linetype(&style.linetype) {
+            style.linetype = parent.linetype.clone();
+        }
+        if color_by_block {
+            style.color_index = parent.color_index;
+        }
     }
-    pts
+    style
 }
 
-fn arc_points(
-    center: Vec2,
-    radius: f64,
-    start_deg: f64,
-    end_deg: f64,
-    segments: usize,
-) -> Vec<Vec2> {
-    if !radius.is_finite() || radius <= 0.0 || segments < 2 {
-        return Vec::new();
-    }
-    let a0 = start_deg.to_radians();
-    let mut a1 = end_deg.to_radians();
-    if a1 < a0 {
-        a1 += std::f64::consts::TAU;
-    }
-    let mut pts = Vec::with_capacity(segments + 1);
-    for i in 0..=segments {
-        let t = i as f64 / segments as f64;
-        let a = a0 + (a1 - a0) * t;
-        pts.push(Vec2::new(
-            center.x + radius * a.cos(),
-            center.y + radius * a.sin(),
-        ));
-    }
-    pts
+/// Vertices of a hatch boundary edge with the bulge to the next, leaving out
+/// the end point, which the next edge starts at. Ellipses and splines are
+/// flattened to within [`HATCH_EDGE_TOLERANCE`] of their size.
+fn edge_vertices(edge: &raw::Edge) -> Vec<(Vec2, f64)> {
+    match edge {
+        raw::Edge::Line(a, _) => vec![(*a, 0.0)],
+        raw::Edge::Arc {
+            center,
+            radius,
+            start_deg,
+            end_deg,
+            ccw,
+        } => {
+            // Clockwise edges store their angles mirrored, as if counter-clockwise.
+            let (start, sweep) = hatch_edge_sweep(*start_deg, *end_deg, *ccw);
+            // Bulges cannot describe a full turn, so split anything over half.
+            let pieces = if sweep.abs() > 180.0 { 2 } else { 1 };
+            let step = sweep / pieces as f64;
+            let bulge = (step.to_radians() / 4.0).tan();
+            (0..pieces)
+                .map(|i| {
+                    let a = (start + step * i as f64).to_radians();
+                    (*center + Vec2::from_angle(a) * *radius, bulge)
+                })
+                .collect()
+        }
+        raw::Edge::Ellipse {
+            center,
+            major,
+            ratio,
+            start_deg,
+            end_deg,
+            ccw,
+        } => {
+            let (start, sweep) = hatch_edge_sweep(*start_deg, *end_deg, *ccw);
+            // Flattened counter-clockwise, then turned back if need be.
+            let ellipse = Ellipse2D {
+                center: *center,
+                major_axis: *major,
+                ratio: *ratio,
+                start_param_deg: start.min(start + sweep),
+                end_param_deg: start.max(start + sweep),
+            };
+            let mut points = ellipse.tessellate(major.length() * HATCH_EDGE_TOLERANCE);
+            if sweep < 0.0 {
+                points.reverse();
+            }
+            leave_out_end(points)
+        }
+        raw::Edge::Spline(spline) => {
+            let hull = BBox2::from_points(spline.control_points.iter().copied());
+            leave_out_end(spline.tessellate(hull.diag() * HATCH_EDGE_TOLERANCE))
+        }
+    }
 }
 
-fn ellipse_points(
-    center: Vec2,
-    major: Vec2,
-    minor: Vec2,
-    start: f64,
-    end: f64,
-    segments: usize,
-) -> Vec<Vec2> {
-    if segments < 2 || !start.is_finite() || !end.is_finite() {
-        return Vec::new();
-    }
-    let a0 = start;
-    let mut a1 = end;
-    if a1 < a0 {
-        a1 += std::f64::consts::TAU;
-    }
-    let mut pts = Vec::with_capacity(segments + 1);
-    for i in 0..=segments {
-        let t = i as f64 / segments as f64;
-        let a = a0 + (a1 - a0) * t;
-        pts.push(Vec2::new(
-            center.x + major.x * a.cos() + minor.x * a.sin(),
-            center.y + major.y * a.cos() + minor.y * a.sin(),
-        ));
-    }
-    pts
+/// Chord tolerance of curved hatch edges, relative to their size.
+const HATCH_EDGE_TOLERANCE: f64 = 1e-3;
+
+fn leave_out_end(mut points: Vec<Vec2>) -> Vec<(Vec2, f64)> {
+    points.pop();
+    points.into_iter().map(|p| (p, 0.0)).collect()
 }
 
-fn is_full_ellipse(start: f64, end: f64) -> bool {
-    if !start.is_finite() || !end.is_finite() {
-        return false;
-    }
-    let mut delta = (end - start).abs();
-    if delta > std::f64::consts::TAU {
-        delta = delta % std::f64::consts::TAU;
+/// Start angle and signed sweep, in degrees, of an arc given by its end
+/// angles. Clockwise hatch edges store theirs mirrored. A full turn comes out
+/// as 360 rather than 0.
+fn hatch_edge_sweep(start_deg: f64, end_deg: f64, ccw: bool) -> (f64, f64) {
+    let sweep = match (end_deg - start_deg).rem_euclid(360.0) {
+        s if s < 1e-9 => 360.0,
+        s => s,
+    };
+    if ccw {
+        (start_deg, sweep)
+    } else {
+        (-start_deg, -sweep)
     }
-    (delta - std::f64::consts::TAU).abs() < 1e-6 || delta < 1e-6
 }
 
-fn norm(v: Vec2) -> Vec2 {
-    let len = (v.x * v.x + v.y * v.y).sqrt();
-    if !len.is_finite() || len < 1e-12 {
-        return Vec2::new(0.0, 0.0);
+fn is_layer_zero(layer: &Option<String>) -> bool {
+    matches!(layer.as_deref(), Some(v) if v.eq_ignore_ascii_case("0"))
+}
+
+fn is_byblock_linetype(linetype: &Option<String>) -> bool {
+    matches!(linetype.as_deref(), Some(v) if v.eq_ignore_ascii_case("BYBLOCK"))
+}
+
+fn classify_linetype(linetype: &Option<String>) -> EntityKind {
+    let lt = match linetype {
+        Some(v) => v.to_ascii_lowercase(),
+        None => return EntityKind::Unknown,
+    };
+    if lt.contains("center") || lt.contains("centre") {
+        return EntityKind::Center;
     }
-    Vec2::new(v.x / len, v.y / len)
+    if lt.contains("hidden") || lt.contains("hid") {
+        return EntityKind::Hidden;
+    }
+    if lt.contains("phantom") {
+        return EntityKind::Phantom;
+    }
+    EntityKind::Object
 }
 
+/// The full MTEXT value, with its `\P` paragraph breaks as newlines.
 fn join_mtext(t: &dxf::entities::MText) -> String {
-    if t.extended_text.is_empty() {
-        return t.text.clone();
-    }
     let mut s = String::new();
     s.push_str(&t.text);
     for part in &t.extended_text {
         s.push_str(part);
     }
-    s
+    s.replace("\\P", "\n")
 }
 
 fn empty_to_none(s: &str) -> Option<String> {
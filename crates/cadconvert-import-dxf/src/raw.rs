@@ -0,0 +1,450 @@
+//! A second, shallow pass over the group codes of an ASCII DXF.
+//!
+//! The `dxf` crate drops entity types it has no struct for, HATCH and
+//! MULTILEADER among them, without a trace. This pass picks those two out and
+//! notes the type of every entity, so what is left out can be counted. It
+//! keeps the group codes of ATTRIBs, whose layer, color and handle the crate
+//! drops, and reads VIEWPORTs and the LAYOUT objects that tie each paper-space
+//! sheet to its block, neither of which the crate exposes. Binary DXF is not
+//! scanned.
+
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::Nurbs2D;
+use std::collections::HashMap;
+
+/// Entities as group codes, in file order.
+#[derive(Debug, Default)]
+pub(crate) struct RawEntities<'a> {
+    /// The ENTITIES section.
+    pub model: Vec<RawEntity<'a>>,
+    /// BLOCKS section content by lowercase block name.
+    pub blocks: HashMap<String, Vec<RawEntity<'a>>>,
+    /// Paper-space layouts, in tab order.
+    pub layouts: Vec<Layout>,
+}
+
+#[derive(Debug)]
+pub(crate) struct RawEntity<'a> {
+    pub type_name: &'a str,
+    /// Kept only for the types imported from here and for ATTRIBs.
+    pub codes: Vec<(i32, &'a str)>,
+    /// Group code 67: drawn on the sheet rather than in model space.
+    pub paper_space: bool,
+    pub handle: Option<&'a str>,
+}
+
+impl<'a> RawEntities<'a> {
+    /// The ATTRIBs following the INSERT with this handle, in file order.
+    pub fn attributes(&self, insert_handle: &str) -> &[RawEntity<'a>] {
+        let lists = std::iter::once(&self.model).chain(self.blocks.values());
+        for list in lists {
+            let is_insert = |e: &RawEntity<'_>| {
+                e.type_name == "INSERT"
+                    && e.handle
+                        .is_some_and(|h| h.eq_ignore_ascii_case(insert_handle))
+            };
+            if let Some(i) = list.iter().position(is_insert) {
+                let rest = &list[i + 1..];
+                let count = rest.iter().take_while(|e| e.type_name == "ATTRIB").count();
+                return &rest[..count];
+            }
+        }
+        &[]
+    }
+}
+
+impl<'a> RawEntity<'a> {
+    /// The first value of group code `code`, trimmed.
+    pub fn code(&self, code: i32) -> Option<&'a str> {
+        self.codes
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, v)| v.trim())
+    }
+}
+
+/// A LAYOUT object.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Layout {
+    pub name: String,
+    pub tab_order: i64,
+    /// Lowercase name of the block holding the sheet: `*model_space`,
+    /// `*paper_space` for the active layout, `*paper_space0` and on for the
+    /// others.
+    pub block: Option<String>,
+}
+
+/// Types read from group codes rather than by the `dxf` crate.
+pub(crate) const RAW_TYPES: [&str; 3] = ["HATCH", "MULTILEADER", "VIEWPORT"];
+
+/// Splits `text` into entities, or `None` if it is not ASCII DXF.
+pub(crate) fn scan(text: &str) -> Option<RawEntities<'_>> {
+    let mut lines = text.lines();
+    let mut pairs = std::iter::from_fn(move || {
+        let code = lines.next()?.trim().parse::<i32>().ok()?;
+        Some((code, lines.next()?))
+    })
+    .peekable();
+    pairs.peek()?;
+
+    let mut raw = RawEntities::default();
+    let mut section = "";
+    let mut block: Option<String> = None;
+    // Block record handle to name, and layouts with their record's handle.
+    let mut records: HashMap<&str, String> = HashMap::new();
+    let mut layouts: Vec<(Layout, Option<&str>)> = Vec::new();
+    while let Some((code, value)) = pairs.next() {
+        let value = value.trim();
+        match (code, value) {
+            (0, "SECTION") => {
+                section = pairs
+                    .next_if(|(c, _)| *c == 2)
+                    .map_or("", |(_, v)| v.trim());
+            }
+            (0, "ENDSEC") => section = "",
+            (0, "BLOCK") if section == "BLOCKS" => {
+                let mut name = String::new();
+                while let Some((c, v)) = pairs.next_if(|(c, _)| *c != 0) {
+                    if c == 2 {
+                        name = v.trim().to_ascii_lowercase();
+                    }
+                }
+                raw.blocks.entry(name.clone()).or_default();
+                block = Some(name);
+            }
+            (0, "ENDBLK") => block = None,
+            (0, "BLOCK_RECORD") if section == "TABLES" => {
+                let (mut handle, mut name) = (None, String::new());
+                while let Some((c, v)) = pairs.next_if(|(c, _)| *c != 0) {
+                    match c {
+                        5 => handle = Some(v.trim()),
+                        2 => name = v.trim().to_ascii_lowercase(),
+                        _ => {}
+                    }
+                }
+                if let Some(handle) = handle {
+                    records.insert(handle, name);
+                }
+            }
+            (0, "LAYOUT") if section == "OBJECTS" => {
+                let mut layout = Layout {
+                    name: String::new(),
+                    tab_order: 0,
+                    block: None,
+                };
+                let (mut in_layout, mut record) = (false, None);
+                while let Some((c, v)) = pairs.next_if(|(c, _)| *c != 0) {
+                    match (c, v.trim()) {
+                        (100, marker) => in_layout = marker == "AcDbLayout",
+                        (1, name) if in_layout => layout.name = name.to_string(),
+                        (71, tab) if in_layout => layout.tab_order = tab.parse().unwrap_or(0),
+                        (330, handle) if in_layout => record = Some(handle),
+                        _ => {}
+                    }
+                }
+                layouts.push((layout, record));
+            }
+            (0, type_name) if section == "ENTITIES" || block.is_some() => {
+                let keep = RAW_TYPES.contains(&type_name) || type_name == "ATTRIB";
+                let mut codes = Vec::new();
+                let (mut paper_space, mut handle) = (false, None);
+                while let Some((c, v)) = pairs.next_if(|(c, _)| *c != 0) {
+                    match c {
+                        5 => handle = Some(v.trim()),
+                        67 => paper_space = v.trim() == "1",
+                        _ => {}
+                    }
+                    if keep {
+                        codes.push((c, v));
+                    }
+                }
+                let entity = RawEntity {
+                    type_name,
+                    codes,
+                    paper_space,
+                    handle,
+                };
+                match &block {
+                    Some(name) => raw.blocks.entry(name.clone()).or_default().push(entity),
+                    None => raw.model.push(entity),
+                }
+            }
+            _ => {}
+        }
+    }
+    raw.layouts = layouts
+        .into_iter()
+        .map(|(mut layout, record)| {
+            layout.block = record.and_then(|h| records.get(h)).cloned();
+            layout
+        })
+        .collect();
+    raw.layouts.sort_by_key(|l| l.tab_order);
+    Some(raw)
+}
+
+/// Reads group codes in the order a structure writes them.
+struct Cursor<'c, 'a> {
+    codes: &'c [(i32, &'a str)],
+    at: usize,
+}
+
+impl<'a> Cursor<'_, 'a> {
+    /// The value of the next `code`, skipping anything before it.
+    fn seek(&mut self, code: i32) -> Option<&'a str> {
+        let i = self.codes[self.at..].iter().position(|(c, _)| *c == code)?;
+        self.at += i + 1;
+        Some(self.codes[self.at - 1].1)
+    }
+
+    fn number(&mut self, code: i32) -> Option<f64> {
+        self.seek(code)?.trim().parse().ok()
+    }
+
+    fn int(&mut self, code: i32) -> Option<i64> {
+        self.seek(code)?.trim().parse().ok()
+    }
+
+    fn point(&mut self, x: i32) -> Option<Vec2> {
+        Some(Vec2::new(self.number(x)?, self.number(x + 10)?))
+    }
+
+    /// The value of `code` if it comes next.
+    fn optional(&mut self, code: i32) -> Option<f64> {
+        let (c, v) = self.codes.get(self.at)?;
+        if *c != code {
+            return None;
+        }
+        self.at += 1;
+        v.trim().parse().ok()
+    }
+}
+
+/// One loop of a hatch boundary.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum BoundaryPath {
+    /// Vertices with the bulge to the next one.
+    Polyline(Vec<(Vec2, f64)>),
+    Edges(Vec<Edge>),
+}
+
+/// Angles in degrees, as written: mirrored for clockwise edges.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Edge {
+    Line(Vec2, Vec2),
+    Arc {
+        center: Vec2,
+        radius: f64,
+        start_deg: f64,
+        end_deg: f64,
+        ccw: bool,
+    },
+    Ellipse {
+        center: Vec2,
+        /// Major axis end, relative to the center.
+        major: Vec2,
+        ratio: f64,
+        start_deg: f64,
+        end_deg: f64,
+        ccw: bool,
+    },
+    Spline(Nurbs2D),
+}
+
+/// Boundary paths of a HATCH, up to the first one that does not parse.
+pub(crate) fn hatch_paths(codes: &[(i32, &str)]) -> Vec<BoundaryPath> {
+    let mut cursor = Cursor { codes, at: 0 };
+    let count = cursor.int(91).unwrap_or(0);
+    (0..count)
+        .map_while(|_| boundary_path(&mut cursor))
+        .collect()
+}
+
+fn boundary_path(c: &mut Cursor<'_, '_>) -> Option<BoundaryPath> {
+    let flags = c.int(92)?;
+    if flags & 2 != 0 {
+        let has_bulge = c.int(72)? != 0;
+        let count = c.int(93)?;
+        let vertices = (0..count)
+            .map(|_| {
+                let p = c.point(10)?;
+                let bulge = if has_bulge { c.optional(42) } else { None };
+                Some((p, bulge.unwrap_or(0.0)))
+            })
+            .collect::<Option<_>>()?;
+        return Some(BoundaryPath::Polyline(vertices));
+    }
+    let count = c.int(93)?;
+    let edges = (0..count).map(|_| edge(c)).collect::<Option<_>>()?;
+    Some(BoundaryPath::Edges(edges))
+}
+
+fn edge(c: &mut Cursor<'_, '_>) -> Option<Edge> {
+    Some(match c.int(72)? {
+        1 => Edge::Line(c.point(10)?, c.point(11)?),
+        2 => Edge::Arc {
+            center: c.point(10)?,
+            radius: c.number(40)?,
+            start_deg: c.number(50)?,
+            end_deg: c.number(51)?,
+            ccw: c.int(73)? != 0,
+        },
+        3 => Edge::Ellipse {
+            center: c.point(10)?,
+            major: c.point(11)?,
+            ratio: c.number(40)?,
+            start_deg: c.number(50)?,
+            end_deg: c.number(51)?,
+            ccw: c.int(73)? != 0,
+        },
+        4 => {
+            let degree = usize::try_from(c.int(94)?).ok()?;
+            let rational = c.int(73)? != 0;
+            let (knot_count, control) = (c.int(95)?, c.int(96)?);
+            let knots = (0..knot_count)
+                .map(|_| c.number(40))
+                .collect::<Option<_>>()?;
+            // A weight follows each control point of a rational spline.
+            let mut weights = Vec::new();
+            let control_points = (0..control)
+                .map(|_| {
+                    let p = c.point(10)?;
+                    if rational {
+                        weights.push(c.optional(42).unwrap_or(1.0));
+                    }
+                    Some(p)
+                })
+                .collect::<Option<_>>()?;
+            Edge::Spline(Nurbs2D {
+                degree,
+                control_points,
+                weights,
+                knots,
+            })
+        }
+        _ => return None,
+    })
+}
+
+/// What a MULTILEADER draws: leader lines ending in a landing, and text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct MLeader {
+    pub lines: Vec<Vec<Vec2>>,
+    pub text: Option<String>,
+    pub text_at: Option<Vec2>,
+    pub text_height: Option<f64>,
+    pub text_direction: Option<Vec2>,
+}
+
+pub(crate) fn mleader(codes: &[(i32, &str)]) -> MLeader {
+    #[derive(PartialEq)]
+    enum In {
+        Entity,
+        Context,
+        Leader,
+        Line,
+    }
+    let number = |v: &str| v.trim().parse::<f64>().ok();
+    let mut m = MLeader::default();
+    let mut state = In::Entity;
+    // Per leader: its lines, then the landing from its last point.
+    let mut lines: Vec<Vec<Vec2>> = Vec::new();
+    let (mut last, mut dogleg): (Option<Vec2>, Option<Vec2>) = (None, None);
+    let mut dogleg_length = 0.0;
+    let mut x = None;
+    for &(code, value) in codes {
+        match (code, value.trim(), &state) {
+            (300, "CONTEXT_DATA{", In::Entity) => state = In::Context,
+            (301, "}", In::Context) => state = In::Entity,
+            (302, "LEADER{", In::Context) => {
+                state = In::Leader;
+                (last, dogleg, dogleg_length) = (None, None, 0.0);
+            }
+            (303, "}", In::Leader) => {
+                state = In::Context;
+                for mut line in lines.drain(..) {
+                    if let Some(last) = last {
+                        line.push(last);
+                        if let Some(d) = dogleg.filter(|_| dogleg_length > 0.0) {
+                            line.push(Vec2::new(
+                                last.x + d.x * dogleg_length,
+                                last.y + d.y * dogleg_length,
+                            ));
+                        }
+                    }
+                    if line.len() >= 2 {
+                        m.lines.push(line);
+                    }
+                }
+            }
+            (304, "LEADER_LINE{", In::Leader) => {
+                state = In::Line;
+                lines.push(Vec::new());
+            }
+            (305, "}", In::Line) => state = In::Leader,
+            (304, _, In::Context) => {
+                m.text = Some(value.replace("\\P", "\n")).filter(|t| !t.trim().is_empty())
+            }
+            (41, v, In::Context) => m.text_height = number(v),
+            (10..=13, v, _) => x = number(v),
+            (20, v, In::Line) => {
+                if let (Some(line), Some(x), Some(y)) = (lines.last_mut(), x, number(v)) {
+                    line.push(Vec2::new(x, y));
+                }
+            }
+            (20, v, In::Leader) => last = x.zip(number(v)).map(|(x, y)| Vec2::new(x, y)),
+            (21, v, In::Leader) => dogleg = x.zip(number(v)).map(|(x, y)| Vec2::new(x, y)),
+            (40, v, In::Leader) => dogleg_length = number(v).unwrap_or(0.0),
+            (22, v, In::Context) => m.text_at = x.zip(number(v)).map(|(x, y)| Vec2::new(x, y)),
+            (23, v, In::Context) => {
+                m.text_direction = x.zip(number(v)).map(|(x, y)| Vec2::new(x, y))
+            }
+            _ => {}
+        }
+    }
+    m
+}
+
+/// A paper-space VIEWPORT: a window on the sheet onto model space.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Viewport {
+    /// The window on the sheet.
+    pub center: Vec2,
+    pub width: f64,
+    pub height: f64,
+    /// Zero when turned off.
+    pub status: i64,
+    /// 1 is the sheet's own viewport rather than a window on it.
+    pub id: i64,
+    /// Looking straight down the Z axis, so model XY maps onto the sheet.
+    pub plan: bool,
+    /// Model point at the middle of the view, after twisting about it.
+    pub target: Vec2,
+    pub view_center: Vec2,
+    /// Model height the window shows.
+    pub view_height: f64,
+    pub twist_deg: f64,
+}
+
+pub(crate) fn viewport(codes: &[(i32, &str)]) -> Option<Viewport> {
+    let number = |code: i32| {
+        codes
+            .iter()
+            .find(|(c, _)| *c == code)
+            .and_then(|(_, v)| v.trim().parse::<f64>().ok())
+    };
+    let point = |x: i32| Some(Vec2::new(number(x)?, number(x + 10)?));
+    let direction = [number(16), number(26), number(36)].map(|v| v.unwrap_or(0.0));
+    Some(Viewport {
+        center: point(10)?,
+        width: number(40)?,
+        height: number(41)?,
+        status: number(68).unwrap_or(1.0) as i64,
+        id: number(69).unwrap_or(0.0) as i64,
+        plan: direction[0] == 0.0 && direction[1] == 0.0 && direction[2] >= 0.0,
+        target: point(17).unwrap_or(Vec2::ZERO),
+        view_center: point(12).unwrap_or(Vec2::ZERO),
+        view_height: number(45)?,
+        twist_deg: number(51).unwrap_or(0.0),
+    })
+}
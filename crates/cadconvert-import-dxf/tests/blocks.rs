@@ -0,0 +1,66 @@
+mod common;
+
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{Primitive2D, Provenance};
+use common::load;
+
+fn source(handle: Option<&str>, block_path: &[&str], tag: Option<&str>) -> Provenance {
+    Provenance {
+        handle: handle.map(str::to_string),
+        block_path: block_path.iter().map(|b| b.to_string()).collect(),
+        insert_handle: (!block_path.is_empty()).then(|| "200".to_string()),
+        attribute_tag: tag.map(str::to_string),
+    }
+}
+
+#[test]
+fn entities_record_the_blocks_they_came_through() {
+    let drawing = load("blocks.dxf");
+    let sources: Vec<&Provenance> = drawing.entities.iter().map(|e| &e.source).collect();
+    assert_eq!(
+        vec![
+            &source(Some("100"), &[], None),
+            &source(Some("F1"), &["FRAME"], None),
+            &source(Some("B1"), &["FRAME", "BOSS"], None),
+        ],
+        sources
+    );
+    // The boss is placed by both inserts.
+    match &drawing.entities[2].primitive {
+        Primitive2D::Circle(c) => assert_eq!(Vec2::new(110.0, 100.0), c.center),
+        other => panic!("expected a circle, got {other:?}"),
+    }
+}
+
+#[test]
+fn attributes_become_texts() {
+    let drawing = load("blocks.dxf");
+    let texts: Vec<(&str, Vec2, &Provenance)> = drawing
+        .texts
+        .iter()
+        .map(|t| (t.text.as_str(), t.at, &t.source))
+        .collect();
+    // The constant definition shows through the insert; the others give way
+    // to the insert's own attributes, which are already in place. Invisible
+    // ones are left out.
+    assert_eq!(
+        vec![
+            (
+                "ACME",
+                Vec2::new(100.0, 95.0),
+                &source(Some("F3"), &["FRAME"], Some("COMPANY"))
+            ),
+            (
+                "CC-1234",
+                Vec2::new(120.0, 95.0),
+                &source(Some("201"), &["FRAME"], Some("PARTNO"))
+            ),
+        ],
+        texts
+    );
+    // An attribute keeps its own layer; its BYBLOCK color is the insert's.
+    let partno = &drawing.texts[1];
+    assert_eq!(Some("NOTES"), partno.style.layer.as_deref());
+    assert_eq!(Some(3), partno.style.color_index);
+    assert_eq!(Some(2.5), partno.height);
+}
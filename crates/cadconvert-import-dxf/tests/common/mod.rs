@@ -0,0 +1,24 @@
+//! Helpers shared by the DXF import tests.
+#![allow(dead_code)]
+
+use cadconvert_core::model::{Drawing2D, Entity2D};
+use cadconvert_import_dxf::import_dxf;
+use std::path::PathBuf;
+
+pub fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../fixtures/dxf")
+        .join(name)
+}
+
+pub fn load(name: &str) -> Drawing2D {
+    import_dxf(&fixture(name)).unwrap()
+}
+
+pub fn on_layer<'a>(drawing: &'a Drawing2D, layer: &str) -> Vec<&'a Entity2D> {
+    drawing
+        .entities
+        .iter()
+        .filter(|e| e.style.layer.as_deref() == Some(layer))
+        .collect()
+}
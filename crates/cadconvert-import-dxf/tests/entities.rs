@@ -0,0 +1,155 @@
+mod common;
+
+use cadconvert_core::analysis::{AnalysisConfig, Analyzer};
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{Ellipse2D, Entity2D, EntityKind, Polyline2D, Primitive2D};
+use common::{load, on_layer};
+use std::collections::BTreeMap;
+
+fn polyline(e: &Entity2D) -> &Polyline2D {
+    match &e.primitive {
+        Primitive2D::Polyline(p) => p,
+        other => panic!("expected a polyline, got {other:?}"),
+    }
+}
+
+fn points(p: &Polyline2D) -> Vec<(f64, f64)> {
+    p.vertices.iter().map(|v| (v.pos.x, v.pos.y)).collect()
+}
+
+#[test]
+fn hatches_points_leaders_and_tolerances() {
+    let drawing = load("entities.dxf");
+
+    // One closed outline per boundary loop, arcs kept as bulges.
+    let hatch = on_layer(&drawing, "SECTION");
+    assert_eq!(2, hatch.len());
+    assert!(hatch.iter().all(|e| e.kind == EntityKind::Hatch));
+    let square = polyline(hatch[0]);
+    assert!(square.closed);
+    assert_eq!(
+        vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)],
+        points(square)
+    );
+    assert_eq!(1.0, square.vertices[2].bulge);
+    let edges = polyline(hatch[1]);
+    assert_eq!(
+        vec![(20.0, 0.0), (30.0, 0.0), (30.0, 10.0), (20.0, 10.0)],
+        points(edges)
+    );
+    assert!((edges.vertices[1].bulge - 1.0).abs() < 1e-9, "{edges:?}");
+
+    // Elliptical and spline edges are followed, not their control points:
+    // half an ellipse over a rational spline that dips to 5/3 at its middle.
+    let [slot] = on_layer(&drawing, "SLOT")[..] else {
+        panic!("expected one slot");
+    };
+    let slot = points(polyline(slot));
+    assert_eq!((80.0, 5.0), slot[0]);
+    let (above, below): (Vec<_>, Vec<_>) = slot.iter().partition(|(_, y)| *y > 5.0);
+    for (x, y) in &above {
+        let r = ((x - 70.0) / 10.0).hypot((y - 5.0) / 5.0);
+        assert!((r - 1.0).abs() < 1e-9, "{slot:?}");
+    }
+    let bottom = below.iter().map(|(_, y)| *y).fold(f64::INFINITY, f64::min);
+    assert!(
+        bottom > 5.0 / 3.0 - 1e-9 && bottom < 5.0 / 3.0 + 0.05,
+        "{slot:?}"
+    );
+
+    // SOLID corners run 1, 2, 4, 3 around the outline.
+    let [solid] = on_layer(&drawing, "FILL")[..] else {
+        panic!("expected one solid");
+    };
+    assert_eq!(EntityKind::Hatch, solid.kind);
+    assert_eq!(
+        vec![(40.0, 0.0), (50.0, 0.0), (50.0, 10.0), (40.0, 10.0)],
+        points(polyline(solid))
+    );
+
+    let [point] = on_layer(&drawing, "CENTERS")[..] else {
+        panic!("expected one point");
+    };
+    assert_eq!(Primitive2D::Point(Vec2::new(5.0, 5.0)), point.primitive);
+
+    // The multileader's line ends in its landing, its text stays with it.
+    let leaders = on_layer(&drawing, "NOTES");
+    assert_eq!(2, leaders.len());
+    assert!(leaders.iter().all(|e| e.kind == EntityKind::Dimension));
+    assert_eq!(
+        vec![(10.0, 10.0), (15.0, 15.0), (20.0, 15.0)],
+        points(polyline(leaders[0]))
+    );
+    let mleader = polyline(leaders[1]);
+    assert!(!mleader.closed);
+    assert_eq!(
+        vec![(20.0, 30.0), (28.0, 20.0), (30.0, 20.0)],
+        points(mleader)
+    );
+    let note = drawing.texts.iter().find(|t| t.text == "R5\nTHRU").unwrap();
+    assert_eq!(Vec2::new(31.0, 21.0), note.at);
+    assert_eq!(Some(2.5), note.height);
+
+    let frame = drawing
+        .texts
+        .iter()
+        .find(|t| t.style.layer.as_deref() == Some("GDT"))
+        .unwrap();
+    assert!(frame.text.ends_with(" | 0.1 | A"), "{frame:?}");
+    assert_eq!(Vec2::new(60.0, 0.0), frame.at);
+    assert!((frame.rotation_deg - 90.0).abs() < 1e-9);
+}
+
+#[test]
+fn skipped_and_projected_entities_are_reported() {
+    let drawing = load("entities.dxf");
+    let log = &drawing.import_log;
+    let skipped = BTreeMap::from([("RAY".to_string(), 1), ("WIPEOUT".to_string(), 2)]);
+    assert_eq!(skipped, log.skipped);
+
+    // The 3D face and the sloped line; a line at a constant Z is flat.
+    let skew: Vec<u64> = on_layer(&drawing, "SKEW").iter().map(|e| e.id).collect();
+    assert_eq!(skew, log.projected);
+    assert_eq!(3, polyline(on_layer(&drawing, "SKEW")[0]).vertices.len());
+
+    let report = Analyzer::new(AnalysisConfig::default()).analyze("dxf", &drawing);
+    assert_eq!(skipped, report.stats.skipped_entities);
+    let warning = |code: &str| report.warnings.iter().find(|w| w.code == code).unwrap();
+    assert!(
+        warning("entities_skipped")
+            .message
+            .contains("1 RAY, 2 WIPEOUT"),
+        "{:?}",
+        report.warnings
+    );
+    assert_eq!(skew, warning("entities_projected").entity_ids);
+}
+
+#[test]
+fn stretched_circles_become_ellipses() {
+    let drawing = load("entities.dxf");
+    let stretched = on_layer(&drawing, "STRETCHED");
+    let ellipses: Vec<&Ellipse2D> = stretched
+        .iter()
+        .map(|e| match &e.primitive {
+            Primitive2D::Ellipse(el) => el,
+            other => panic!("expected an ellipse, got {other:?}"),
+        })
+        .collect();
+    let [circle, arc] = ellipses[..] else {
+        panic!("expected two ellipses, got {stretched:?}");
+    };
+
+    // Twice as wide as the block's circle, the same height.
+    assert!(circle.is_closed());
+    assert_eq!(Vec2::new(100.0, 0.0), circle.center);
+    assert!((circle.major_axis.length() - 4.0).abs() < 1e-9);
+    assert!((circle.ratio - 0.5).abs() < 1e-9);
+    let bbox = circle.bbox();
+    assert!((bbox.width() - 8.0).abs() < 1e-9 && (bbox.height() - 4.0).abs() < 1e-9);
+
+    // The quarter arc keeps its ends, stretched along x.
+    assert!((arc.sweep_deg() - 90.0).abs() < 1e-9);
+    assert!(arc.start_point().distance(Vec2::new(108.0, 0.0)) < 1e-9);
+    assert!(arc.end_point().distance(Vec2::new(100.0, 4.0)) < 1e-9);
+}
@@ -0,0 +1,368 @@
+use cadconvert_core::analysis::{AnalysisConfig, Analyzer};
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{
+    Arc2D, Bezier2D, Circle2D, DimensionDefinition, DimensionEntity, Drawing2D, Ellipse2D,
+    Entity2D, EntityKind, ImportLog, LineSeg2D, Nurbs2D, Polyline2D, PolylineVertex2D, Primitive2D,
+    Provenance, Style, TextAlign, TextEntity, Units,
+};
+use cadconvert_import_dxf::{
+    export_dxf, import_dxf, to_dxf, CLUSTERS_LAYER, CONFLICTS_LAYER, UNRESOLVED_DIMS_LAYER,
+    VIEWS_LAYER,
+};
+use std::path::PathBuf;
+
+fn style(layer: &str, linetype: &str, color_index: Option<i16>) -> Style {
+    Style {
+        layer: Some(layer.to_string()),
+        linetype: Some(linetype.to_string()),
+        color_index,
+        lineweight: None,
+    }
+}
+
+fn dim(id: u64, text: &str, measurement: Option<f64>, at: Option<Vec2>) -> DimensionEntity {
+    DimensionEntity {
+        id,
+        raw_type: Some(0),
+        text: Some(text.to_string()),
+        measurement,
+        at,
+        definition: None,
+        style: style("DIMS", "CONTINUOUS", None),
+        source: Provenance::default(),
+    }
+}
+
+fn sample() -> Drawing2D {
+    let entity = |id, kind, primitive, style| Entity2D {
+        id,
+        kind,
+        primitive,
+        style,
+        source: Provenance::default(),
+    };
+    Drawing2D {
+        units: Units::Millimeters,
+        entities: vec![
+            entity(
+                1,
+                EntityKind::Object,
+                Primitive2D::Line(LineSeg2D {
+                    a: Vec2::new(0.0, 0.0),
+                    b: Vec2::new(10.0, 0.0),
+                }),
+                style("OUTLINE", "CONTINUOUS", Some(1)),
+            ),
+            entity(
+                2,
+                EntityKind::Hidden,
+                Primitive2D::Circle(Circle2D {
+                    center: Vec2::new(5.0, 5.0),
+                    radius: 2.0,
+                }),
+                style("HIDDEN", "HIDDEN", None),
+            ),
+            entity(
+                3,
+                EntityKind::Center,
+                Primitive2D::Arc(Arc2D {
+                    center: Vec2::new(5.0, 5.0),
+                    radius: 4.0,
+                    start_angle_deg: 0.0,
+                    end_angle_deg: 90.0,
+                }),
+                style("AXES", "CENTER", Some(3)),
+            ),
+            entity(
+                4,
+                EntityKind::Object,
+                Primitive2D::Polyline(Polyline2D {
+                    vertices: vec![
+                        PolylineVertex2D {
+                            pos: Vec2::new(0.0, 10.0),
+                            bulge: 0.5,
+                        },
+                        PolylineVertex2D {
+                            pos: Vec2::new(10.0, 10.0),
+                            bulge: 0.0,
+                        },
+                        PolylineVertex2D {
+                            pos: Vec2::new(10.0, 20.0),
+                            bulge: 0.0,
+                        },
+                    ],
+                    closed: true,
+                }),
+                style("OUTLINE", "CONTINUOUS", Some(1)),
+            ),
+            entity(
+                5,
+                EntityKind::Object,
+                Primitive2D::CubicBezier(Bezier2D {
+                    p0: Vec2::new(0.0, 30.0),
+                    p1: Vec2::new(3.0, 33.0),
+                    p2: Vec2::new(7.0, 33.0),
+                    p3: Vec2::new(10.0, 30.0),
+                }),
+                style("OUTLINE", "CONTINUOUS", Some(1)),
+            ),
+            entity(
+                6,
+                EntityKind::Object,
+                Primitive2D::Ellipse(Ellipse2D {
+                    center: Vec2::new(20.0, 5.0),
+                    major_axis: Vec2::new(0.0, 4.0),
+                    ratio: 0.5,
+                    start_param_deg: 30.0,
+                    end_param_deg: 200.0,
+                }),
+                style("OUTLINE", "CONTINUOUS", Some(1)),
+            ),
+            // A quarter circle, exactly.
+            entity(
+                7,
+                EntityKind::Object,
+                Primitive2D::Nurbs(Nurbs2D {
+                    degree: 2,
+                    control_points: vec![
+                        Vec2::new(35.0, 0.0),
+                        Vec2::new(35.0, 5.0),
+                        Vec2::new(30.0, 5.0),
+                    ],
+                    weights: vec![1.0, std::f64::consts::FRAC_1_SQRT_2, 1.0],
+                    knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+                }),
+                style("OUTLINE", "CONTINUOUS", Some(1)),
+            ),
+        ],
+        dims: vec![
+            dim(10, "<>", Some(10.0), Some(Vec2::new(5.0, -3.0))),
+            dim(11, "12.5", Some(10.0), Some(Vec2::new(5.0, -6.0))),
+            dim(12, "R", None, None),
+        ],
+        texts: vec![
+            TextEntity {
+                id: 20,
+                text: "PART A".to_string(),
+                at: Vec2::new(0.0, -10.0),
+                height: Some(2.5),
+                rotation_deg: 30.0,
+                align: TextAlign::Center,
+                style: style("NOTES", "CONTINUOUS", Some(7)),
+                source: Provenance::default(),
+            },
+            TextEntity {
+                id: 21,
+                text: "SCALE 1:2\nSHEET 1".to_string(),
+                at: Vec2::new(20.0, -10.0),
+                height: Some(2.5),
+                rotation_deg: 90.0,
+                align: TextAlign::Right,
+                style: style("NOTES", "CONTINUOUS", Some(7)),
+                source: Provenance::default(),
+            },
+        ],
+        import_log: ImportLog::default(),
+    }
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("cadconvert-{}-{name}.dxf", std::process::id()))
+}
+
+#[test]
+fn exported_drawing_imports_back() {
+    let drawing = sample();
+    let path = temp_path("round-trip");
+    export_dxf(&drawing, None, &path).unwrap();
+    let back = import_dxf(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    let geometry = |d: &Drawing2D| {
+        d.entities
+            .iter()
+            .map(|e| (e.primitive.clone(), e.style.clone(), e.kind.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(geometry(&drawing)[..4], geometry(&back)[..4]);
+    // The Bézier comes back as the single span spline it is written as.
+    let Primitive2D::Nurbs(bezier) = &back.entities[4].primitive else {
+        panic!("{:?}", back.entities[4]);
+    };
+    assert_eq!(3, bezier.degree);
+    assert_eq!(Some(Vec2::new(5.0, 32.25)), bezier.point_at(0.5));
+    let (Primitive2D::Ellipse(a), Primitive2D::Ellipse(b)) =
+        (&drawing.entities[5].primitive, &back.entities[5].primitive)
+    else {
+        panic!("{:?}", back.entities[5]);
+    };
+    assert!(
+        (a.start_point().x - b.start_point().x).abs() < 1e-9,
+        "{b:?}"
+    );
+    assert!((a.end_point().y - b.end_point().y).abs() < 1e-9, "{b:?}");
+    assert!((a.sweep_deg() - b.sweep_deg()).abs() < 1e-9, "{b:?}");
+    // Weights are written to 12 digits, which keeps the arc a circle.
+    let Primitive2D::Nurbs(quarter) = &back.entities[6].primitive else {
+        panic!("{:?}", back.entities[6]);
+    };
+    let mid = quarter.point_at(0.3).unwrap();
+    assert!(((mid.x - 30.0).hypot(mid.y) - 5.0).abs() < 1e-9, "{mid:?}");
+
+    assert_eq!(2, back.texts.len());
+    for (a, b) in drawing.texts.iter().zip(&back.texts) {
+        assert_eq!(a.text, b.text);
+        assert_eq!(a.at, b.at);
+        assert_eq!(a.height, b.height);
+        assert!((a.rotation_deg - b.rotation_deg).abs() < 1e-9, "{b:?}");
+        assert_eq!(a.align, b.align);
+        assert_eq!(a.style, b.style);
+    }
+
+    assert_eq!(3, back.dims.len());
+    for (a, b) in drawing.dims.iter().zip(&back.dims) {
+        assert_eq!(a.text, b.text);
+        assert_eq!(a.raw_type, b.raw_type);
+        assert_eq!(a.style, b.style);
+        assert_eq!(a.measurement.unwrap_or(0.0), b.measurement.unwrap_or(0.0));
+    }
+    assert_eq!(drawing.dims[1].at, back.dims[1].at);
+}
+
+#[test]
+fn dimensions_keep_their_definition_points() {
+    let mut drawing = sample();
+    drawing.dims[0].definition = Some(DimensionDefinition {
+        points: vec![
+            Vec2::new(10.0, -3.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+        ],
+        rotation_deg: 0.0,
+    });
+    drawing.dims[1].raw_type = Some(1);
+    drawing.dims[1].definition = Some(DimensionDefinition {
+        points: vec![
+            Vec2::new(10.0, -6.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 10.0),
+        ],
+        rotation_deg: 45.0,
+    });
+
+    let out = to_dxf(&drawing, None);
+    let rotated = out
+        .entities()
+        .filter_map(|e| match &e.specific {
+            dxf::entities::EntityType::RotatedDimension(d) => Some(d),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(3, rotated.len());
+    let xy = |p: &dxf::Point| Vec2::new(p.x, p.y);
+    assert_eq!(
+        Vec2::new(10.0, -3.0),
+        xy(&rotated[0].dimension_base.definition_point_1)
+    );
+    assert_eq!(Vec2::new(0.0, 0.0), xy(&rotated[0].definition_point_2));
+    assert_eq!(Vec2::new(10.0, 0.0), xy(&rotated[0].definition_point_3));
+    assert_eq!(45.0, rotated[1].rotation_angle);
+    // Without definition points, everything sits on the text.
+    assert_eq!(
+        rotated[2].dimension_base.text_mid_point,
+        rotated[2].definition_point_2
+    );
+
+    let path = temp_path("definition-points");
+    export_dxf(&drawing, None, &path).unwrap();
+    let back = import_dxf(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(drawing.dims[0].definition, back.dims[0].definition);
+    assert_eq!(drawing.dims[1].definition, back.dims[1].definition);
+}
+
+#[test]
+fn mtext_angles_are_written_in_radians() {
+    let path = temp_path("mtext-angle");
+    export_dxf(&sample(), None, &path).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    let lines: Vec<&str> = written.lines().map(str::trim).collect();
+    let pairs: Vec<(&str, &str)> = lines.chunks(2).map(|p| (p[0], p[1])).collect();
+    let start = pairs.iter().position(|p| *p == ("0", "MTEXT")).unwrap();
+    let end = start + 1 + pairs[start + 1..].iter().position(|p| p.0 == "0").unwrap();
+    let value = |code: &str| -> f64 {
+        let (_, v) = pairs[start..end].iter().find(|p| p.0 == code).unwrap();
+        v.parse().unwrap()
+    };
+    assert!((value("50") - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    assert!(value("11").abs() < 1e-9 && (value("21") - 1.0).abs() < 1e-9);
+
+    // A note with only an angle, as older writers leave it.
+    let mut out = dxf::Drawing::new();
+    out.header.version = dxf::enums::AcadVersion::R2000;
+    out.add_entity(dxf::entities::Entity::new(
+        dxf::entities::EntityType::MText(dxf::entities::MText {
+            text: "A\\PB".to_string(),
+            rotation_angle: std::f64::consts::FRAC_PI_2,
+            ..Default::default()
+        }),
+    ));
+    let path = temp_path("mtext-angle-only");
+    out.save_file(&path).unwrap();
+    let back = import_dxf(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert!((back.texts[0].rotation_deg - 90.0).abs() < 1e-9, "{back:?}");
+}
+
+#[test]
+fn dashed_linetypes_get_patterns() {
+    let out = to_dxf(&sample(), None);
+    let pattern = |name: &str| {
+        out.line_types()
+            .find(|lt| lt.name == name)
+            .map(|lt| lt.dash_dot_space_lengths.clone())
+            .unwrap_or_default()
+    };
+    assert_eq!(vec![0.25, -0.125], pattern("HIDDEN"));
+    assert_eq!(4, pattern("CENTER").len());
+    assert!(pattern("CONTINUOUS").is_empty());
+}
+
+#[test]
+fn overlay_marks_clusters_views_and_dimension_problems() {
+    let drawing = sample();
+    let report = Analyzer::new(AnalysisConfig {
+        min_cluster_entities: 1,
+        ..AnalysisConfig::default()
+    })
+    .analyze("dxf", &drawing);
+    assert!(!report.view_clusters.is_empty());
+
+    let out = to_dxf(&drawing, Some(&report));
+    for layer in [
+        CLUSTERS_LAYER,
+        VIEWS_LAYER,
+        UNRESOLVED_DIMS_LAYER,
+        CONFLICTS_LAYER,
+    ] {
+        assert!(out.layers().any(|l| l.name == layer), "missing {layer}");
+    }
+    let on = |layer: &str| {
+        out.entities()
+            .filter(|e| e.common.layer == layer)
+            .collect::<Vec<_>>()
+    };
+    // A frame and a label per cluster.
+    assert_eq!(2 * report.view_clusters.len(), on(CLUSTERS_LAYER).len());
+    // Dimension 11 has a position, so it gets a circle as well as a label.
+    assert_eq!(2, on(CONFLICTS_LAYER).len());
+    // Dimension 12 has none and is only listed.
+    assert_eq!(1, on(UNRESOLVED_DIMS_LAYER).len());
+
+    let without = to_dxf(&drawing, None);
+    assert!(without
+        .entities()
+        .all(|e| !e.common.layer.starts_with("CADCONVERT_")));
+}
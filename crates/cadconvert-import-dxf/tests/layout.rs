@@ -0,0 +1,129 @@
+mod common;
+
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{Drawing2D, LineSeg2D, Primitive2D};
+use cadconvert_import_dxf::{import_dxf_with, DxfImportOptions, DxfSpace};
+use common::{fixture, on_layer};
+
+fn load(layout: Option<&str>) -> anyhow::Result<Drawing2D> {
+    let options = DxfImportOptions {
+        space: DxfSpace::Layout(layout.map(str::to_string)),
+    };
+    import_dxf_with(&fixture("layout.dxf"), &options)
+}
+
+fn line(a: (f64, f64), b: (f64, f64)) -> Primitive2D {
+    Primitive2D::Line(LineSeg2D {
+        a: Vec2::new(a.0, a.1),
+        b: Vec2::new(b.0, b.1),
+    })
+}
+
+#[test]
+fn model_space_lists_the_sheet_apart() {
+    let drawing = common::load("layout.dxf");
+    assert_eq!(4, on_layer(&drawing, "PART").len());
+    assert_eq!(5, drawing.entities.len());
+    assert_eq!(3, drawing.texts.len());
+
+    // The active sheet comes as it is drawn, listed as paper space.
+    let log = &drawing.import_log;
+    let [border] = on_layer(&drawing, "BORDER")[..] else {
+        panic!("expected one border line");
+    };
+    assert_eq!(line((0.0, 0.0), (420.0, 0.0)), border.primitive);
+    let title = drawing.texts.iter().find(|t| t.text == "TITLE").unwrap();
+    assert_eq!(vec![border.id, title.id], log.paper_space);
+    assert_eq!(None, log.layout);
+    assert!(
+        log.skipped.is_empty() && log.projected.is_empty(),
+        "{log:?}"
+    );
+
+    // The model tab is model space too.
+    let model = load(Some("model")).unwrap();
+    assert_eq!(drawing.entities, model.entities);
+    assert_eq!(None, model.import_log.layout);
+}
+
+#[test]
+fn active_layout_shows_model_space_through_its_viewport() {
+    let drawing = load(None).unwrap();
+    let log = &drawing.import_log;
+    assert_eq!(Some("Layout1"), log.layout.as_deref());
+
+    // Drawn on the sheet, at sheet coordinates.
+    let [border] = on_layer(&drawing, "BORDER")[..] else {
+        panic!("expected one border line");
+    };
+    assert_eq!(line((0.0, 0.0), (420.0, 0.0)), border.primitive);
+    let title = drawing.texts.iter().find(|t| t.text == "TITLE").unwrap();
+    assert_eq!(vec![border.id, title.id], log.paper_space);
+
+    // At half size around the window's middle, cut at its edges.
+    let part = on_layer(&drawing, "PART");
+    assert_eq!(line((75.0, 75.0), (125.0, 75.0)), part[0].primitive);
+    assert_eq!(line((50.0, 100.0), (150.0, 100.0)), part[1].primitive);
+    match &part[2].primitive {
+        Primitive2D::Circle(c) => {
+            assert_eq!(Vec2::new(100.0, 100.0), c.center);
+            assert_eq!(5.0, c.radius);
+        }
+        other => panic!("expected a circle, got {other:?}"),
+    }
+    // The big circle only shows across the window's corners.
+    let corners: Vec<_> = part[3..]
+        .iter()
+        .map(|e| match &e.primitive {
+            Primitive2D::Arc(a) => a,
+            other => panic!("expected an arc, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(4, corners.len());
+    for arc in corners {
+        assert!((arc.radius - 60.0).abs() < 1e-9);
+        assert!((arc.sweep_deg() - 22.885).abs() < 1e-3, "{arc:?}");
+        let middle =
+            arc.point_at(arc.start_angle_deg + arc.sweep_deg() / 2.0) - part[2].bbox().center();
+        assert!(middle.x.abs() < 50.0 && middle.y.abs() < 50.0, "{arc:?}");
+    }
+    assert_eq!(part[3].style, part[4].style);
+
+    let [inside] = &drawing
+        .texts
+        .iter()
+        .filter(|t| t.text != "TITLE")
+        .collect::<Vec<_>>()[..]
+    else {
+        panic!("expected the note outside the window to be dropped");
+    };
+    assert_eq!("INSIDE", inside.text);
+    assert_eq!(Vec2::new(95.0, 95.0), inside.at);
+    assert_eq!(Some(1.25), inside.height);
+}
+
+#[test]
+fn named_layout_with_a_twisted_viewport() {
+    let drawing = load(Some("detail")).unwrap();
+    assert_eq!(Some("Detail"), drawing.import_log.layout.as_deref());
+    let border = on_layer(&drawing, "BORDER");
+    assert_eq!(vec![border[0].id], drawing.import_log.paper_space);
+    assert_eq!(line((0.0, 0.0), (100.0, 0.0)), border[0].primitive);
+
+    // Turned a quarter about the target and cut to the window.
+    let [part] = on_layer(&drawing, "PART")[..] else {
+        panic!("expected one line through the window");
+    };
+    let Primitive2D::Line(l) = &part.primitive else {
+        panic!("expected a line, got {part:?}");
+    };
+    assert!(l.a.distance(Vec2::new(50.0, 40.0)) < 1e-9, "{l:?}");
+    assert!(l.b.distance(Vec2::new(50.0, 70.0)) < 1e-9, "{l:?}");
+    assert!(drawing.texts.is_empty());
+}
+
+#[test]
+fn unknown_layouts_list_the_known_ones() {
+    let err = load(Some("Sheet 9")).unwrap_err().to_string();
+    assert!(err.contains("Model, Layout1, Detail"), "{err}");
+}
@@ -1,12 +1,22 @@
 use anyhow::{Context, Result};
 use cadconvert_core::geom::Vec2;
 use cadconvert_core::model::{
-    Bezier2D, Circle2D, Drawing2D, Entity2D, EntityKind, LineSeg2D, Polyline2D, PolylineVertex2D,
-    Primitive2D, Style, TextEntity, Units,
+    Arc2D, Bezier2D, Circle2D, Drawing2D, Ellipse2D, Entity2D, EntityKind, ImportLog, LineSeg2D,
+    Polyline2D, PolylineVertex2D, Primitive2D, Provenance, Style, TextAlign, TextEntity, Units,
 };
 use roxmltree::{Document, Node};
+use simplecss::{AttributeOperator, DeclarationTokenizer, PseudoClass, StyleSheet};
 use std::path::Path;
 
+const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+const INKSCAPE_NS: &str = "http://www.inkscape.org/namespaces/inkscape";
+
+/// The initial `font-size`, which relative sizes at the top level refer to.
+const MEDIUM_FONT_SIZE: f64 = 16.0;
+
+/// Containers whose content is only drawn through `<use>`, if at all.
+const NOT_RENDERED: [&str; 6] = ["defs", "symbol", "clipPath", "mask", "marker", "pattern"];
+
 pub fn import_svg(path: &Path) -> Result<Drawing2D> {
     let xml = std::fs::read_to_string(path).with_context(|| format!("read SVG: {path:?}"))?;
     let doc = Document::parse(&xml).with_context(|| format!("parse SVG XML: {path:?}"))?;
@@ -18,106 +28,702 @@ pub fn import_svg(path: &Path) -> Result<Drawing2D> {
 
     let vb = parse_viewbox(svg.attribute("viewBox"));
     let height = vb.map(|v| v.3);
+    let viewport = match vb {
+        Some((_, _, w, h)) => (w, h),
+        None => (
+            svg.attribute("width").and_then(parse_len).unwrap_or(0.0),
+            svg.attribute("height").and_then(parse_len).unwrap_or(0.0),
+        ),
+    };
 
-    let mut next_id: u64 = 1;
-    let mut entities = Vec::new();
-    let mut texts = Vec::new();
-
-    walk(svg, Transform2D::identity(), height, &mut next_id, &mut entities, &mut texts);
+    let mut importer = SvgImporter::new(svg, height);
+    let scope = Scope {
+        tx: Transform2D::identity(),
+        viewport,
+        visible: true,
+        paint: Paint::default(),
+        font: Font::default(),
+        layer: None,
+    };
+    importer.walk(svg, scope, &mut Vec::new());
 
     Ok(Drawing2D {
         units: Units::Unknown,
-        entities,
+        entities: importer.entities,
         dims: Vec::new(),
-        texts,
+        texts: importer.texts,
+        import_log: ImportLog::default(),
     })
 }
 
-fn walk(
-    node: Node<'_, '_>,
-    parent_tx: Transform2D,
+/// What an element inherits from its ancestors.
+#[derive(Debug, Clone, Copy)]
+struct Scope<'a> {
+    tx: Transform2D,
+    /// Size of the nearest viewport, which percentages refer to.
+    viewport: (f64, f64),
+    visible: bool,
+    paint: Paint<'a>,
+    font: Font<'a>,
+    /// Name of the innermost enclosing layer group.
+    layer: Option<&'a str>,
+}
+
+/// Inherited stroke properties, as specified.
+#[derive(Debug, Clone, Copy, Default)]
+struct Paint<'a> {
+    stroke: Option<&'a str>,
+    stroke_width: Option<&'a str>,
+    stroke_dasharray: Option<&'a str>,
+}
+
+/// Inherited text properties; the size as computed, in user units.
+#[derive(Debug, Clone, Copy, Default)]
+struct Font<'a> {
+    size: Option<f64>,
+    anchor: Option<&'a str>,
+}
+
+/// Kind and style for the entities of one element.
+struct EntityStyle {
+    kind: EntityKind,
+    style: Style,
+    source: Provenance,
+}
+
+struct SvgImporter<'a, 'input> {
+    root: Node<'a, 'input>,
+    styles: StyleSheet<'a>,
     svg_height: Option<f64>,
-    next_id: &mut u64,
-    entities: &mut Vec<Entity2D>,
-    texts: &mut Vec<TextEntity>,
-) {
-    let node_tx = parse_transform(node.attribute("transform"));
-    let tx = parent_tx.mul(node_tx);
-
-    if node.is_element() {
+    next_id: u64,
+    entities: Vec<Entity2D>,
+    texts: Vec<TextEntity>,
+}
+
+impl<'a, 'input> SvgImporter<'a, 'input> {
+    fn new(root: Node<'a, 'input>, svg_height: Option<f64>) -> Self {
+        let mut styles = StyleSheet::new();
+        for node in root.document().descendants() {
+            if node.has_tag_name("style") {
+                for css in node.children().filter_map(|c| c.text()) {
+                    styles.parse_more(css);
+                }
+            }
+        }
+        Self {
+            root,
+            styles,
+            svg_height,
+            next_id: 1,
+            entities: Vec::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    /// Imports `node` and its subtree. `stack` holds the ids of the `<use>`
+    /// targets being expanded, to stop reference cycles.
+    fn walk(&mut self, node: Node<'a, 'input>, parent: Scope<'a>, stack: &mut Vec<String>) {
+        if !node.is_element() || self.property(node, "display") == Some("none") {
+            return;
+        }
         let tag = node.tag_name().name();
+        if NOT_RENDERED.contains(&tag) {
+            return;
+        }
+
+        let mut scope = parent;
+        scope.tx = scope.tx.mul(parse_transform(node.attribute("transform")));
+        match self.property(node, "visibility") {
+            Some("visible") => scope.visible = true,
+            Some("hidden" | "collapse") => scope.visible = false,
+            _ => {}
+        }
+        let paint = &mut scope.paint;
+        paint.stroke = self.property(node, "stroke").or(paint.stroke);
+        paint.stroke_width = self.property(node, "stroke-width").or(paint.stroke_width);
+        paint.stroke_dasharray = self
+            .property(node, "stroke-dasharray")
+            .or(paint.stroke_dasharray);
+        let font = &mut scope.font;
+        if let Some(size) = self.property(node, "font-size") {
+            let parent = font.size.unwrap_or(MEDIUM_FONT_SIZE);
+            font.size = font_size(size, parent).or(font.size);
+        }
+        font.anchor = self.property(node, "text-anchor").or(font.anchor);
+
+        match tag {
+            "g" => scope.layer = layer_name(node).or(scope.layer),
+            "svg" if node != self.root => scope = nested_viewport(node, scope),
+            "use" => return self.import_use(node, scope, stack),
+            _ if scope.visible => self.import_shape(node, scope),
+            _ => {}
+        }
+
+        for c in node.children() {
+            self.walk(c, scope, stack);
+        }
+    }
+
+    fn import_shape(&mut self, node: Node<'a, 'input>, scope: Scope<'a>) {
+        let tx = scope.tx;
+        let svg_height = self.svg_height;
+        let tag = node.tag_name().name();
+        let style = self.entity_style(node, &scope);
         match tag {
             "line" => {
-                if let Some(seg) = parse_line(node, tx, svg_height) {
-                    entities.push(Entity2D {
-                        id: alloc_id(next_id),
-                        kind: EntityKind::Unknown,
-                        primitive: Primitive2D::Line(seg),
-                        style: parse_style(node),
-                    });
-                }
+                let seg = parse_line(node, tx, scope.viewport, svg_height);
+                self.push(Primitive2D::Line(seg), &style);
             }
-            "circle" => {
-                if let Some(circle) = parse_circle(node, tx, svg_height) {
-                    entities.push(Entity2D {
-                        id: alloc_id(next_id),
-                        kind: EntityKind::Unknown,
-                        primitive: Primitive2D::Circle(circle),
-                        style: parse_style(node),
-                    });
+            "circle" | "ellipse" => {
+                let (vw, vh) = scope.viewport;
+                let cx = length(node, "cx", vw).unwrap_or(0.0);
+                let cy = length(node, "cy", vh).unwrap_or(0.0);
+                let (rx, ry) = if tag == "circle" {
+                    let r = length(node, "r", vw.hypot(vh) / 2f64.sqrt());
+                    (r, r)
+                } else {
+                    auto_radii(length(node, "rx", vw), length(node, "ry", vh))
+                };
+                if let (Some(rx), Some(ry)) = (rx, ry) {
+                    let center = Vec2::new(cx, cy);
+                    self.import_ellipse(center, rx, ry, tx, &style);
                 }
             }
+            "rect" => self.import_rect(node, scope, &style),
             "polyline" | "polygon" => {
                 if let Some(poly) = parse_polyline(node, tx, svg_height) {
                     let closed = tag == "polygon";
-                    entities.push(Entity2D {
-                        id: alloc_id(next_id),
-                        kind: EntityKind::Unknown,
-                        primitive: Primitive2D::Polyline(Polyline2D {
-                            vertices: poly,
-                            closed,
-                        }),
-                        style: parse_style(node),
+                    let primitive = Primitive2D::Polyline(Polyline2D {
+                        vertices: poly,
+                        closed,
                     });
+                    self.push(primitive, &style);
                 }
             }
             "path" => {
                 if let Some(d) = node.attribute("d") {
-                    parse_path(d, tx, svg_height, next_id, entities, node);
+                    self.import_path(d, tx, &style);
                 }
             }
-            "text" => {
-                let value = node.text().unwrap_or("").trim().to_string();
-                if !value.is_empty() {
-                    if let Some(at) = parse_text_pos(node, tx, svg_height) {
-                        texts.push(TextEntity {
-                            id: alloc_id(next_id),
-                            text: value,
-                            at,
-                            height: None,
-                            style: parse_style(node),
-                        });
-                    }
+            "text" => self.import_text(node, scope, style),
+            _ => {}
+        }
+    }
+
+    /// A `<text>` with its `<tspan>`s, each positioned one starting a new
+    /// line. Placed at the text's own position, else its first line's.
+    fn import_text(&mut self, node: Node<'a, 'input>, scope: Scope<'a>, style: EntityStyle) {
+        let (vw, vh) = scope.viewport;
+        let position = |n: Node<'_, '_>| {
+            let (x, y) = (length(n, "x", vw), length(n, "y", vh));
+            (x.is_some() || y.is_some()).then(|| Vec2::new(x.unwrap_or(0.0), y.unwrap_or(0.0)))
+        };
+        let mut start = position(node);
+        let mut lines = vec![String::new()];
+        for n in node.descendants().skip(1) {
+            let mut within = n.ancestors().take_while(|a| *a != node);
+            if within.any(|a| self.property(a, "display") == Some("none")) {
+                continue;
+            }
+            if let Some(text) = n.text().filter(|_| n.is_text()) {
+                lines.last_mut().unwrap().push_str(text);
+            } else if let Some(p) = position(n).filter(|_| n.has_tag_name("tspan")) {
+                start = start.or(Some(p));
+                if !lines.last().unwrap().trim().is_empty() {
+                    lines.push(String::new());
                 }
             }
-            _ => {}
+        }
+        // Whitespace collapses as with the default `xml:space`.
+        let lines: Vec<String> = lines
+            .iter()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|l| !l.is_empty())
+            .collect();
+        if lines.is_empty() {
+            return;
+        }
+
+        let tx = scope.tx;
+        let dir = self.to_cad_vector(tx, Vec2::new(1.0, 0.0));
+        let id = self.next_id();
+        self.texts.push(TextEntity {
+            id,
+            text: lines.join("\n"),
+            at: self.to_cad(tx, start.unwrap_or(Vec2::new(0.0, 0.0))),
+            height: scope.font.size.map(|h| h * tx.scale()),
+            // Adding zero turns the -0 of a flipped baseline into 0.
+            rotation_deg: dir.y.atan2(dir.x).to_degrees() + 0.0,
+            align: match scope.font.anchor {
+                Some("middle") => TextAlign::Center,
+                Some("end") => TextAlign::Right,
+                _ => TextAlign::Left,
+            },
+            style: style.style,
+            source: style.source,
+        });
+    }
+
+    /// Draws the element a `<use>` points at, moved by its `x`/`y` and, for a
+    /// `<symbol>` with a `viewBox`, fitted into its `width`/`height`.
+    fn import_use(&mut self, node: Node<'a, 'input>, scope: Scope<'a>, stack: &mut Vec<String>) {
+        let Some(id) = node
+            .attribute("href")
+            .or_else(|| node.attribute((XLINK_NS, "href")))
+            .and_then(|href| href.strip_prefix('#'))
+        else {
+            return;
+        };
+        if stack.len() > 8 || stack.iter().any(|s| s == id) {
+            return;
+        }
+        let Some(target) = self
+            .root
+            .document()
+            .descendants()
+            .find(|n| n.attribute("id") == Some(id))
+        else {
+            return;
+        };
+
+        let (vw, vh) = scope.viewport;
+        let x = length(node, "x", vw).unwrap_or(0.0);
+        let y = length(node, "y", vh).unwrap_or(0.0);
+        let mut inner = scope;
+        inner.tx = scope.tx.mul(Transform2D::translate(x, y));
+
+        stack.push(id.to_string());
+        if target.has_tag_name("symbol") {
+            if let Some(vb) = parse_viewbox(target.attribute("viewBox")) {
+                let width = length(node, "width", vw)
+                    .or_else(|| length(target, "width", vw))
+                    .unwrap_or(vw);
+                let height = length(node, "height", vh)
+                    .or_else(|| length(target, "height", vh))
+                    .unwrap_or(vh);
+                let aspect = target.attribute("preserveAspectRatio");
+                inner.tx = inner.tx.mul(viewbox_transform(vb, width, height, aspect));
+                inner.viewport = (vb.2, vb.3);
+            }
+            for c in target.children() {
+                self.walk(c, inner, stack);
+            }
+        } else {
+            self.walk(target, inner, stack);
+        }
+        stack.pop();
+    }
+
+    fn import_rect(&mut self, node: Node<'a, 'input>, scope: Scope<'a>, style: &EntityStyle) {
+        let (vw, vh) = scope.viewport;
+        let x = length(node, "x", vw).unwrap_or(0.0);
+        let y = length(node, "y", vh).unwrap_or(0.0);
+        let (Some(w), Some(h)) = (length(node, "width", vw), length(node, "height", vh)) else {
+            return;
+        };
+        if w <= 0.0 || h <= 0.0 {
+            return;
+        }
+        let (rx, ry) = auto_radii(length(node, "rx", vw), length(node, "ry", vh));
+        let rx = rx.unwrap_or(0.0).clamp(0.0, w / 2.0);
+        let ry = ry.unwrap_or(0.0).clamp(0.0, h / 2.0);
+        let tx = scope.tx;
+
+        if rx == 0.0 || ry == 0.0 {
+            let vertices = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)]
+                .into_iter()
+                .map(|(px, py)| PolylineVertex2D {
+                    pos: self.to_cad(tx, Vec2::new(px, py)),
+                    bulge: 0.0,
+                })
+                .collect();
+            let primitive = Primitive2D::Polyline(Polyline2D {
+                vertices,
+                closed: true,
+            });
+            return self.push(primitive, style);
+        }
+
+        // Clockwise on screen from the start of the top edge; each side is
+        // followed by the corner after it.
+        let p = Vec2::new;
+        let sides = [
+            (p(x + rx, y), p(x + w - rx, y), p(x + w, y + ry)),
+            (p(x + w, y + ry), p(x + w, y + h - ry), p(x + w - rx, y + h)),
+            (p(x + w - rx, y + h), p(x + rx, y + h), p(x, y + h - ry)),
+            (p(x, y + h - ry), p(x, y + ry), p(x + rx, y)),
+        ];
+        for (a, b, c) in sides {
+            if a != b {
+                self.push_line(tx, a, b, style);
+            }
+            let corner = SvgArc {
+                from: b,
+                to: c,
+                rx,
+                ry,
+                rotation_deg: 0.0,
+                large_arc: false,
+                sweep: true,
+            };
+            self.push_arc(tx, &corner, style);
+        }
+    }
+
+    /// A circle when `tx` keeps the ellipse circular, otherwise an ellipse.
+    fn import_ellipse(
+        &mut self,
+        center: Vec2,
+        rx: f64,
+        ry: f64,
+        tx: Transform2D,
+        style: &EntityStyle,
+    ) {
+        if !(rx > 0.0 && ry > 0.0) {
+            return;
+        }
+        let u = self.to_cad_vector(tx, Vec2::new(rx, 0.0));
+        let v = self.to_cad_vector(tx, Vec2::new(0.0, ry));
+        if let Some(radius) = circle_radius(u, v) {
+            let center = self.to_cad(tx, center);
+            return self.push(Primitive2D::Circle(Circle2D { center, radius }), style);
+        }
+        let center = self.to_cad(tx, center);
+        if let Some(ellipse) = Ellipse2D::from_conjugate(center, u, v, 0.0, 360.0) {
+            self.push(Primitive2D::Ellipse(ellipse), style);
+        }
+    }
+
+    fn import_path(&mut self, d: &str, tx: Transform2D, style: &EntityStyle) {
+        let mut cur = Vec2::new(0.0, 0.0);
+        let mut start = Vec2::new(0.0, 0.0);
+        // Second control point of the previous C/S or control point of the
+        // previous Q/T, for the reflection in a following S or T.
+        let mut last_cubic: Option<Vec2> = None;
+        let mut last_quad: Option<Vec2> = None;
+
+        for seg in svgtypes::PathParser::from(d) {
+            let seg = match seg {
+                Ok(s) => s,
+                Err(_) => return,
+            };
+            let base = cur;
+            let at = move |abs: bool, x: f64, y: f64| {
+                if abs {
+                    Vec2::new(x, y)
+                } else {
+                    Vec2::new(base.x + x, base.y + y)
+                }
+            };
+            let (mut cubic_ctrl, mut quad_ctrl) = (None, None);
+            use svgtypes::PathSegment::*;
+            match seg {
+                MoveTo { abs, x, y } => {
+                    cur = at(abs, x, y);
+                    start = cur;
+                }
+                LineTo { abs, x, y } => {
+                    let next = at(abs, x, y);
+                    self.push_line(tx, cur, next, style);
+                    cur = next;
+                }
+                HorizontalLineTo { abs, x } => {
+                    let next = Vec2::new(if abs { x } else { cur.x + x }, cur.y);
+                    self.push_line(tx, cur, next, style);
+                    cur = next;
+                }
+                VerticalLineTo { abs, y } => {
+                    let next = Vec2::new(cur.x, if abs { y } else { cur.y + y });
+                    self.push_line(tx, cur, next, style);
+                    cur = next;
+                }
+                CurveTo {
+                    abs,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                } => {
+                    let (p1, p2, p3) = (at(abs, x1, y1), at(abs, x2, y2), at(abs, x, y));
+                    self.push_cubic(tx, [cur, p1, p2, p3], style);
+                    cubic_ctrl = Some(p2);
+                    cur = p3;
+                }
+                SmoothCurveTo { abs, x2, y2, x, y } => {
+                    let p1 = last_cubic.map_or(cur, |c| reflect(c, cur));
+                    let (p2, p3) = (at(abs, x2, y2), at(abs, x, y));
+                    self.push_cubic(tx, [cur, p1, p2, p3], style);
+                    cubic_ctrl = Some(p2);
+                    cur = p3;
+                }
+                Quadratic { abs, x1, y1, x, y } => {
+                    let (q, p) = (at(abs, x1, y1), at(abs, x, y));
+                    let (p1, p2) = quad_to_cubic(cur, q, p);
+                    self.push_cubic(tx, [cur, p1, p2, p], style);
+                    quad_ctrl = Some(q);
+                    cur = p;
+                }
+                SmoothQuadratic { abs, x, y } => {
+                    let q = last_quad.map_or(cur, |c| reflect(c, cur));
+                    let p = at(abs, x, y);
+                    let (p1, p2) = quad_to_cubic(cur, q, p);
+                    self.push_cubic(tx, [cur, p1, p2, p], style);
+                    quad_ctrl = Some(q);
+                    cur = p;
+                }
+                EllipticalArc {
+                    abs,
+                    rx,
+                    ry,
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    x,
+                    y,
+                } => {
+                    let next = at(abs, x, y);
+                    let arc = SvgArc {
+                        from: cur,
+                        to: next,
+                        rx: rx.abs(),
+                        ry: ry.abs(),
+                        rotation_deg: x_axis_rotation,
+                        large_arc,
+                        sweep,
+                    };
+                    self.push_arc(tx, &arc, style);
+                    cur = next;
+                }
+                ClosePath { .. } => {
+                    self.push_line(tx, cur, start, style);
+                    cur = start;
+                }
+            }
+            last_cubic = cubic_ctrl;
+            last_quad = quad_ctrl;
+        }
+    }
+
+    fn push(&mut self, primitive: Primitive2D, style: &EntityStyle) {
+        let id = self.next_id();
+        self.entities.push(Entity2D {
+            id,
+            kind: style.kind.clone(),
+            primitive,
+            style: style.style.clone(),
+            source: style.source.clone(),
+        });
+    }
+
+    fn push_line(&mut self, tx: Transform2D, a: Vec2, b: Vec2, style: &EntityStyle) {
+        let seg = LineSeg2D {
+            a: self.to_cad(tx, a),
+            b: self.to_cad(tx, b),
+        };
+        self.push(Primitive2D::Line(seg), style);
+    }
+
+    fn push_cubic(&mut self, tx: Transform2D, [p0, p1, p2, p3]: [Vec2; 4], style: &EntityStyle) {
+        let bezier = Bezier2D {
+            p0: self.to_cad(tx, p0),
+            p1: self.to_cad(tx, p1),
+            p2: self.to_cad(tx, p2),
+            p3: self.to_cad(tx, p3),
+        };
+        self.push(Primitive2D::CubicBezier(bezier), style);
+    }
+
+    /// An arc when `tx` keeps it circular, otherwise an elliptical arc; a
+    /// line or nothing for degenerate arcs.
+    fn push_arc(&mut self, tx: Transform2D, arc: &SvgArc, style: &EntityStyle) {
+        let Some(ellipse) = arc.center_form() else {
+            if arc.from != arc.to {
+                self.push_line(tx, arc.from, arc.to, style);
+            }
+            return;
+        };
+        let u = self.to_cad_vector(tx, ellipse.axis_offset(ellipse.rx, 0.0));
+        let v = self.to_cad_vector(tx, ellipse.axis_offset(0.0, ellipse.ry));
+        let center = self.to_cad(tx, ellipse.center);
+        let Some(radius) = circle_radius(u, v) else {
+            let (start, sweep) = (ellipse.theta0.to_degrees(), ellipse.delta.to_degrees());
+            if let Some(e) = Ellipse2D::from_conjugate(center, u, v, start, sweep) {
+                self.push(Primitive2D::Ellipse(e), style);
+            }
+            return;
+        };
+
+        let angle = |p: Vec2| (p - center).angle().to_degrees().rem_euclid(360.0);
+        // Increasing parameter runs counterclockwise when u turns left into v.
+        let ccw = u.cross(v) * ellipse.delta > 0.0;
+        let (from, to) = if ccw {
+            (arc.from, arc.to)
+        } else {
+            (arc.to, arc.from)
+        };
+        let arc = Arc2D {
+            center,
+            radius,
+            start_angle_deg: angle(self.to_cad(tx, from)),
+            end_angle_deg: angle(self.to_cad(tx, to)),
+        };
+        self.push(Primitive2D::Arc(arc), style);
+    }
+
+    /// The value of a property as specified on `node`: from its `style`
+    /// attribute, else the most specific style sheet rule, else its
+    /// presentation attribute. `inherit` counts as unspecified.
+    fn property(&self, node: Node<'a, 'input>, name: &str) -> Option<&'a str> {
+        let inline = || {
+            let style = node.attribute("style")?;
+            DeclarationTokenizer::from(style)
+                .filter(|d| d.name == name)
+                .last()
+                .map(|d| d.value)
+        };
+        // Rules are sorted by specificity, later rules last among equals.
+        let sheet = || {
+            self.styles.rules.iter().rev().find_map(|rule| {
+                let decl = rule.declarations.iter().rev().find(|d| d.name == name)?;
+                rule.selector
+                    .matches(&CssElement(node))
+                    .then_some(decl.value)
+            })
+        };
+        inline()
+            .or_else(sheet)
+            .or_else(|| node.attribute(name))
+            .map(str::trim)
+            .filter(|v| *v != "inherit")
+    }
+
+    /// Layer from the enclosing layer group; linetype, kind and lineweight from
+    /// the stroke it inherits. The element id is kept as the handle.
+    fn entity_style(&self, node: Node<'a, 'input>, scope: &Scope<'a>) -> EntityStyle {
+        let paint = scope.paint;
+        let dasharray = paint.stroke_dasharray.filter(|d| *d != "none");
+        let linetype = dasharray.and_then(classify_dasharray);
+        let stroked = paint.stroke.is_some_and(|s| s != "none");
+        // A solid stroke on a named layer is left for the layer name to
+        // classify when the drawing is normalized.
+        let kind = match &linetype {
+            Some((_, kind)) => kind.clone(),
+            None if stroked && dasharray.is_none() && scope.layer.is_none() => EntityKind::Object,
+            None => EntityKind::Unknown,
+        };
+        let (vw, vh) = scope.viewport;
+        let lineweight = paint
+            .stroke_width
+            .and_then(|w| parse_length(w, vw.hypot(vh) / 2f64.sqrt()))
+            .filter(|w| *w >= 0.0)
+            .map(|w| w * scope.tx.scale());
+        EntityStyle {
+            kind,
+            style: Style {
+                layer: scope.layer.map(|s| s.to_string()),
+                linetype: linetype
+                    .map(|(name, _)| name)
+                    .or(dasharray)
+                    .map(|s| s.to_string()),
+                color_index: None,
+                lineweight,
+            },
+            source: Provenance {
+                handle: node.attribute("id").map(|s| s.to_string()),
+                ..Provenance::default()
+            },
+        }
+    }
+
+    fn to_cad(&self, tx: Transform2D, p: Vec2) -> Vec2 {
+        flip_y(tx.apply_point(p), self.svg_height)
+    }
+
+    fn to_cad_vector(&self, tx: Transform2D, v: Vec2) -> Vec2 {
+        let w = tx.apply_vector(v);
+        if self.svg_height.is_some() {
+            Vec2::new(w.x, -w.y)
+        } else {
+            w
         }
     }
 
-    for c in node.children() {
-        walk(c, tx, svg_height, next_id, entities, texts);
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
     }
 }
 
-fn alloc_id(next_id: &mut u64) -> u64 {
-    let id = *next_id;
-    *next_id += 1;
-    id
+/// The scope inside a nested `<svg>`: moved to its `x`/`y` and, with a
+/// `viewBox`, fitted into its `width`/`height`.
+fn nested_viewport<'a>(node: Node<'_, '_>, mut scope: Scope<'a>) -> Scope<'a> {
+    let (vw, vh) = scope.viewport;
+    let x = length(node, "x", vw).unwrap_or(0.0);
+    let y = length(node, "y", vh).unwrap_or(0.0);
+    let width = length(node, "width", vw).unwrap_or(vw);
+    let height = length(node, "height", vh).unwrap_or(vh);
+    scope.tx = scope.tx.mul(Transform2D::translate(x, y));
+    match parse_viewbox(node.attribute("viewBox")) {
+        Some(vb) => {
+            let aspect = node.attribute("preserveAspectRatio");
+            scope.tx = scope.tx.mul(viewbox_transform(vb, width, height, aspect));
+            scope.viewport = (vb.2, vb.3);
+        }
+        None => scope.viewport = (width, height),
+    }
+    scope
+}
+
+/// Maps `viewBox` onto a `width` by `height` viewport per
+/// `preserveAspectRatio` (default `xMidYMid meet`).
+fn viewbox_transform(
+    (vx, vy, vw, vh): (f64, f64, f64, f64),
+    width: f64,
+    height: f64,
+    aspect: Option<&str>,
+) -> Transform2D {
+    if !(vw > 0.0 && vh > 0.0) {
+        return Transform2D::identity();
+    }
+    use svgtypes::Align;
+    let aspect = aspect
+        .and_then(|s| s.parse::<svgtypes::AspectRatio>().ok())
+        .unwrap_or_default();
+    let (mut sx, mut sy) = (width / vw, height / vh);
+    if aspect.align != Align::None {
+        let s = if aspect.slice { sx.max(sy) } else { sx.min(sy) };
+        (sx, sy) = (s, s);
+    }
+    let (fx, fy) = match aspect.align {
+        Align::None | Align::XMinYMin => (0.0, 0.0),
+        Align::XMidYMin => (0.5, 0.0),
+        Align::XMaxYMin => (1.0, 0.0),
+        Align::XMinYMid => (0.0, 0.5),
+        Align::XMidYMid => (0.5, 0.5),
+        Align::XMaxYMid => (1.0, 0.5),
+        Align::XMinYMax => (0.0, 1.0),
+        Align::XMidYMax => (0.5, 1.0),
+        Align::XMaxYMax => (1.0, 1.0),
+    };
+    Transform2D {
+        a: sx,
+        b: 0.0,
+        c: 0.0,
+        d: sy,
+        e: (width - vw * sx) * fx - vx * sx,
+        f: (height - vh * sy) * fy - vy * sy,
+    }
 }
 
 fn parse_viewbox(viewbox: Option<&str>) -> Option<(f64, f64, f64, f64)> {
     let vb = viewbox?;
-    let parts: Vec<_> = vb.split_whitespace().collect();
+    let parts: Vec<_> = vb
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|p| !p.is_empty())
+        .collect();
     if parts.len() != 4 {
         return None;
     }
@@ -128,53 +734,135 @@ fn parse_viewbox(viewbox: Option<&str>) -> Option<(f64, f64, f64, f64)> {
     Some((a, b, c, d))
 }
 
-fn parse_style(node: Node<'_, '_>) -> Style {
-    // Minimal, deterministic: preserve layer-ish metadata when available.
-    // Real classification happens later based on dash patterns / stroke etc.
-    let layer = node.attribute("id").map(|s| s.to_string());
-    let linetype = node
-        .attribute("stroke-dasharray")
-        .or_else(|| node.attribute("style").and_then(find_dasharray_in_style))
-        .map(|s| s.to_string());
-    Style {
-        layer,
-        linetype,
-        color_index: None,
+/// The layer a `<g>` stands for: an Inkscape layer by its label, or a group
+/// named like the layers CAD and illustration exporters write (`Layer_HIDDEN`,
+/// or a `data-name` for names that are not valid ids).
+fn layer_name<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    let id = node.attribute("id");
+    if node.attribute((INKSCAPE_NS, "groupmode")) == Some("layer") {
+        return node.attribute((INKSCAPE_NS, "label")).or(id);
     }
+    let layer_id = id.filter(|id| {
+        id.get(..5).is_some_and(|p| p.eq_ignore_ascii_case("layer"))
+            && matches!(id.get(5..6), Some("_" | "-"))
+    });
+    node.attribute("data-name").or(layer_id)
 }
 
-fn find_dasharray_in_style(style: &str) -> Option<&str> {
-    // style="...;stroke-dasharray: 5, 2;..."
-    for part in style.split(';') {
-        let part = part.trim();
-        if let Some(rest) = part.strip_prefix("stroke-dasharray:") {
-            return Some(rest.trim());
-        }
+/// A node as simplecss sees it, for selector matching.
+#[derive(Clone, Copy)]
+struct CssElement<'a, 'input>(Node<'a, 'input>);
+
+impl simplecss::Element for CssElement<'_, '_> {
+    fn parent_element(&self) -> Option<Self> {
+        self.0.parent_element().map(CssElement)
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.0.prev_sibling_element().map(CssElement)
+    }
+
+    fn has_local_name(&self, name: &str) -> bool {
+        self.0.tag_name().name() == name
+    }
+
+    fn attribute_matches(&self, local_name: &str, operator: AttributeOperator<'_>) -> bool {
+        self.0
+            .attribute(local_name)
+            .is_some_and(|v| operator.matches(v))
+    }
+
+    fn pseudo_class_matches(&self, class: PseudoClass<'_>) -> bool {
+        class == PseudoClass::FirstChild && self.0.prev_sibling_element().is_none()
     }
-    None
 }
 
-fn parse_line(node: Node<'_, '_>, tx: Transform2D, svg_height: Option<f64>) -> Option<LineSeg2D> {
-    let x1 = parse_len(node.attribute("x1")?)?;
-    let y1 = parse_len(node.attribute("y1")?)?;
-    let x2 = parse_len(node.attribute("x2")?)?;
-    let y2 = parse_len(node.attribute("y2")?)?;
-    let a = flip_y(tx.apply_point(Vec2::new(x1, y1)), svg_height);
-    let b = flip_y(tx.apply_point(Vec2::new(x2, y2)), svg_height);
-    Some(LineSeg2D { a, b })
+/// Names a `stroke-dasharray` after the drafting linetype its dash and gap
+/// ratios match: dashes of one length (hidden), long-short (center) or
+/// long-short-short (phantom). Dots and sparse patterns match none.
+fn classify_dasharray(value: &str) -> Option<(&'static str, EntityKind)> {
+    let mut pattern = value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|p| !p.is_empty())
+        .map(|p| parse_len(p).filter(|v| *v >= 0.0))
+        .collect::<Option<Vec<f64>>>()?;
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_within(..);
+    }
+    // Repeats of a shorter pattern, like "6 4 6 4", count once.
+    let longest = pattern.iter().copied().fold(0.0, f64::max);
+    let same = |a: f64, b: f64| (a - b).abs() <= 0.05 * longest;
+    let period = (2..=pattern.len())
+        .step_by(2)
+        .find(|p| {
+            pattern.len() % p == 0
+                && pattern
+                    .iter()
+                    .enumerate()
+                    .all(|(i, v)| same(*v, pattern[i % p]))
+        })
+        .unwrap_or(pattern.len());
+    pattern.truncate(period);
+
+    let dashes: Vec<f64> = pattern.iter().copied().step_by(2).collect();
+    let gaps = pattern.iter().copied().skip(1).step_by(2);
+    let long_dash = dashes.iter().copied().fold(0.0, f64::max);
+    if long_dash <= 0.0 || gaps.fold(0.0, f64::max) > long_dash {
+        return None;
+    }
+    let long = dashes.iter().filter(|d| **d >= long_dash / 2.0).count();
+    let short = dashes.len() - long;
+    match (long, short) {
+        (_, 0) => Some(("HIDDEN", EntityKind::Hidden)),
+        (1, 1) => Some(("CENTER", EntityKind::Center)),
+        (1, 2) => Some(("PHANTOM", EntityKind::Phantom)),
+        _ => None,
+    }
+}
+
+/// A length attribute, with percentages taken of `reference`.
+fn length(node: Node<'_, '_>, name: &str, reference: f64) -> Option<f64> {
+    parse_length(node.attribute(name)?, reference)
+}
+
+fn parse_length(value: &str, reference: f64) -> Option<f64> {
+    let value = value.trim();
+    match value.strip_suffix('%') {
+        Some(percent) => Some(percent.trim().parse::<f64>().ok()? / 100.0 * reference),
+        None => parse_len(value),
+    }
+}
+
+/// A `font-size` value, with `em` and percentages taken of `parent`.
+fn font_size(value: &str, parent: f64) -> Option<f64> {
+    let size = match value.strip_suffix("em") {
+        Some(em) if !em.ends_with('r') => parse_len(em)? * parent,
+        _ => parse_length(value, parent)?,
+    };
+    (size > 0.0).then_some(size)
 }
 
-fn parse_circle(
+/// `rx`/`ry` of an ellipse or rounded rect, where a missing one copies the
+/// other.
+fn auto_radii(rx: Option<f64>, ry: Option<f64>) -> (Option<f64>, Option<f64>) {
+    (rx.or(ry), ry.or(rx))
+}
+
+/// A missing coordinate is 0, as for a circle's center.
+fn parse_line(
     node: Node<'_, '_>,
     tx: Transform2D,
+    viewport: (f64, f64),
     svg_height: Option<f64>,
-) -> Option<Circle2D> {
-    let cx = parse_len(node.attribute("cx")?)?;
-    let cy = parse_len(node.attribute("cy")?)?;
-    let r = parse_len(node.attribute("r")?)?;
-    let c = flip_y(tx.apply_point(Vec2::new(cx, cy)), svg_height);
-    // Note: transform may include scaling; we ignore non-uniform scaling for now.
-    Some(Circle2D { center: c, radius: r })
+) -> LineSeg2D {
+    let (vw, vh) = viewport;
+    let coord = |name, reference| length(node, name, reference).unwrap_or(0.0);
+    let a = Vec2::new(coord("x1", vw), coord("y1", vh));
+    let b = Vec2::new(coord("x2", vw), coord("y2", vh));
+    LineSeg2D {
+        a: flip_y(tx.apply_point(a), svg_height),
+        b: flip_y(tx.apply_point(b), svg_height),
+    }
 }
 
 fn parse_polyline(
@@ -197,109 +885,106 @@ fn parse_polyline(
     if out.len() >= 2 { Some(out) } else { None }
 }
 
-fn parse_text_pos(node: Node<'_, '_>, tx: Transform2D, svg_height: Option<f64>) -> Option<Vec2> {
-    let x = parse_len(node.attribute("x")?)?;
-    let y = parse_len(node.attribute("y")?)?;
-    Some(flip_y(tx.apply_point(Vec2::new(x, y)), svg_height))
+/// Radius of the circle with perpendicular radius vectors `u` and `v`, if they
+/// are that.
+fn circle_radius(u: Vec2, v: Vec2) -> Option<f64> {
+    let (lu, lv) = (u.length(), v.length());
+    if (lu - lv).abs() > 1e-9 * lu.max(lv) || u.dot(v).abs() > 1e-9 * lu * lv {
+        return None;
+    }
+    Some(lu)
 }
 
-fn parse_path(
-    d: &str,
-    tx: Transform2D,
-    svg_height: Option<f64>,
-    next_id: &mut u64,
-    entities: &mut Vec<Entity2D>,
-    node: Node<'_, '_>,
-) {
-    let style = parse_style(node);
-    let mut cur = Vec2::new(0.0, 0.0);
-    let mut start = Vec2::new(0.0, 0.0);
-
-    let mut parser = svgtypes::PathParser::from(d);
-    while let Some(seg) = parser.next() {
-        let seg = match seg {
-            Ok(s) => s,
-            Err(_) => return,
-        };
-        use svgtypes::PathSegment::*;
-        match seg {
-            MoveTo { abs, x, y } => {
-                cur = if abs {
-                    Vec2::new(x, y)
-                } else {
-                    Vec2::new(cur.x + x, cur.y + y)
-                };
-                start = cur;
-            }
-            LineTo { abs, x, y } => {
-                let next = if abs {
-                    Vec2::new(x, y)
-                } else {
-                    Vec2::new(cur.x + x, cur.y + y)
-                };
-                let a = flip_y(tx.apply_point(cur), svg_height);
-                let b = flip_y(tx.apply_point(next), svg_height);
-                entities.push(Entity2D {
-                    id: alloc_id(next_id),
-                    kind: EntityKind::Unknown,
-                    primitive: Primitive2D::Line(LineSeg2D { a, b }),
-                    style: style.clone(),
-                });
-                cur = next;
-            }
-            CurveTo {
-                abs,
-                x1,
-                y1,
-                x2,
-                y2,
-                x,
-                y,
-            } => {
-                let p1 = if abs {
-                    Vec2::new(x1, y1)
-                } else {
-                    Vec2::new(cur.x + x1, cur.y + y1)
-                };
-                let p2 = if abs {
-                    Vec2::new(x2, y2)
-                } else {
-                    Vec2::new(cur.x + x2, cur.y + y2)
-                };
-                let p3 = if abs {
-                    Vec2::new(x, y)
-                } else {
-                    Vec2::new(cur.x + x, cur.y + y)
-                };
-                let b = Bezier2D {
-                    p0: flip_y(tx.apply_point(cur), svg_height),
-                    p1: flip_y(tx.apply_point(p1), svg_height),
-                    p2: flip_y(tx.apply_point(p2), svg_height),
-                    p3: flip_y(tx.apply_point(p3), svg_height),
-                };
-                entities.push(Entity2D {
-                    id: alloc_id(next_id),
-                    kind: EntityKind::Unknown,
-                    primitive: Primitive2D::CubicBezier(b),
-                    style: style.clone(),
-                });
-                cur = p3;
-            }
-            ClosePath { .. } => {
-                let a = flip_y(tx.apply_point(cur), svg_height);
-                let b = flip_y(tx.apply_point(start), svg_height);
-                entities.push(Entity2D {
-                    id: alloc_id(next_id),
-                    kind: EntityKind::Unknown,
-                    primitive: Primitive2D::Line(LineSeg2D { a, b }),
-                    style: style.clone(),
-                });
-                cur = start;
-            }
-            _ => {
-                // Deterministic v0: ignore quadratic/arc commands.
-            }
+/// `c` mirrored through `about`.
+fn reflect(c: Vec2, about: Vec2) -> Vec2 {
+    about * 2.0 - c
+}
+
+/// Inner control points of the cubic equal to the quadratic `p0 q p`.
+fn quad_to_cubic(p0: Vec2, q: Vec2, p: Vec2) -> (Vec2, Vec2) {
+    (p0.lerp(q, 2.0 / 3.0), p.lerp(q, 2.0 / 3.0))
+}
+
+/// An `A` command in endpoint form, in user coordinates.
+struct SvgArc {
+    from: Vec2,
+    to: Vec2,
+    rx: f64,
+    ry: f64,
+    rotation_deg: f64,
+    large_arc: bool,
+    sweep: bool,
+}
+
+/// The ellipse an [`SvgArc`] lies on, with the swept parameter range.
+struct ArcEllipse {
+    center: Vec2,
+    rx: f64,
+    ry: f64,
+    cos_phi: f64,
+    sin_phi: f64,
+    theta0: f64,
+    delta: f64,
+}
+
+impl SvgArc {
+    /// Endpoint to center parameterization, per SVG 1.1 appendix F.6.5,
+    /// with radii scaled up as F.6.6 requires. `None` when the arc is a
+    /// straight line or nothing at all.
+    fn center_form(&self) -> Option<ArcEllipse> {
+        if self.from == self.to || self.rx == 0.0 || self.ry == 0.0 {
+            return None;
+        }
+        let (sin_phi, cos_phi) = self.rotation_deg.to_radians().sin_cos();
+        let hx = (self.from.x - self.to.x) / 2.0;
+        let hy = (self.from.y - self.to.y) / 2.0;
+        let x1 = cos_phi * hx + sin_phi * hy;
+        let y1 = -sin_phi * hx + cos_phi * hy;
+
+        let (mut rx, mut ry) = (self.rx, self.ry);
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+        let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut coef = (num / den).max(0.0).sqrt();
+        if self.large_arc == self.sweep {
+            coef = -coef;
+        }
+        let cx1 = coef * rx * y1 / ry;
+        let cy1 = -coef * ry * x1 / rx;
+        let center = Vec2::new(
+            cos_phi * cx1 - sin_phi * cy1 + (self.from.x + self.to.x) / 2.0,
+            sin_phi * cx1 + cos_phi * cy1 + (self.from.y + self.to.y) / 2.0,
+        );
+
+        let theta0 = ((y1 - cy1) / ry).atan2((x1 - cx1) / rx);
+        let theta1 = ((-y1 - cy1) / ry).atan2((-x1 - cx1) / rx);
+        let mut delta = (theta1 - theta0).rem_euclid(std::f64::consts::TAU);
+        if !self.sweep && delta > 0.0 {
+            delta -= std::f64::consts::TAU;
         }
+        Some(ArcEllipse {
+            center,
+            rx,
+            ry,
+            cos_phi,
+            sin_phi,
+            theta0,
+            delta,
+        })
+    }
+}
+
+impl ArcEllipse {
+    /// Offset from the center along the ellipse's axes.
+    fn axis_offset(&self, u: f64, v: f64) -> Vec2 {
+        Vec2::new(
+            self.cos_phi * u - self.sin_phi * v,
+            self.sin_phi * u + self.cos_phi * v,
+        )
     }
 }
 
@@ -376,6 +1061,23 @@ impl Transform2D {
         }
     }
 
+    fn translate(x: f64, y: f64) -> Self {
+        Self {
+            e: x,
+            f: y,
+            ..Self::identity()
+        }
+    }
+
+    /// Factor lengths scale by, on average over directions.
+    fn scale(self) -> f64 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+
+    fn apply_vector(self, v: Vec2) -> Vec2 {
+        Vec2::new(self.a * v.x + self.c * v.y, self.b * v.x + self.d * v.y)
+    }
+
     fn apply_point(self, p: Vec2) -> Vec2 {
         Vec2::new(
             self.a * p.x + self.c * p.y + self.e,
@@ -0,0 +1,47 @@
+//! Helpers shared by the SVG import tests. Elements are looked up by their
+//! id, which the importer keeps as the handle of what it draws for them.
+#![allow(dead_code)]
+
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{Drawing2D, Entity2D, Primitive2D, TextEntity};
+use cadconvert_import_svg::import_svg;
+use std::path::PathBuf;
+
+pub fn load(name: &str) -> Drawing2D {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../fixtures/svg")
+        .join(name);
+    import_svg(&path).unwrap()
+}
+
+/// Primitives drawn for the element with this id, in drawing order.
+pub fn element<'a>(drawing: &'a Drawing2D, id: &str) -> Vec<&'a Primitive2D> {
+    drawing
+        .entities
+        .iter()
+        .filter(|e| e.source.handle.as_deref() == Some(id))
+        .map(|e| &e.primitive)
+        .collect()
+}
+
+/// The first entity drawn for the element with this id.
+pub fn entity<'a>(drawing: &'a Drawing2D, id: &str) -> &'a Entity2D {
+    drawing
+        .entities
+        .iter()
+        .find(|e| e.source.handle.as_deref() == Some(id))
+        .unwrap_or_else(|| panic!("no entity for {id}"))
+}
+
+/// The text drawn for the `<text>` with this id.
+pub fn text<'a>(drawing: &'a Drawing2D, id: &str) -> &'a TextEntity {
+    drawing
+        .texts
+        .iter()
+        .find(|t| t.source.handle.as_deref() == Some(id))
+        .unwrap_or_else(|| panic!("no text for {id}"))
+}
+
+pub fn close(a: Vec2, b: Vec2) -> bool {
+    (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+}
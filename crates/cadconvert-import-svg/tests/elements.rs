@@ -0,0 +1,149 @@
+mod common;
+
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::Primitive2D;
+use common::{close, element, load};
+
+fn assert_polygon(p: &Primitive2D, corners: &[(f64, f64)]) {
+    let Primitive2D::Polyline(poly) = p else {
+        panic!("expected a polyline, got {p:?}");
+    };
+    assert!(poly.closed);
+    let points: Vec<_> = poly.vertices.iter().map(|v| v.pos).collect();
+    assert_eq!(corners.len(), points.len(), "{points:?}");
+    for (&(x, y), &p) in corners.iter().zip(&points) {
+        assert!(close(p, Vec2::new(x, y)), "{points:?}");
+    }
+}
+
+fn assert_line(p: &Primitive2D, a: (f64, f64), b: (f64, f64)) {
+    let Primitive2D::Line(seg) = p else {
+        panic!("expected a line, got {p:?}");
+    };
+    assert!(close(seg.a, Vec2::new(a.0, a.1)), "{seg:?}");
+    assert!(close(seg.b, Vec2::new(b.0, b.1)), "{seg:?}");
+}
+
+fn count(prims: &[&Primitive2D], f: fn(&Primitive2D) -> bool) -> usize {
+    prims.iter().filter(|p| f(p)).count()
+}
+
+#[test]
+fn rects_and_ellipses() {
+    let drawing = load("shapes.svg");
+
+    let plain = element(&drawing, "plain");
+    assert_polygon(
+        plain[0],
+        &[(10.0, 90.0), (30.0, 90.0), (30.0, 80.0), (10.0, 80.0)],
+    );
+    let percent = element(&drawing, "percent");
+    assert_polygon(
+        percent[0],
+        &[(50.0, 10.0), (60.0, 10.0), (60.0, 5.0), (50.0, 5.0)],
+    );
+
+    let rounded = element(&drawing, "rounded");
+    assert_eq!(4, count(&rounded, |p| matches!(p, Primitive2D::Line(_))));
+    assert_eq!(4, count(&rounded, |p| matches!(p, Primitive2D::Arc(_))));
+    let Primitive2D::Arc(top_right) = rounded[1] else {
+        panic!("{rounded:?}");
+    };
+    assert!(
+        close(top_right.center, Vec2::new(65.0, 85.0)),
+        "{top_right:?}"
+    );
+    assert!((top_right.radius - 5.0).abs() < 1e-9);
+    assert!(
+        top_right.start_angle_deg.abs() < 1e-9 && (top_right.end_angle_deg - 90.0).abs() < 1e-9
+    );
+
+    // Elliptical corners with no straight edges left between them.
+    let pill = element(&drawing, "pill");
+    assert_eq!(4, count(&pill, |p| matches!(p, Primitive2D::Ellipse(_))));
+    assert_eq!(4, pill.len());
+
+    let [Primitive2D::Ellipse(ellipse)] = element(&drawing, "ellipse")[..] else {
+        panic!("expected an ellipse");
+    };
+    assert!(ellipse.is_closed());
+    assert!(close(ellipse.center, Vec2::new(70.0, 50.0)), "{ellipse:?}");
+    assert!(
+        close(ellipse.start_point(), Vec2::new(85.0, 50.0)),
+        "{ellipse:?}"
+    );
+    // Counter-clockwise in the drawing, though the y-down source runs the
+    // other way.
+    assert!(
+        close(ellipse.point_at(90.0), Vec2::new(70.0, 55.0)),
+        "{ellipse:?}"
+    );
+    assert!((ellipse.ratio - 1.0 / 3.0).abs() < 1e-9);
+
+    let [Primitive2D::Circle(round)] = element(&drawing, "round-ellipse")[..] else {
+        panic!("expected a circle");
+    };
+    assert!(close(round.center, Vec2::new(70.0, 20.0)) && (round.radius - 5.0).abs() < 1e-9);
+    let [Primitive2D::Circle(scaled)] = element(&drawing, "scaled-circle")[..] else {
+        panic!("expected a circle");
+    };
+    assert!(close(scaled.center, Vec2::new(20.0, 10.0)) && (scaled.radius - 6.0).abs() < 1e-9);
+
+    assert_line(
+        element(&drawing, "percent-line")[0],
+        (10.0, 50.0),
+        (20.0, 100.0),
+    );
+}
+
+#[test]
+fn use_draws_its_target_in_place() {
+    let drawing = load("use_symbol.svg");
+
+    // Nothing in <defs> is drawn unless used; cycles and missing targets are
+    // skipped.
+    let mut ticks = element(&drawing, "tick");
+    assert_eq!(4, ticks.len());
+    ticks.sort_by(|a, b| format!("{a:?}").cmp(&format!("{b:?}")));
+    assert_line(ticks[0], (10.0, 68.0), (15.0, 68.0));
+    assert_line(ticks[1], (10.0, 70.0), (15.0, 70.0));
+    assert_line(ticks[2], (10.0, 90.0), (15.0, 90.0));
+    assert_line(ticks[3], (30.0, 90.0), (35.0, 90.0));
+
+    // The symbol's 10 unit view box is fitted into the 20 unit use.
+    let boxed = element(&drawing, "box-rect");
+    assert_eq!(1, boxed.len());
+    assert_polygon(
+        boxed[0],
+        &[(50.0, 50.0), (70.0, 50.0), (70.0, 30.0), (50.0, 30.0)],
+    );
+    assert_eq!(5, drawing.entities.len());
+}
+
+#[test]
+fn nested_svg_viewports() {
+    let drawing = load("nested.svg");
+    assert_line(element(&drawing, "centered")[0], (20.0, 90.0), (40.0, 70.0));
+    assert_line(
+        element(&drawing, "stretched")[0],
+        (60.0, 40.0),
+        (80.0, 20.0),
+    );
+    let half = element(&drawing, "half");
+    assert_polygon(
+        half[0],
+        &[(0.0, 50.0), (25.0, 50.0), (25.0, 40.0), (0.0, 40.0)],
+    );
+}
+
+#[test]
+fn hidden_elements_are_skipped() {
+    let drawing = load("hidden.svg");
+    let mut ids: Vec<_> = drawing
+        .entities
+        .iter()
+        .filter_map(|e| e.source.handle.as_deref())
+        .collect();
+    ids.sort();
+    assert_eq!(vec!["shown-1", "shown-2"], ids);
+}
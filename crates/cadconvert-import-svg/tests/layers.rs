@@ -0,0 +1,43 @@
+mod common;
+
+use cadconvert_core::model::{Drawing2D, EntityKind};
+use cadconvert_core::normalize::{normalize_in_place, NormalizeConfig};
+use common::load;
+
+/// Layer and kind of each entity, in document order.
+fn layers(drawing: &Drawing2D) -> Vec<(Option<&str>, EntityKind)> {
+    drawing
+        .entities
+        .iter()
+        .map(|e| (e.style.layer.as_deref(), e.kind.clone()))
+        .collect()
+}
+
+#[test]
+fn layer_groups_name_their_content() {
+    let drawing = load("layers.svg");
+    assert_eq!(
+        vec![
+            (Some("Front view"), EntityKind::Unknown),
+            (Some("Hidden"), EntityKind::Unknown),
+            (Some("Front view"), EntityKind::Unknown),
+            (Some("layer3"), EntityKind::Unknown),
+            (Some("Layer_CENTER"), EntityKind::Unknown),
+            (Some("Layer_CENTER"), EntityKind::Hidden),
+            (Some("Top view"), EntityKind::Unknown),
+            // An element id is its handle, not a layer.
+            (None, EntityKind::Object),
+        ],
+        layers(&drawing)
+    );
+}
+
+#[test]
+fn layer_names_classify_kinds_when_normalized() {
+    let mut drawing = load("layers.svg");
+    let stats = normalize_in_place(&mut drawing, &NormalizeConfig::default());
+    assert_eq!(2, stats.inferred_kinds);
+    let kinds: Vec<_> = layers(&drawing).into_iter().map(|(_, k)| k).collect();
+    assert_eq!(EntityKind::Hidden, kinds[1]);
+    assert_eq!(EntityKind::Center, kinds[4]);
+}
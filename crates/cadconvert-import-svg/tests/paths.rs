@@ -0,0 +1,171 @@
+mod common;
+
+use cadconvert_core::geom::Vec2;
+use cadconvert_core::model::{Arc2D, Bezier2D, Drawing2D, LineSeg2D, Primitive2D};
+use common::{close, element, load};
+
+fn assert_same_paths(drawing: &Drawing2D, a: &str, b: &str) {
+    let (a, b) = (element(drawing, a), element(drawing, b));
+    assert!(!a.is_empty());
+    assert_eq!(a.len(), b.len());
+    for (a, b) in a.iter().zip(&b) {
+        let same = match (a, b) {
+            (Primitive2D::Line(a), Primitive2D::Line(b)) => close(a.a, b.a) && close(a.b, b.b),
+            (Primitive2D::CubicBezier(a), Primitive2D::CubicBezier(b)) => {
+                close(a.p0, b.p0) && close(a.p1, b.p1) && close(a.p2, b.p2) && close(a.p3, b.p3)
+            }
+            (Primitive2D::Arc(a), Primitive2D::Arc(b)) => {
+                close(a.center, b.center)
+                    && (a.radius - b.radius).abs() < 1e-9
+                    && (a.start_angle_deg - b.start_angle_deg).abs() < 1e-9
+                    && (a.end_angle_deg - b.end_angle_deg).abs() < 1e-9
+            }
+            _ => false,
+        };
+        assert!(same, "{a:?} != {b:?}");
+    }
+}
+
+fn bezier(p: &Primitive2D) -> &Bezier2D {
+    match p {
+        Primitive2D::CubicBezier(b) => b,
+        other => panic!("expected a Bézier, got {other:?}"),
+    }
+}
+
+fn arc(p: &Primitive2D) -> &Arc2D {
+    match p {
+        Primitive2D::Arc(a) => a,
+        other => panic!("expected an arc, got {other:?}"),
+    }
+}
+
+fn assert_arc(a: &Arc2D, center: Vec2, radius: f64, start: f64, end: f64) {
+    assert!(close(a.center, center), "{a:?}");
+    assert!((a.radius - radius).abs() < 1e-9, "{a:?}");
+    let angle = |x: f64, y: f64| ((x - y + 180.0).rem_euclid(360.0) - 180.0).abs() < 1e-9;
+    assert!(angle(a.start_angle_deg, start), "{a:?}");
+    assert!(angle(a.end_angle_deg, end), "{a:?}");
+}
+
+#[test]
+fn lines_and_axis_aligned_lines() {
+    let drawing = load("path_lines.svg");
+    assert_same_paths(&drawing, "abs", "rel");
+    let lines: Vec<_> = element(&drawing, "abs")
+        .into_iter()
+        .map(|p| match p {
+            Primitive2D::Line(LineSeg2D { a, b }) => (*a, *b),
+            other => panic!("expected a line, got {other:?}"),
+        })
+        .collect();
+    // Y is flipped against the 100 high view box.
+    assert_eq!(
+        vec![
+            (Vec2::new(10.0, 90.0), Vec2::new(40.0, 90.0)),
+            (Vec2::new(40.0, 90.0), Vec2::new(40.0, 70.0)),
+            (Vec2::new(40.0, 70.0), Vec2::new(10.0, 70.0)),
+            (Vec2::new(10.0, 70.0), Vec2::new(10.0, 90.0)),
+        ],
+        lines
+    );
+}
+
+#[test]
+fn cubic_and_quadratic_curves() {
+    let drawing = load("path_curves.svg");
+    assert_same_paths(&drawing, "cubic-abs", "cubic-rel");
+    assert_same_paths(&drawing, "quad-abs", "quad-rel");
+
+    let cubic = element(&drawing, "cubic-abs");
+    assert_eq!(2, cubic.len());
+    let s = bezier(cubic[1]);
+    assert!(close(s.p0, Vec2::new(20.0, 50.0)));
+    assert!(close(s.p1, Vec2::new(20.0, 40.0)), "{s:?}");
+
+    // Q 15 70 20 80 is the cubic with controls two thirds of the way to
+    // (15, 70); T reflects that control to (25, 90).
+    let quad = element(&drawing, "quad-abs");
+    assert_eq!(2, quad.len());
+    let q = bezier(quad[0]);
+    assert!(
+        close(q.p1, Vec2::new(10.0 + 10.0 / 3.0, 20.0 + 20.0 / 3.0)),
+        "{q:?}"
+    );
+    assert!(
+        close(q.p2, Vec2::new(20.0 - 10.0 / 3.0, 20.0 + 20.0 / 3.0)),
+        "{q:?}"
+    );
+    let t = bezier(quad[1]);
+    assert!(
+        close(t.p1, Vec2::new(20.0 + 10.0 / 3.0, 20.0 - 20.0 / 3.0)),
+        "{t:?}"
+    );
+    assert!(close(t.p3, Vec2::new(30.0, 20.0)));
+
+    let smooth = element(&drawing, "smooth-first");
+    assert_eq!(2, smooth.len());
+    assert!(close(bezier(smooth[0]).p1, Vec2::new(60.0, 50.0)));
+    // A quadratic with its control on the start point is a straight line.
+    let t = bezier(smooth[1]);
+    assert!(close(t.p1, t.p0), "{t:?}");
+    assert!(close(t.p2, Vec2::new(80.0 - 40.0 / 3.0, 20.0)), "{t:?}");
+}
+
+#[test]
+fn circular_arcs_become_arcs() {
+    let drawing = load("path_arcs.svg");
+    assert_same_paths(&drawing, "arc-abs", "arc-rel");
+
+    let half = element(&drawing, "arc-abs");
+    assert_eq!(1, half.len());
+    assert_arc(arc(half[0]), Vec2::new(20.0, 50.0), 10.0, 0.0, 180.0);
+
+    let large = arc(element(&drawing, "large")[0]);
+    assert!((large.radius - 10.0).abs() < 1e-9);
+    let sweep = (large.end_angle_deg - large.start_angle_deg).rem_euclid(360.0);
+    assert!((sweep - 270.0).abs() < 1e-9, "{large:?}");
+
+    let scaled = element(&drawing, "scaled-up");
+    assert_arc(arc(scaled[0]), Vec2::new(20.0, 10.0), 10.0, 180.0, 0.0);
+
+    let stretched = element(&drawing, "stretched");
+    assert_arc(arc(stretched[0]), Vec2::new(90.0, 30.0), 10.0, 0.0, 180.0);
+
+    let flat = element(&drawing, "flat");
+    assert!(matches!(flat[..], [Primitive2D::Line(_)]), "{flat:?}");
+}
+
+#[test]
+fn elliptical_arcs_stay_elliptical() {
+    let drawing = load("path_arcs.svg");
+
+    // A half ellipse over its top, counter-clockwise from its right end.
+    let [Primitive2D::Ellipse(half)] = element(&drawing, "ellipse")[..] else {
+        panic!("expected an ellipse");
+    };
+    assert!(close(half.center, Vec2::new(60.0, 10.0)), "{half:?}");
+    assert!((half.ratio - 0.5).abs() < 1e-9);
+    assert!((half.sweep_deg() - 180.0).abs() < 1e-9, "{half:?}");
+    assert!(close(half.start_point(), Vec2::new(80.0, 10.0)), "{half:?}");
+    assert!(close(half.end_point(), Vec2::new(40.0, 10.0)), "{half:?}");
+    assert!((half.bbox().max.y - 20.0).abs() < 1e-9);
+
+    // A circular arc squashed by its transform.
+    let [Primitive2D::Ellipse(squashed)] = element(&drawing, "squashed")[..] else {
+        panic!("expected an ellipse");
+    };
+    assert!(
+        close(squashed.center, Vec2::new(20.0, 90.0)),
+        "{squashed:?}"
+    );
+    assert!(
+        close(squashed.major_axis, Vec2::new(10.0, 0.0)),
+        "{squashed:?}"
+    );
+    assert!(
+        close(squashed.point_at(90.0), Vec2::new(20.0, 95.0)),
+        "{squashed:?}"
+    );
+    assert!((squashed.sweep_deg() - 180.0).abs() < 1e-9);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use cadconvert_core::model::EntityKind;
+use common::{entity, load};
+
+#[test]
+fn dash_patterns_classify_linetypes() {
+    let drawing = load("styles.svg");
+    for (id, kind, linetype) in [
+        ("object", EntityKind::Object, None),
+        ("hidden", EntityKind::Hidden, Some("HIDDEN")),
+        ("center", EntityKind::Center, Some("CENTER")),
+        ("phantom", EntityKind::Phantom, Some("PHANTOM")),
+        ("inline", EntityKind::Object, None),
+        ("repeated", EntityKind::Hidden, Some("HIDDEN")),
+        ("dotted", EntityKind::Unknown, Some("0.5 3")),
+        ("inherited", EntityKind::Center, Some("CENTER")),
+        ("unstroked", EntityKind::Hidden, Some("HIDDEN")),
+        ("reset", EntityKind::Hidden, Some("HIDDEN")),
+    ] {
+        let e = entity(&drawing, id);
+        assert_eq!(kind, e.kind, "{id}");
+        assert_eq!(linetype, e.style.linetype.as_deref(), "{id}");
+    }
+}
+
+#[test]
+fn stroke_width_becomes_lineweight() {
+    let drawing = load("styles.svg");
+    for (id, weight) in [
+        ("object", Some(0.5)),
+        ("center", Some(0.25)),
+        ("inline", Some(1.0)),
+        // Scaled with the element.
+        ("inherited", Some(4.0)),
+        ("unstroked", None),
+    ] {
+        assert_eq!(weight, entity(&drawing, id).style.lineweight, "{id}");
+    }
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use cadconvert_core::model::{TextAlign, TextEntity};
+use common::{load, text};
+
+fn assert_text(t: &TextEntity, value: &str, at: (f64, f64), height: f64) {
+    assert_eq!(value, t.text);
+    assert!(
+        (t.at.x - at.0).abs() < 1e-9 && (t.at.y - at.1).abs() < 1e-9,
+        "{t:?}"
+    );
+    assert!((t.height.unwrap() - height).abs() < 1e-9, "{t:?}");
+}
+
+#[test]
+fn tspans_join_into_lines() {
+    let drawing = load("text.svg");
+    assert_eq!(5, drawing.texts.len());
+
+    let plain = text(&drawing, "plain");
+    assert_text(plain, "PLAIN", (10.0, 10.0), 4.0);
+    assert_eq!(TextAlign::Left, plain.align);
+    // Not -0 from flipping the Y axis.
+    assert!(plain.rotation_deg == 0.0 && plain.rotation_deg.is_sign_positive());
+
+    assert_text(
+        text(&drawing, "lines"),
+        "Ø12 H7\n2 HOLES",
+        (10.0, 20.0),
+        5.0,
+    );
+    // Shifted but not positioned spans stay on the line; hidden ones are left
+    // out.
+    assert_text(text(&drawing, "tolerance"), "10+0.1", (50.0, 20.0), 4.0);
+}
+
+#[test]
+fn anchors_and_transforms_place_text() {
+    let drawing = load("text.svg");
+
+    // Rotated a quarter turn clockwise on screen is counter-clockwise in CAD.
+    let rotated = text(&drawing, "rotated");
+    assert_text(rotated, "25.4", (90.0, 50.0), 6.0);
+    assert!((rotated.rotation_deg - 90.0).abs() < 1e-9, "{rotated:?}");
+    assert_eq!(TextAlign::Center, rotated.align);
+
+    let scaled = text(&drawing, "scaled");
+    assert_text(scaled, "SPACED OUT", (10.0, 60.0), 8.0);
+    assert_eq!(TextAlign::Right, scaled.align);
+    assert_eq!(0.0, scaled.rotation_deg);
+}